@@ -14,7 +14,7 @@ use std::{env, io::Write, str::FromStr};
 use subwasmlib::*;
 use text_style::{AnsiColor, StyledStr};
 use utils::*;
-use wasm_loader::{NodeEndpoint, Source as WasmLoaderSource};
+use wasm_loader::{NodeEndpoint, OnchainBlock, Source as WasmLoaderSource};
 
 /// Main entry point of the `subwasm` cli
 fn main() -> color_eyre::Result<()> {
@@ -23,6 +23,7 @@ fn main() -> color_eyre::Result<()> {
 
 	let opts: Opts = Opts::parse();
 	// debug!("opts: {opts:#?}");
+	let config = opts.testbed_config();
 
 	match opts.subcmd {
 		Some(SubCommand::Get(get_opts)) => {
@@ -44,15 +45,25 @@ fn main() -> color_eyre::Result<()> {
 			};
 			debug!("rpc_url: {rpc_url:?}");
 
+			let integrity = get_opts.integrity.integrity();
 			match (download_url, rpc_url) {
 				(None, Some(rpc_url)) => {
-					let _file =
+					if get_opts.integrity.requires_url() {
+						Err(SubwasmLibError::Generic(
+							"Published checksums and signatures can only be checked for a runtime downloaded from a url".into(),
+						))?
+					}
+					let file =
 						download_runtime(NodeEndpoint::from_str(rpc_url.as_str())?, get_opts.block, get_opts.output)?;
+					if let Err(e) = integrity.check(&std::fs::read(&file)?) {
+						let _ = std::fs::remove_file(&file);
+						Err(e)?
+					}
 					Ok(())
 				}
 				(Some(url), _) => {
 					let target = get_output_file_local(get_opts.output);
-					let output = fetch_at_url(url, Some(target))?;
+					let output = fetch_verified(url, Some(target), &integrity)?;
 					debug!("Fetched at {output:?}");
 					if output.exists() {
 						info!("Got runtime at {output:?}");
@@ -67,16 +78,56 @@ fn main() -> color_eyre::Result<()> {
 			}
 		}
 
-		Some(SubCommand::Info(info_opts)) => {
+		Some(SubCommand::Info(info_cmd_opts)) => {
+			let info_opts = info_cmd_opts.source;
+			let wasm = info_opts.file.as_ref().map(|file| file.display().to_string());
 			let gh_url =
 				if let Some(u) = info_opts.github { Some(GithubRef::from_str(u.as_str())?.as_url()) } else { None };
 			let download_url = select_url(gh_url, info_opts.url);
-			let source = get_source(info_opts.file, info_opts.chain, info_opts.block, download_url)?;
+			if download_url.is_none() && info_cmd_opts.integrity.requires_url() {
+				Err(SubwasmLibError::Generic(
+					"Published checksums and signatures can only be checked for a runtime downloaded from a url".into(),
+				))?
+			}
+			let integrity = info_cmd_opts.integrity.integrity();
+			let source =
+				get_verified_source(info_opts.file, info_opts.chain, info_opts.block, download_url, &integrity)?;
 
 			info!("⏱️  Loading WASM from {:?}", &source);
-			let subwasm = Subwasm::new(&source.try_into()?)?;
-
-			Ok(subwasm.runtime_info().print(opts.json)?)
+			let subwasm = Subwasm::with_config(&source.try_into()?, &config)?;
+			subwasm.check_integrity(&integrity)?;
+
+			let current = info_cmd_opts
+				.current
+				.map(|current| Subwasm::with_config(&WasmLoaderSource::File(current.as_file()?), &config))
+				.transpose()?;
+			let runtime_info = match &current {
+				Some(current) => &subwasm.runtime_info_for(current)?,
+				None => subwasm.runtime_info()?,
+			};
+			match (info_cmd_opts.hashes, info_cmd_opts.format) {
+				(Some(kinds), _) => {
+					let kinds = if kinds.is_empty() { HashKind::ALL.to_vec() } else { kinds };
+					let hashes = runtime_info.hashes().filter(&kinds);
+					if opts.json {
+						println!("{}", serde_json::to_string_pretty(&hashes)?);
+					} else {
+						print!("{hashes}");
+					}
+				}
+				(None, Some(InfoFormat::Srtool)) => {
+					println!("{}", serde_json::to_string_pretty(&subwasm.srtool_digest(runtime_info, wasm)?)?)
+				}
+				(None, format) => runtime_info.print(format.map_or(opts.json, |format| format == InfoFormat::Json))?,
+			}
+			subwasm.print_runtime_logs();
+			if let Some(denied) = info_cmd_opts.deny_features {
+				runtime_info.deny_features(&denied)?;
+			}
+			if let Some(target) = info_cmd_opts.wasm_target {
+				runtime_info.check_wasm_target(target)?;
+			}
+			Ok(())
 		}
 
 		Some(SubCommand::Version(info_opts)) => {
@@ -86,9 +137,11 @@ fn main() -> color_eyre::Result<()> {
 			let source = get_source(info_opts.file, info_opts.chain, info_opts.block, download_url)?;
 
 			info!("⏱️  Loading WASM from {:?}", &source);
-			let subwasm: Subwasm = Subwasm::new(&source.try_into()?)?;
+			let subwasm: Subwasm = Subwasm::with_config(&source.try_into()?, &config)?;
 
-			Ok(subwasm.runtime_info().print_version(opts.json)?)
+			subwasm.print_version(opts.json)?;
+			subwasm.print_runtime_logs();
+			Ok(())
 		}
 
 		Some(SubCommand::Metadata(meta_opts)) => {
@@ -98,7 +151,7 @@ fn main() -> color_eyre::Result<()> {
 			let source = get_source(meta_opts.file, meta_opts.chain, meta_opts.block, download_url)?;
 
 			info!("⏱️  Loading WASM from {:?}", &source);
-			let subwasm: Subwasm = Subwasm::new(&source.try_into()?)?;
+			let subwasm: Subwasm = Subwasm::with_config(&source.try_into()?, &config)?;
 
 			let mut fmt: OutputFormat = meta_opts.format.unwrap_or_else(|| "human".into()).into();
 			if opts.json {
@@ -125,6 +178,7 @@ fn main() -> color_eyre::Result<()> {
 			};
 
 			subwasm.write_metadata(fmt, meta_opts.module, &mut out)?;
+			subwasm.print_runtime_logs();
 
 			// TODO: Remove that when deprecating the --json flag but beware, --json is a global flag used by other commands...
 			if opts.json {
@@ -146,7 +200,7 @@ fn main() -> color_eyre::Result<()> {
 			let src_a = WasmLoaderSource::File(runtime_1);
 			let src_b = WasmLoaderSource::File(runtime_2);
 
-			let diff_result = reduced_diff(src_a, src_b).expect("Reduced diff failed");
+			let diff_result = reduced_diff(src_a, src_b, &config).expect("Reduced diff failed");
 			if opts.json {
 				let s = serde_json::to_string_pretty(&diff_result).expect("serde_json ran into issues");
 				println!("{s}");
@@ -169,6 +223,329 @@ fn main() -> color_eyre::Result<()> {
 			}
 		}
 
+		Some(SubCommand::Cmp(cmp_opts)) => {
+			let src_a = WasmLoaderSource::File(cmp_opts.runtime_1.as_file()?);
+			let src_b = WasmLoaderSource::File(cmp_opts.runtime_2.as_file()?);
+
+			let comparison = compare_runtimes(&src_a, &src_b)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&comparison)?);
+			} else {
+				print!("{comparison}");
+			}
+
+			if let WasmComparison::Different { sections, .. } = comparison {
+				Err(SubwasmLibError::RuntimesDiffer(sections.join(", ")))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Hostfns(hostfns_opts)) => {
+			let gh_url =
+				if let Some(u) = hostfns_opts.github { Some(GithubRef::from_str(u.as_str())?.as_url()) } else { None };
+			let download_url = select_url(gh_url, hostfns_opts.url);
+			let source = get_source(hostfns_opts.file, hostfns_opts.chain, hostfns_opts.block, download_url)?;
+			let source: WasmLoaderSource = source.try_into()?;
+
+			let Some(against) = hostfns_opts.against else {
+				let functions = host_functions(&source)?;
+				if opts.json {
+					println!("{}", serde_json::to_string_pretty(&functions)?);
+				} else {
+					functions.iter().for_each(|f| println!("{f}"));
+				}
+				return Ok(());
+			};
+
+			let check = check_host_functions(&source, &against)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&check)?);
+			} else if opts.quiet {
+				print!("{check:#}");
+			} else {
+				print!("{check}");
+			}
+			if !check.is_ok() {
+				Err(SubwasmLibError::HostFunctionsMismatch(check.failures().join(", ")))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Call(call_opts)) => {
+			let config = WasmTestBedConfig { trace: call_opts.trace, ..config };
+			let source = WasmLoaderSource::File(call_opts.runtime.as_file()?);
+			// Loading the state first so that we do not compile the runtime for nothing
+			let state =
+				call_opts.state.map(|path| StateSnapshot::load(&path)).transpose().map_err(SubwasmLibError::from)?;
+			let mut subwasm = Subwasm::with_config(&source, &config)?;
+			if let Some(state) = state {
+				info!("Loaded {} keys from a {}", state.len(), state.format);
+				subwasm = subwasm.with_state(state);
+			}
+
+			let report = subwasm.call(&call_opts.method, &call_opts.input.unwrap_or_default());
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{report}");
+			}
+			subwasm.print_runtime_logs();
+			if let Some(error) = report.error {
+				Err(SubwasmLibError::CallFailed(report.method, error))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Bench(bench_opts)) => {
+			let mut options = BenchOptions { runs: bench_opts.runs, ..Default::default() };
+			if !bench_opts.methods.is_empty() {
+				options.methods = bench_opts.methods;
+			}
+			let reference =
+				BenchReport::new(&WasmLoaderSource::File(bench_opts.runtime.as_file()?), &config, &options)?;
+
+			if let Some(other) = bench_opts.compare {
+				let other = BenchReport::new(&WasmLoaderSource::File(other.as_file()?), &config, &options)?;
+				let comparison = BenchComparison { reference, other };
+				if opts.json {
+					println!("{}", serde_json::to_string_pretty(&comparison)?);
+				} else {
+					print!("{comparison}");
+				}
+			} else if opts.json {
+				println!("{}", serde_json::to_string_pretty(&reference)?);
+			} else {
+				print!("{reference}");
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Genesis(genesis_opts)) => {
+			let subwasm = Subwasm::with_config(&WasmLoaderSource::File(genesis_opts.runtime.as_file()?), &config)?;
+			let genesis = subwasm.genesis()?;
+			let preset = genesis_opts.preset.as_deref();
+
+			if genesis_opts.list {
+				let presets = genesis.presets()?;
+				if opts.json {
+					println!("{}", serde_json::to_string_pretty(&presets)?);
+				} else {
+					presets.iter().for_each(|preset| println!("{preset}"));
+				}
+			} else if genesis_opts.raw {
+				print_big_output_safe(&serde_json::to_string_pretty(&genesis.raw_chain_spec(preset)?)?)?;
+			} else {
+				println!("{}", serde_json::to_string_pretty(&genesis.config(preset)?)?);
+			}
+			subwasm.print_runtime_logs();
+			Ok(())
+		}
+
+		Some(SubCommand::UpgradeCheck(upgrade_check_opts)) => {
+			// The logs of the migrations are part of the report
+			let config = WasmTestBedConfig { log_level: config.log_level.max(LevelFilter::Info), ..config };
+			let source = WasmLoaderSource::File(upgrade_check_opts.runtime.as_file()?);
+			let state = StateSnapshot::load(&upgrade_check_opts.state).map_err(SubwasmLibError::from)?;
+			info!("Loaded {} keys from a {}", state.len(), state.format);
+			let subwasm = Subwasm::with_config(&source, &config)?;
+
+			let report = subwasm.upgrade_check(&state, upgrade_check_opts.checks)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{report}");
+			}
+			if let Some(error) = report.error {
+				Err(SubwasmLibError::CallFailed(report.method, error))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Smoke(smoke_opts)) => {
+			let source = WasmLoaderSource::File(smoke_opts.runtime.as_file()?);
+			let state =
+				smoke_opts.state.map(|path| StateSnapshot::load(&path)).transpose().map_err(SubwasmLibError::from)?;
+			if let Some(state) = &state {
+				info!("Loaded {} keys from a {}", state.len(), state.format);
+			}
+			let subwasm = Subwasm::with_config(&source, &config)?;
+
+			let options = SmokeOptions { blocks: smoke_opts.blocks, preset: smoke_opts.preset };
+			let report = subwasm.smoke(state.as_ref(), &options)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{report}");
+			}
+			subwasm.print_runtime_logs();
+			if let Some(error) = report.error {
+				Err(SubwasmLibError::Generic(error))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Preimage(preimage_opts)) => {
+			let subwasm = Subwasm::with_config(&WasmLoaderSource::File(preimage_opts.runtime.as_file()?), &config)?;
+			let current = preimage_opts
+				.current
+				.map(|current| Subwasm::with_config(&WasmLoaderSource::File(current.as_file()?), &config))
+				.transpose()?;
+
+			let preimage = subwasm.preimage(current.as_ref(), preimage_opts.call, &preimage_opts.wrap)?;
+			if let Some(output) = &preimage_opts.output {
+				std::fs::write(output, &preimage.bytes)?;
+				info!("Wrote the encoded call to {output:?}");
+			}
+			if opts.json {
+				let mut value = serde_json::to_value(&preimage)?;
+				match &preimage_opts.output {
+					Some(output) => value["output"] = json!(output),
+					None => value["encoded"] = json!(preimage.hex()),
+				}
+				println!("{}", serde_json::to_string_pretty(&value)?);
+			} else {
+				print!("{preimage}");
+				match &preimage_opts.output {
+					Some(output) => println!("Output: {}", output.display()),
+					None => println!("Encoded call: {}", preimage.hex()),
+				}
+			}
+			subwasm.print_runtime_logs();
+			Ok(())
+		}
+
+		Some(SubCommand::XcmUpgrade(xcm_opts)) => {
+			let subwasm = Subwasm::with_config(&WasmLoaderSource::File(xcm_opts.runtime.as_file()?), &config)?;
+			let relay = Subwasm::with_config(&WasmLoaderSource::File(xcm_opts.relay.as_file()?), &config)?;
+
+			let options = XcmUpgradeOptions {
+				para_id: xcm_opts.para_id,
+				call: xcm_opts.call,
+				ref_time: xcm_opts.ref_time,
+				proof_size: xcm_opts.proof_size,
+				wrappers: xcm_opts.wrap,
+			};
+			let upgrade = subwasm.xcm_upgrade(&relay, &options)?;
+			if let Some(output) = &xcm_opts.output {
+				std::fs::write(output, &upgrade.relay_call.bytes)?;
+				info!("Wrote the encoded relay chain call to {output:?}");
+			}
+			if opts.json {
+				let mut value = serde_json::to_value(&upgrade)?;
+				match &xcm_opts.output {
+					Some(output) => value["relay_call"]["output"] = json!(output),
+					None => value["relay_call"]["encoded"] = json!(upgrade.relay_call.hex()),
+				}
+				println!("{}", serde_json::to_string_pretty(&value)?);
+			} else {
+				print!("{upgrade}");
+				match &xcm_opts.output {
+					Some(output) => println!("Output: {}", output.display()),
+					None => println!("Encoded call: {}", upgrade.relay_call.hex()),
+				}
+			}
+			subwasm.print_runtime_logs();
+			Ok(())
+		}
+
+		Some(SubCommand::Verify(verify_opts)) => {
+			let url = match (verify_opts.rpc_url, verify_opts.chain) {
+				(Some(rpc_url), _) => rpc_url.as_url()?,
+				(None, Some(chain)) => chain.get_random_url(None)?,
+				(None, None) => unreachable!("clap requires a chain or an rpc url"),
+			};
+			let reference = OnchainBlock::new(url.as_str(), verify_opts.block)?;
+
+			let subwasm = Subwasm::with_config(&WasmLoaderSource::File(verify_opts.runtime.as_file()?), &config)?;
+			let current = verify_opts
+				.current
+				.map(|current| Subwasm::with_config(&WasmLoaderSource::File(current.as_file()?), &config))
+				.transpose()?;
+
+			info!("Reading the storage from {}", reference.endpoint);
+			let report = subwasm.verify(&reference, current.as_ref())?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{report}");
+			}
+			subwasm.print_runtime_logs();
+			if !report.is_ok() {
+				Err(SubwasmLibError::Generic(format!("Runtime {} not found on chain", report.runtime)))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::VerifyDigest(digest_opts)) => {
+			let digest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&digest_opts.digest)?)?;
+			let subwasm = Subwasm::with_config(&WasmLoaderSource::File(digest_opts.runtime.as_file()?), &config)?;
+
+			let report = subwasm.verify_digest(&digest)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{report}");
+			}
+			subwasm.print_runtime_logs();
+			if !report.is_ok() {
+				Err(SubwasmLibError::Generic(format!("The runtime does not match {}", digest_opts.digest.display())))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::CanUpgrade(can_upgrade_opts)) => {
+			let current = Subwasm::with_config(&WasmLoaderSource::File(can_upgrade_opts.current.as_file()?), &config)?;
+			let new = Subwasm::with_config(&WasmLoaderSource::File(can_upgrade_opts.new.as_file()?), &config)?;
+
+			let report = new.can_upgrade_from(&current, can_upgrade_opts.max_code_size)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{report}");
+			}
+			new.print_runtime_logs();
+			if !report.is_ok() {
+				Err(SubwasmLibError::Generic(format!(
+					"The upgrade from {} to {} would be rejected",
+					report.current, report.new
+				)))?
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Bloat(bloat_opts)) => {
+			let report_1 = BloatReport::new(&WasmLoaderSource::File(bloat_opts.runtime_1.as_file()?))?;
+
+			if let Some(runtime_2) = bloat_opts.runtime_2 {
+				let report_2 = BloatReport::new(&WasmLoaderSource::File(runtime_2.as_file()?))?;
+				let diff = BloatDiff::new(&report_1, &report_2).top(bloat_opts.top);
+				if opts.json {
+					println!("{}", serde_json::to_string_pretty(&diff)?);
+				} else {
+					print!("{diff}");
+				}
+			} else {
+				let report = report_1.top(bloat_opts.top);
+				if opts.json {
+					println!("{}", serde_json::to_string_pretty(&report)?);
+				} else {
+					print!("{report}");
+				}
+			}
+			Ok(())
+		}
+
+		Some(SubCommand::Strip(sopts)) => {
+			let options = sopts.strip_options();
+			let result = strip(sopts.input, sopts.output, &options)?;
+			if opts.json {
+				println!("{}", serde_json::to_string_pretty(&result)?);
+			} else {
+				print!("{result}");
+			}
+			Ok(())
+		}
+
 		Some(SubCommand::Compress(copts)) => Ok(compress(copts.input, copts.output)?),
 
 		Some(SubCommand::Decompress(dopts)) => Ok(decompress(dopts.input, dopts.output)?),
@@ -208,17 +585,17 @@ fn main() -> color_eyre::Result<()> {
 			let source = get_source(show_opts.file, show_opts.chain, show_opts.block, download_url)?;
 
 			info!("⏱️  Loading WASM from {:?}", &source);
-			let subwasm: Subwasm = Subwasm::new(&source.try_into()?)?;
+			let subwasm: Subwasm = Subwasm::with_config(&source.try_into()?, &config)?;
 
 			if show_opts.summary {
-				Ok(subwasm.display_reduced_summary(opts.json)?)
+				subwasm.display_reduced_summary(opts.json)?;
+			} else if let Some(pallet) = show_opts.pallet {
+				subwasm.display_reduced_pallet(&pallet, opts.json)?;
 			} else {
-				if let Some(pallet) = show_opts.pallet {
-					Ok(subwasm.display_reduced_pallet(&pallet, opts.json)?)
-				} else {
-					Ok(subwasm.display_reduced_runtime(opts.json)?)
-				}
+				subwasm.display_reduced_runtime(opts.json)?;
 			}
+			subwasm.print_runtime_logs();
+			Ok(())
 		}
 	}
 }
@@ -2,7 +2,8 @@
 //! supported by the `subwasm` cli.
 
 use crate::error;
-use clap::{crate_authors, crate_version, ColorChoice, Parser, Subcommand};
+use clap::{crate_authors, crate_version, ArgAction, ColorChoice, Parser, Subcommand};
+use log::LevelFilter;
 use std::path::PathBuf;
 use subwasmlib::{source::Source, *};
 use url::Url;
@@ -20,16 +21,40 @@ pub struct Opts {
 	#[clap(short, long, global = true, display_order = 99)]
 	pub quiet: bool,
 
+	/// Show the logs emitted by the runtime during the calls: `-v` up to info, `-vv` up to debug, `-vvv` up to trace
+	#[clap(short, long, global = true, action = ArgAction::Count, display_order = 99)]
+	pub verbose: u8,
+
 	/// Do not write color information to the output. This is recommended for scripts.
 	#[clap(short, long, global = true, env = "NO_COLOR", display_order = 99)]
 	pub no_color: bool,
 
+	/// How the runtime instances are created: "pooling", "recreate", "pooling-no-cow" or "recreate-no-cow".
+	#[clap(long, global = true, env = "SUBWASM_EXEC_METHOD", default_value = "pooling", display_order = 100)]
+	pub exec_method: ExecutionMethod,
+
+	/// How the heap of the runtime is allocated: "dynamic" or "static".
+	#[clap(long, global = true, env = "SUBWASM_HEAP_STRATEGY", default_value = "dynamic", display_order = 100)]
+	pub heap_strategy: HeapStrategy,
+
+	/// With the dynamic heap strategy, the maximum number of 64KB pages the memory may grow to (0 for no limit).
+	#[clap(long, global = true, env = "SUBWASM_MAX_PAGES", default_value_t = 64, display_order = 100)]
+	pub max_pages: u32,
+
+	/// With the static heap strategy, the number of 64KB pages allocated on top of the initial memory of the runtime.
+	#[clap(long, global = true, env = "SUBWASM_EXTRA_PAGES", default_value_t = DEFAULT_EXTRA_PAGES, display_order = 100)]
+	pub extra_pages: u32,
+
+	/// Number of runtime instances kept ready for reuse
+	#[clap(long, global = true, env = "SUBWASM_INSTANCES", default_value_t = 8, display_order = 100)]
+	pub instances: usize,
+
 	#[allow(missing_docs)]
 	#[clap(subcommand)]
 	pub subcmd: Option<SubCommand>,
 
 	/// Show the version
-	#[clap(short, long, alias = "V")]
+	#[clap(short = 'V', long)]
 	pub version: bool,
 }
 
@@ -42,7 +67,7 @@ pub enum SubCommand {
 
 	#[allow(missing_docs)]
 	#[clap(version = crate_version!(), author = crate_authors!())]
-	Info(InfoOpts),
+	Info(InfoCmdOpts),
 
 	#[allow(missing_docs)]
 	#[clap(version = crate_version!(), author = crate_authors!())]
@@ -60,6 +85,62 @@ pub enum SubCommand {
 	#[clap(version = crate_version!(), author = crate_authors!())]
 	Diff(DiffOpts),
 
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Cmp(CmpOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Hostfns(HostFnsOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Call(CallOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Bench(BenchOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Genesis(GenesisOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	UpgradeCheck(UpgradeCheckOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Smoke(SmokeOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Preimage(PreimageOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	XcmUpgrade(XcmUpgradeOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Verify(VerifyOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	VerifyDigest(VerifyDigestOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	CanUpgrade(CanUpgradeOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Bloat(BloatOpts),
+
+	#[allow(missing_docs)]
+	#[clap(version = crate_version!(), author = crate_authors!())]
+	Strip(StripOpts),
+
 	#[allow(missing_docs)]
 	#[clap(version = crate_version!(), author = crate_authors!())]
 	Compress(CompressOpts),
@@ -108,6 +189,55 @@ pub struct GetOpts {
 	/// existing file as output, it will be overwritten.
 	#[clap(short, long, alias("out"), value_parser)]
 	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub integrity: IntegrityOpts,
+}
+
+/// How to check the integrity of a runtime before using it.
+///
+/// A runtime downloaded from a url is always checked against the sha256 checksums and the `<url>.asc`
+/// gpg signature published next to it, when there are some. The flags below make them required.
+#[derive(Parser, Debug)]
+pub struct IntegrityOpts {
+	/// Fail unless the runtime, as downloaded or stored, has the given hash, in the format `<algo>:<hex>`
+	/// with algo one of "blake2_256", "sha256" or "keccak_256". You may pass it several times.
+	#[clap(long, value_name = "ALGO:HEX")]
+	pub expect_hash: Vec<ExpectedHash>,
+
+	/// Require a sha256 checksum published next to the runtime downloaded from a url,
+	/// `<url>.sha256` or a line of a `SHA256SUMS` or `sha256sums.txt` file. Those are checked whenever they exist.
+	#[clap(long)]
+	pub checksums: bool,
+
+	/// The minisign public key of the release, or the path of its `.pub` file.
+	/// The runtime downloaded from a url must then come with a valid `<url>.minisig` signature.
+	#[clap(long, value_parser = parse_minisign_key)]
+	pub minisign_key: Option<MinisignKey>,
+
+	/// Require a detached signature, `<url>.asc` or `<url>.sig`, for the runtime downloaded from a url.
+	/// The signature is checked by `gpg` with your keyring, a `<url>.asc` signature is checked whenever it exists.
+	#[clap(long)]
+	pub gpg: bool,
+}
+
+impl IntegrityOpts {
+	/// The checks to run
+	pub fn integrity(&self) -> Integrity {
+		Integrity {
+			expected: self.expect_hash.clone(),
+			checksums: self.checksums,
+			minisign_key: self.minisign_key.clone(),
+			gpg: self.gpg,
+		}
+	}
+
+	/// Whether a signature or a published checksum is required, which is only possible for a runtime
+	/// downloaded from a url
+	pub fn requires_url(&self) -> bool {
+		self.checksums || self.minisign_key.is_some() || self.gpg
+	}
 }
 
 /// Shows information about a given runtime
@@ -144,6 +274,44 @@ pub struct InfoOpts {
 	// pub ipfs: Option<String>,
 }
 
+/// Shows information about a given runtime
+#[derive(Parser, Debug)]
+pub struct InfoCmdOpts {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub source: InfoOpts,
+
+	/// Fail if some optional features appear to be compiled in, such as "runtime-benchmarks" or "try-runtime".
+	/// Pass a comma separated list of features or no value at all to deny all of them.
+	#[clap(long, value_delimiter = ',', num_args = 0..)]
+	pub deny_features: Option<Vec<RuntimeFeature>>,
+
+	/// Fail if the runtime uses wasm proposals or floats that the target executor does not support.
+	/// One of "mvp", "deterministic" (the MVP without floats) or "polkadot-v1.x".
+	#[clap(long)]
+	pub wasm_target: Option<WasmTarget>,
+
+	/// The runtime the chain currently runs. The upgrade calls are executed by this runtime, so their hashes
+	/// are computed with its pallet and call indices and its signature of `authorize_upgrade`.
+	/// Defaults to the runtime being inspected.
+	#[clap(long, value_parser = parse_source)]
+	pub current: Option<Source>,
+
+	/// The output format: "human", "json" or "srtool" for a runtime of a srtool digest,
+	/// with the sha256 and the runtime info under `subwasm`. Defaults to "json" with --json, "human" otherwise.
+	#[clap(long, short = 'F')]
+	pub format: Option<InfoFormat>,
+
+	/// Only show the hashes of the runtime, compressed and decompressed. Pass a comma separated list of
+	/// "blake2_256", "sha256", "keccak_256" and "validation_code_hash" or no value at all to show all of them.
+	#[clap(long, value_delimiter = ',', num_args = 0.., conflicts_with = "format")]
+	pub hashes: Option<Vec<HashKind>>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub integrity: IntegrityOpts,
+}
+
 /// Returns the metadata of the given runtime in several format. You may also use the "meta" alias.
 ///
 /// If you want to see the content of a runtime, see the `show` sub-command.
@@ -223,6 +391,22 @@ pub struct DiffOpts {
 	pub runtime_2: Source,
 }
 
+/// Compare 2 runtime binaries, for instance to verify a reproducible build.
+///
+/// Both runtimes are decompressed and compared section by section. Custom sections that
+/// do not change the runtime, such as `name` or `producers`, are reported but ignored.
+/// The command fails if the runtimes are different.
+#[derive(Parser, Debug)]
+pub struct CmpOpts {
+	/// Reference runtime
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime_1: Source,
+
+	/// Second runtime
+	#[clap(index = 2, value_parser = parse_source)]
+	pub runtime_2: Source,
+}
+
 /// Shows the a reduced view of the runtime.
 ///
 /// A reduced view makes it much easier to understand the inner workings of a given runtime.
@@ -261,6 +445,398 @@ pub struct ShowOpts {
 	pub summary: bool,
 }
 
+/// List the host functions a runtime imports from the node, with their signatures.
+///
+/// Using `--against`, the list is checked against another set of host functions and the command
+/// fails if some are missing or have a different signature.
+#[derive(Parser, Debug)]
+pub struct HostFnsOpts {
+	/// The wasm file to load. It can be a path on your local filesystem such /tmp/runtime.wasm
+	///
+	/// You may also fetch the runtime remotely, see `chain` and `url` flags.
+	#[clap(required_unless_present_any = ["chain", "url", "github"], index = 1)]
+	pub file: Option<PathBuf>,
+
+	/// Load the wasm from an RPC node url such as http://localhost:9933 or ws://localhost:9944,
+	/// a node alias such as "polkadot" or "dot",
+	///
+	/// NOTE: --chain local = http://localhost:9933
+	#[clap(long, short, conflicts_with = "file")]
+	pub chain: Option<ChainInfo>,
+
+	/// The optional block where to fetch the runtime. That allows fetching older runtimes but you will need to connect to archive nodes.
+	/// Currently, you must pass a block hash. Passing the block numbers is not supported.
+	#[clap(short, long, requires = "chain")]
+	pub block: Option<BlockRef>,
+
+	/// Load the wasm from a URL (no node) such as <https://github.com/paritytech/polkadot/releases/download/v0.9.42/polkadot_runtime-v9420.compact.compressed.wasm>
+	#[clap(long, short, conflicts_with = "file")]
+	pub url: Option<Url>,
+
+	/// Load the wasm from Github passing a string in the format `<runtime>@<version>`
+	/// such as `kusama@0.9.42`
+	#[clap(long, short, alias = "gh", conflicts_with = "file")]
+	pub github: Option<String>,
+
+	/// The host functions to check against. It can be "sp-io" for the set built into subwasm,
+	/// a json file describing the host functions of a node release, as produced by `hostfns --json`,
+	/// or another runtime such as the previous one.
+	#[clap(long, short)]
+	pub against: Option<HostFunctionSet>,
+}
+
+/// Call a runtime API function and show its SCALE encoded output.
+///
+/// By default, the runtime runs without any storage, so only the functions that do not need
+/// the state of a chain will succeed, such as `Core_version` or `Metadata_metadata`.
+/// Pass `--state` to run the call against a snapshot of the state of a chain.
+/// With `--trace`, every call the runtime makes into a host function is recorded along
+/// with the bytes going in and out of the runtime memory and the time it took.
+#[derive(Parser, Debug)]
+pub struct CallOpts {
+	/// The runtime to call
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// The runtime API function to call, such as `Core_version`
+	#[clap(index = 2)]
+	pub method: String,
+
+	/// The SCALE encoded input of the call, as hex
+	#[clap(long, short, value_parser = parse_hex)]
+	pub input: Option<Bytes>,
+
+	/// Record the host functions called by the runtime
+	#[clap(long)]
+	pub trace: bool,
+
+	/// Run the call against this state instead of an empty storage. It can be the json output of the
+	/// `state_getPairs` rpc, a raw chain spec, a text file with one hex `key value` pair per line
+	/// or a try-runtime snapshot.
+	#[clap(long)]
+	pub state: Option<PathBuf>,
+}
+
+/// Measure how long runtime API calls take and how much memory they use.
+///
+/// The runtime is loaded once and each function is called repeatedly through the cached executor,
+/// without any input. You get the instantiation time, the percentiles of the call times, the peak
+/// number of memory pages and the allocator statistics. Pass `--compare` to get the same figures
+/// for a second runtime side by side, for instance to spot regressions before an upgrade.
+#[derive(Parser, Debug)]
+pub struct BenchOpts {
+	/// The runtime to benchmark
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// The runtime API functions to call. Defaults to `Core_version` and `Metadata_metadata`
+	#[clap(index = 2)]
+	pub methods: Vec<String>,
+
+	/// Another runtime to benchmark the same way and compare with the first one
+	#[clap(long, value_parser = parse_source)]
+	pub compare: Option<Source>,
+
+	/// Number of timed calls per function
+	#[clap(long, short, default_value_t = 10)]
+	pub runs: usize,
+}
+
+/// Show the genesis config of a runtime or build a raw chain spec from it.
+///
+/// This uses the `GenesisBuilder` runtime API, so it only works with runtimes exposing it.
+/// By default, the default genesis config is printed as json. Presets are merged on top of it.
+#[derive(Parser, Debug)]
+pub struct GenesisOpts {
+	/// The runtime to get the genesis config from
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// Name of the preset to use instead of the default genesis config
+	#[clap(long, short)]
+	pub preset: Option<String>,
+
+	/// List the names of the presets
+	#[clap(long, short, conflicts_with_all = ["preset", "raw"])]
+	pub list: bool,
+
+	/// Output a raw chain spec with the genesis storage and the runtime as `:code`
+	#[clap(long)]
+	pub raw: bool,
+}
+
+/// Dry run the upgrade to a new runtime against a snapshot of the state of a chain.
+///
+/// The new runtime replaces `:code` in the state, then its migrations run through
+/// `TryRuntime_on_runtime_upgrade` if the runtime was built with try-runtime, or through
+/// `Core_initialize_block` otherwise. You get the weight consumed, the storage keys the
+/// migrations touched, the logs of the runtime and the panic if a migration failed.
+#[derive(Parser, Debug)]
+pub struct UpgradeCheckOpts {
+	/// The new runtime
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// The state of the chain before the upgrade. It can be the json output of the `state_getPairs` rpc,
+	/// a raw chain spec, a text file with one hex `key value` pair per line or a try-runtime snapshot.
+	#[clap(long)]
+	pub state: PathBuf,
+
+	/// The checks to run around the migrations: none, all, pre-and-post or try-state.
+	/// Only used with runtimes built with try-runtime
+	#[clap(long, default_value_t = UpgradeCheckSelect::default())]
+	pub checks: UpgradeCheckSelect,
+}
+
+/// Check that a runtime can produce blocks.
+///
+/// The genesis state is built with the `GenesisBuilder` runtime API, then a few blocks are produced
+/// in memory with only the inherents: `Core_initialize_block`, `BlockBuilder_inherent_extrinsics`,
+/// `BlockBuilder_apply_extrinsic` and `BlockBuilder_finalize_block`. The events of each block are
+/// reported and the command fails if the runtime panics.
+#[derive(Parser, Debug)]
+pub struct SmokeOpts {
+	/// The runtime to produce blocks with
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// Number of blocks to produce
+	#[clap(long, short, default_value_t = 3)]
+	pub blocks: u32,
+
+	/// Name of the genesis preset to start from instead of the default genesis config
+	#[clap(long, short)]
+	pub preset: Option<String>,
+
+	/// Start from this state instead of the genesis state, for instance with runtimes
+	/// that predate the `GenesisBuilder` runtime API. It can be the json output of the `state_getPairs` rpc,
+	/// a raw chain spec, a text file with one hex `key value` pair per line or a try-runtime snapshot.
+	#[clap(long, conflicts_with = "preset")]
+	pub state: Option<PathBuf>,
+}
+
+/// Encode the call upgrading to a runtime, ready to be submitted as a governance preimage.
+///
+/// The hash and length are printed, as well as the encoded call unless it is written to a file
+/// with --output. The call can be wrapped in other calls, innermost first, such as
+/// `--wrap whitelist` for `Whitelist::dispatch_whitelisted_call_with_preimage`.
+#[derive(Parser, Debug)]
+pub struct PreimageOpts {
+	/// The runtime to upgrade to
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// The upgrade call: set_code, authorize_upgrade, authorize_upgrade_without_checks,
+	/// apply_authorized_upgrade or parachain_authorize_upgrade
+	#[clap(long, short, default_value = "set_code")]
+	pub call: UpgradeCall,
+
+	/// Wrap the call in whitelist, sudo or batch_all. Pass a comma separated list or repeat
+	/// the flag to wrap several times, innermost first. The other arguments of the wrappers are set to zero.
+	#[clap(long, short, value_delimiter = ',')]
+	pub wrap: Vec<CallWrapper>,
+
+	/// The runtime the chain currently runs, which provides the pallet and call indices.
+	/// Defaults to the runtime being upgraded to.
+	#[clap(long, value_parser = parse_source)]
+	pub current: Option<Source>,
+
+	/// Write the encoded call to this file instead of printing it as hex
+	#[clap(long, short)]
+	pub output: Option<PathBuf>,
+}
+
+/// Encode the relay chain call upgrading a parachain with XCM.
+///
+/// The parachain call is encoded with the metadata of the parachain runtime, then sent with `XcmPallet::send`
+/// in a message made of `UnpaidExecution` and `Transact` with the `Superuser` origin, in the latest XCM version
+/// of the relay chain runtime. The relay chain call is printed as hex unless it is written to a file with --output.
+#[derive(Parser, Debug)]
+pub struct XcmUpgradeOpts {
+	/// The parachain runtime to upgrade to
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// The runtime of the relay chain, which provides the XCM types and the indices of the relay chain call
+	#[clap(long, value_parser = parse_source)]
+	pub relay: Source,
+
+	/// Id of the parachain to upgrade
+	#[clap(long)]
+	pub para_id: u32,
+
+	/// The upgrade call executed by the parachain: set_code, authorize_upgrade, authorize_upgrade_without_checks,
+	/// apply_authorized_upgrade or parachain_authorize_upgrade
+	#[clap(long, short, default_value = "parachain_authorize_upgrade")]
+	pub call: UpgradeCall,
+
+	/// Maximum ref_time the parachain call can use
+	#[clap(long, default_value_t = 1_000_000_000)]
+	pub ref_time: u64,
+
+	/// Maximum proof_size the parachain call can use
+	#[clap(long, default_value_t = 100_000)]
+	pub proof_size: u64,
+
+	/// Wrap the relay chain call in whitelist, sudo or batch_all, innermost first
+	#[clap(long, short, value_delimiter = ',')]
+	pub wrap: Vec<CallWrapper>,
+
+	/// Write the encoded relay chain call to this file instead of printing it as hex
+	#[clap(long, short)]
+	pub output: Option<PathBuf>,
+}
+
+/// Check a local runtime against what is enacted or authorized on chain.
+///
+/// The hash of the runtime is compared with the hash of `:code`, `System::AuthorizedUpgrade` and
+/// `ParachainSystem::AuthorizedUpgrade`, and the preimages of the upgrade calls are looked up in
+/// `Preimage::PreimageFor`. The command fails if none of them match.
+#[derive(Parser, Debug)]
+pub struct VerifyOpts {
+	/// The runtime to verify
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// Provide the name of a chain or an alias.
+	///
+	/// --chain local = http://localhost:9933
+	#[clap(short, long, required_unless_present = "rpc_url", conflicts_with = "rpc_url")]
+	pub chain: Option<ChainInfo>,
+
+	/// The node url including (mandatory) the port number. Example: ws://localhost:9944 or http://localhost:9933
+	#[clap(long)]
+	pub rpc_url: Option<OnchainBlock>,
+
+	/// The optional block hash where to read the storage. Defaults to the best block
+	#[clap(short, long)]
+	pub block: Option<BlockRef>,
+
+	/// The runtime the chain currently runs, which provides the call indices of the preimages.
+	/// Defaults to the runtime being verified.
+	#[clap(long, value_parser = parse_source)]
+	pub current: Option<Source>,
+}
+
+/// Check a runtime against a srtool digest, field by field.
+///
+/// The digest can be the json srtool builds, whose compressed or compact runtime is picked according to the
+/// compression of the runtime, or the output of `info --format srtool`. Only the fields subwasm computes are
+/// compared. The command fails if one of them does not match.
+#[derive(Parser, Debug)]
+pub struct VerifyDigestOpts {
+	/// The runtime to verify
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime: Source,
+
+	/// The srtool digest, in json
+	#[clap(index = 2)]
+	pub digest: PathBuf,
+}
+
+/// Check whether a chain running a runtime accepts the upgrade to a new one.
+///
+/// The `spec_name` must not change and the `spec_version` must increase, as `frame_system` requires.
+/// The `transaction_version` must be bumped if the calls changed in a breaking way, and the runtime must fit
+/// in the size limits. The command fails if one of the checks fails.
+#[derive(Parser, Debug)]
+pub struct CanUpgradeOpts {
+	/// The runtime the chain currently runs
+	#[clap(index = 1, value_parser = parse_source)]
+	pub current: Source,
+
+	/// The runtime to upgrade to
+	#[clap(index = 2, value_parser = parse_source)]
+	pub new: Source,
+
+	/// The maximum size of the runtime, as stored, in bytes.
+	/// For a parachain, pass the `max_code_size` of the configuration of the relay chain.
+	#[clap(long)]
+	pub max_code_size: Option<usize>,
+}
+
+/// Break down the size of the decompressed runtime by section, crate and function.
+///
+/// Functions and crates can only be told apart if the runtime has a name section.
+/// If you pass a second runtime, you get the size difference from the first to the second one instead,
+/// top growers first.
+#[derive(Parser, Debug)]
+pub struct BloatOpts {
+	/// The runtime to analyze, or the reference runtime when comparing
+	#[clap(index = 1, value_parser = parse_source)]
+	pub runtime_1: Source,
+
+	/// The runtime to compare with the reference one
+	#[clap(index = 2, value_parser = parse_source)]
+	pub runtime_2: Option<Source>,
+
+	/// Number of crates and functions to show
+	#[clap(long, short, default_value_t = 20)]
+	pub top: usize,
+}
+
+/// Remove custom sections from a runtime wasm file and optionally change its version.
+///
+/// Without any option, or with `--all`, all custom sections are removed except `runtime_version`
+/// and `runtime_apis` which the node reads. Changing the version alone keeps all the sections.
+/// The output is compressed if the input was.
+#[derive(Parser, Debug)]
+pub struct StripOpts {
+	/// The path of the compressed or uncompressed wasm file to load.
+	#[clap(alias("in"), index = 1)]
+	pub input: PathBuf,
+
+	/// The path of the file where the stripped runtime will be stored.
+	#[clap(alias("out"), index = 2)]
+	pub output: PathBuf,
+
+	/// Remove all custom sections except `runtime_version` and `runtime_apis`
+	#[clap(long, conflicts_with_all = ["name", "producers", "sections"])]
+	pub all: bool,
+
+	/// Remove the `name` section
+	#[clap(long)]
+	pub name: bool,
+
+	/// Remove the `producers` section
+	#[clap(long)]
+	pub producers: bool,
+
+	/// Remove the custom section with this name. Can be repeated.
+	#[clap(long = "section", value_name = "SECTION")]
+	pub sections: Vec<String>,
+
+	/// Change the `spec_name` in the `runtime_version` section, for instance for a test network
+	#[clap(long)]
+	pub spec_name: Option<String>,
+
+	/// Change the `spec_version` in the `runtime_version` section, for instance for a test network
+	#[clap(long)]
+	pub spec_version: Option<u32>,
+}
+
+impl StripOpts {
+	/// What to remove and change. All custom sections but the ones the node reads are removed
+	/// with `--all`, or when neither a section nor the version is given.
+	pub fn strip_options(&self) -> StripOptions {
+		let mut sections = self.sections.clone();
+		if self.name {
+			sections.push("name".to_string());
+		}
+		if self.producers {
+			sections.push("producers".to_string());
+		}
+		let rewrites_version = self.spec_name.is_some() || self.spec_version.is_some();
+
+		StripOptions {
+			all: self.all || (sections.is_empty() && !rewrites_version),
+			sections,
+			spec_name: self.spec_name.clone(),
+			spec_version: self.spec_version,
+		}
+	}
+}
+
 /// Compress a given runtime wasm file.
 /// You will get an error if you try compressing a runtime that is already compressed.
 #[derive(Parser, Debug)]
@@ -290,7 +866,44 @@ pub struct DecompressOpts {
 	pub output: PathBuf,
 }
 
+/// Bytes passed as hex on the command line.
+/// We need our own type as clap would otherwise parse a `Vec<u8>` as a list of values.
+pub type Bytes = Vec<u8>;
+
+/// This parser is used by clap to parse a hex `&str`, with or without `0x` prefix, as [Bytes].
+pub fn parse_hex(s: &str) -> std::result::Result<Bytes, hex::FromHexError> {
+	hex::decode(s.trim_start_matches("0x"))
+}
+
 /// This parser wrapper is used by clap to parse a `&str` as [Source].
 pub fn parse_source(s: &str) -> error::Result<Source> {
 	Source::try_from(s).map_err(|_e| error::SubwasmError::SourceParseError(s.to_string()))
 }
+
+/// This parser is used by clap to parse a minisign public key, or the `.pub` file holding it, as [MinisignKey].
+pub fn parse_minisign_key(s: &str) -> std::result::Result<MinisignKey, String> {
+	match std::fs::read_to_string(s) {
+		Ok(content) => content.parse(),
+		Err(_) => s.parse(),
+	}
+}
+
+impl Opts {
+	/// Build the [WasmTestBedConfig] from the global options
+	pub fn testbed_config(&self) -> WasmTestBedConfig {
+		WasmTestBedConfig {
+			execution_method: self.exec_method,
+			heap_strategy: self.heap_strategy,
+			max_pages: if self.max_pages == 0 { None } else { Some(self.max_pages) },
+			extra_pages: self.extra_pages,
+			instances: self.instances,
+			log_level: match self.verbose {
+				0 => LevelFilter::Off,
+				1 => LevelFilter::Info,
+				2 => LevelFilter::Debug,
+				_ => LevelFilter::Trace,
+			},
+			trace: false,
+		}
+	}
+}
@@ -1,7 +1,7 @@
 //! Utils for the main cli
 use log::debug;
 use std::path::PathBuf;
-use subwasmlib::{fetch_at_url, source::Source, ChainInfo};
+use subwasmlib::{fetch_verified, source::Source, ChainInfo, Integrity};
 use url::Url;
 use wasm_loader::BlockRef;
 
@@ -22,6 +22,18 @@ pub fn get_source(
 	chain: Option<ChainInfo>,
 	block: Option<BlockRef>,
 	dl_url: Option<Url>,
+) -> error::Result<Source> {
+	get_verified_source(file, chain, block, dl_url, &Integrity::default())
+}
+
+/// Retrieve one unique source from all the options the user may pass.
+/// A runtime fetched from a URL is checked with `integrity` before being used.
+pub fn get_verified_source(
+	file: Option<PathBuf>,
+	chain: Option<ChainInfo>,
+	block: Option<BlockRef>,
+	dl_url: Option<Url>,
+	integrity: &Integrity,
 ) -> error::Result<Source> {
 	let source: Source = Source::from_options(file, chain, block, dl_url)?;
 	// If the source is a URL, we try to fetch it first
@@ -29,7 +41,7 @@ pub fn get_source(
 	Ok(match source {
 		Source::URL(u) => {
 			debug!("Fetching runtime from {}", u);
-			let runtime_file = fetch_at_url(u, None)?;
+			let runtime_file = fetch_verified(u, None, integrity)?;
 			debug!("Runtime fetched at {:?}", runtime_file.display());
 			Source::File(runtime_file)
 		}
@@ -0,0 +1,19 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod bench {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_benchmarks_calls() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["bench", test_wasm, "Core_version", "--runs", "2", "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains("peak_pages"));
+		}
+	}
+}
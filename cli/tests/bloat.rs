@@ -0,0 +1,28 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod bloat {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_shows_the_size_breakdown() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["bloat", test_wasm, "--top", "5"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains("code"));
+		}
+
+		#[test]
+		fn it_diffs_sizes() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["bloat", test_wasm, test_wasm, "--json"]).assert();
+			assert.success().code(0);
+		}
+	}
+}
@@ -0,0 +1,57 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod call {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_traces_host_calls() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["call", test_wasm, "Core_version", "--trace", "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains("ext_allocator_malloc_version_1"));
+		}
+
+		#[test]
+		fn it_fails_calling_missing_methods() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			cmd.args(["call", test_wasm, "Foo_bar", "--input", "0x00"]).assert().failure();
+		}
+
+		#[test]
+		fn it_calls_against_a_state() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			// System.Account of 0x0101..01 with a nonce of 5
+			let state = crate::test_utils::temp_file();
+			let key =
+				"0x26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9c035f853fcd0f0589e30c9e2dc1a0f57";
+			let value = format!("0x05000000{}", "00".repeat(76));
+			std::fs::write(&state, format!(r#"[["{key}{}", "{value}"]]"#, "01".repeat(32)))
+				.expect("Failed writing state");
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let account = "01".repeat(32);
+			let assert = cmd
+				.args(["call", test_wasm, "AccountNonceApi_account_nonce", "-i", &account, "--state", &state])
+				.assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains("returned: 0x05000000"));
+		}
+
+		#[test]
+		fn it_fails_with_invalid_states() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let state = crate::test_utils::temp_file();
+			std::fs::write(&state, "not a state").expect("Failed writing state");
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			cmd.args(["call", test_wasm, "Core_version", "--state", &state]).assert().failure();
+		}
+	}
+}
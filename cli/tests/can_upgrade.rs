@@ -0,0 +1,35 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod can_upgrade {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_rejects_the_same_spec_version() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["can-upgrade", test_wasm, test_wasm, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.failure().get_output().stdout).to_string();
+			let report: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+			let checks = report["checks"].as_array().expect("Missing checks");
+			assert!(checks.iter().any(|check| check["check"] == "spec_name" && check["outcome"] == "pass"));
+			assert!(checks.iter().any(|check| check["check"] == "spec_version" && check["outcome"] == "fail"));
+			assert!(checks.iter().any(|check| check["check"] == "transaction_version" && check["outcome"] == "pass"));
+		}
+
+		#[test]
+		fn it_checks_the_max_code_size() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["can-upgrade", test_wasm, test_wasm, "--max-code-size", "1024", "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.failure().get_output().stdout).to_string();
+			let report: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+			let checks = report["checks"].as_array().expect("Missing checks");
+			assert!(checks.iter().any(|check| check["check"] == "code_size" && check["outcome"] == "fail"));
+		}
+	}
+}
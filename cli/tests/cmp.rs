@@ -0,0 +1,23 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod cmp {
+		use crate::test_utils::temp_file;
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_compares_identical_runtimes() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let decompressed = temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			cmd.args(["decompress", test_wasm, &decompressed]).assert().success();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["cmp", test_wasm, &decompressed]).assert();
+			assert.success().code(0);
+		}
+	}
+}
@@ -0,0 +1,75 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod genesis {
+		use assert_cmd::Command;
+		use serde_json::Value;
+
+		/// Run `genesis` with `args` on the runtime under test, the current Polkadot runtime, which
+		/// exposes the `GenesisBuilder` runtime API, and parse its output
+		fn genesis(test_wasm: &str, args: &[&str]) -> Value {
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["genesis", test_wasm]).args(args).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			serde_json::from_str(&stdout).expect("Failed parsing the output")
+		}
+
+		fn first_preset(test_wasm: &str) -> String {
+			let presets = genesis(test_wasm, &["--list", "--json"]);
+			presets[0].as_str().expect("No preset found").to_string()
+		}
+
+		#[test]
+		fn it_lists_the_presets() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let presets = genesis(test_wasm, &["--list", "--json"]);
+			let presets = presets.as_array().expect("Expected a list of presets");
+			assert!(!presets.is_empty());
+			assert!(presets.iter().all(|preset| preset.as_str().is_some_and(|name| !name.is_empty())));
+		}
+
+		#[test]
+		fn it_shows_the_config_of_a_preset() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let default = genesis(test_wasm, &[]);
+			assert!(default.get("system").is_some());
+			assert!(default.get("balances").is_some());
+
+			// Presets are merged on top of the default config, which keeps its keys
+			let config = genesis(test_wasm, &["--preset", &first_preset(test_wasm)]);
+			let keys = |config: &Value| config.as_object().map(|config| config.keys().cloned().collect::<Vec<_>>());
+			assert_eq!(keys(&default), keys(&config));
+			assert_ne!(default, config);
+		}
+
+		#[test]
+		fn it_builds_a_raw_chain_spec() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let preset = first_preset(test_wasm);
+
+			let spec = genesis(test_wasm, &["--preset", &preset, "--raw"]);
+			assert!(spec["name"].as_str().is_some_and(|name| name.ends_with(&preset)));
+			let top = spec["genesis"]["raw"]["top"].as_object().expect("Missing the genesis storage");
+			let code = std::fs::read(test_wasm).expect("Failed reading the runtime");
+			assert_eq!(Some(format!("0x{}", hex::encode(code)).as_str()), top["0x3a636f6465"].as_str());
+			// System::BlockHash of the genesis block is set by build_state
+			assert!(top
+				.keys()
+				.any(|key| key.starts_with("0x26aa394eea5630e07c48ae0c9558cef7a44704b568d21667356a5a050c118746")));
+		}
+
+		#[test]
+		fn it_fails_on_unknown_presets() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["genesis", test_wasm, "--preset", "foobar"]).assert();
+			let stderr = String::from_utf8_lossy(&assert.failure().get_output().stderr).to_string();
+			assert!(stderr.contains("genesis preset foobar"));
+		}
+	}
+}
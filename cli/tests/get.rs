@@ -5,7 +5,132 @@ mod cli_tests {
 	#[cfg(test)]
 	mod get {
 		use assert_cmd::Command;
-		use std::path::Path;
+		use std::{
+			collections::HashMap,
+			io::{BufRead, BufReader, Write},
+			net::TcpListener,
+			path::Path,
+		};
+
+		/// sha256 of the `runtime` served by the stub server
+		const RUNTIME_SHA256: &str = "d92c6a81b2ff50096bcda80885427d1f59a25b5f483f7055523504925d16ab23";
+
+		/// Serve the `files` over http, 404 for the other paths
+		fn stub_server(files: HashMap<&'static str, String>) -> String {
+			let listener = TcpListener::bind("127.0.0.1:0").expect("Failed binding the stub server");
+			let url = format!("http://{}", listener.local_addr().expect("Failed getting the stub server address"));
+
+			std::thread::spawn(move || {
+				for stream in listener.incoming().flatten() {
+					let mut request = String::new();
+					BufReader::new(&stream).read_line(&mut request).expect("Failed reading the request");
+					let path = request.split_whitespace().nth(1).unwrap_or_default();
+					let (status, body) = match files.get(path) {
+						Some(body) => ("200 OK", body.as_str()),
+						None => ("404 Not Found", ""),
+					};
+					write!(
+						&stream,
+						"HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+						body.len()
+					)
+					.expect("Failed answering the request");
+				}
+			});
+
+			url
+		}
+
+		#[test]
+		fn it_checks_the_published_checksums() {
+			let url = stub_server(HashMap::from([
+				("/v1/runtime.wasm", "runtime".to_string()),
+				("/v1/SHA256SUMS", format!("{RUNTIME_SHA256}  runtime.wasm\n")),
+			]));
+			let target = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd
+				.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target])
+				.args(["--expect-hash", &format!("sha256:0x{RUNTIME_SHA256}")])
+				.assert();
+			assert.success().code(0);
+			assert!(Path::new(&target).exists());
+		}
+
+		#[test]
+		fn it_requires_a_published_checksum() {
+			let url = stub_server(HashMap::from([("/v1/runtime.wasm", "runtime".to_string())]));
+			let target = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target]).assert();
+			assert.success().code(0);
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd
+				.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target, "--checksums"])
+				.assert();
+			assert.failure();
+			assert!(!Path::new(&target).exists());
+		}
+
+		#[test]
+		fn it_fails_on_checksum_mismatch() {
+			let url = stub_server(HashMap::from([
+				("/v1/runtime.wasm", "tampered runtime".to_string()),
+				("/v1/runtime.wasm.sha256", format!("{RUNTIME_SHA256}  runtime.wasm\n")),
+			]));
+			let target = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target]).assert();
+			assert.failure();
+			assert!(!Path::new(&target).exists());
+		}
+
+		#[test]
+		fn it_fails_on_a_wrong_checksum_file() {
+			let url = stub_server(HashMap::from([
+				("/v1/runtime.wasm", "runtime".to_string()),
+				("/v1/SHA256SUMS", format!("{}  runtime.wasm\n{RUNTIME_SHA256}  other.wasm\n", "00".repeat(32))),
+			]));
+			let target = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target]).assert();
+			assert.failure();
+			assert!(!Path::new(&target).exists());
+		}
+
+		#[test]
+		fn it_fails_on_expected_hash_mismatch() {
+			let url = stub_server(HashMap::from([("/v1/runtime.wasm", "runtime".to_string())]));
+			let target = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd
+				.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target])
+				.args(["--expect-hash", &format!("blake2_256:0x{RUNTIME_SHA256}")])
+				.assert();
+			assert.failure();
+			assert!(!Path::new(&target).exists());
+		}
+
+		#[test]
+		fn it_requires_the_minisign_signature() {
+			let url = stub_server(HashMap::from([("/v1/runtime.wasm", "runtime".to_string())]));
+			let target = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd
+				.args(["get", "--url", &format!("{url}/v1/runtime.wasm"), "--output", &target])
+				.args(["--minisign-key", "RWQBAgMEBQYHCBgmvgRoGfMy6cD4/m8NLOj8bC5dRP8z4Yot4JV00q4j"])
+				.assert();
+			assert.failure();
+			assert!(!Path::new(&target).exists());
+		}
+
 		#[test]
 		fn it_gets_a_runtime() {
 			const TARGET: &str = "/tmp/polkadot_runtime.wasm";
@@ -0,0 +1,32 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod hostfns {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_lists_host_functions() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["hostfns", test_wasm]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains("ext_storage_get_version_1(i64) -> i64"));
+		}
+
+		#[test]
+		fn it_checks_host_functions() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["hostfns", test_wasm, "--against", "sp-io"]).assert();
+			assert.success().code(0);
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["hostfns", test_wasm, "--against", test_wasm]).assert();
+			assert.success().code(0);
+		}
+	}
+}
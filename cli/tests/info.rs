@@ -5,6 +5,8 @@ mod cli_tests {
 	#[cfg(test)]
 	mod info {
 		use assert_cmd::Command;
+		use subwasmlib::HashKind;
+		use wasm_loader::{Source, WasmLoader};
 
 		#[test]
 		fn it_fails_without_source() {
@@ -21,5 +23,122 @@ mod cli_tests {
 			let assert = cmd.args(["info", test_wasm]).assert();
 			assert.success().code(0);
 		}
+
+		fn info_json(test_wasm: &str, check_version: &str) -> serde_json::Value {
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert =
+				cmd.args(["info", test_wasm, "--json"]).env("AUTHORIZE_UPGRADE_CHECK_VERSION", check_version).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			serde_json::from_str(&stdout).expect("Failed parsing the info")
+		}
+
+		fn is_hash(value: &serde_json::Value) -> bool {
+			value.as_str().is_some_and(|hash| hash.starts_with("0x") && hash.len() == 66)
+		}
+
+		#[test]
+		fn it_uses_the_call_indices_of_the_metadata() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let with_check = info_json(test_wasm, "true");
+			let without_check = info_json(test_wasm, "false");
+			assert!(is_hash(&with_check["proposal_hash"]));
+			assert_eq!(with_check["proposal_hash"], without_check["proposal_hash"]);
+
+			assert_eq!("env", with_check["authorize_upgrade"]["signature_source"]);
+			assert_eq!(true, with_check["authorize_upgrade"]["check_version"]);
+			assert_eq!(false, without_check["authorize_upgrade"]["check_version"]);
+			assert_eq!(2, with_check["authorize_upgrade"]["prefix"].as_array().map_or(0, |prefix| prefix.len()));
+			assert_eq!(with_check["authorize_upgrade"]["prefix"], without_check["authorize_upgrade"]["prefix"]);
+			assert!(is_hash(&with_check["authorize_upgrade"]["hash"]));
+			assert!(is_hash(&without_check["authorize_upgrade"]["hash"]));
+			assert_ne!(with_check["authorize_upgrade"]["hash"], without_check["authorize_upgrade"]["hash"]);
+		}
+
+		#[test]
+		fn it_encodes_the_calls_for_the_current_runtime() {
+			// There is no ParachainSystem in this runtime, the signature without check_version is assumed
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["info", test_wasm, "--current", test_wasm, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let info: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the info");
+			assert_eq!("default", info["authorize_upgrade"]["signature_source"]);
+			assert!(info["authorize_upgrade"]["check_version"].is_null());
+			assert_eq!(info["parachain_authorize_upgrade_hash"], info["authorize_upgrade"]["hash"]);
+		}
+
+		#[test]
+		fn it_denies_features() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["info", test_wasm, "--deny-features", "runtime-benchmarks,try-runtime"]).assert();
+			assert.success().code(0);
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["info", test_wasm, "--deny-features", "std"]).assert();
+			assert.failure().code(2);
+		}
+
+		#[test]
+		fn it_checks_the_wasm_target() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["info", test_wasm, "--wasm-target", "polkadot-v1.x"]).assert();
+			assert.success().code(0);
+		}
+
+		/// The runtime under test as stored and decompressed
+		fn load(test_wasm: &str) -> WasmLoader {
+			WasmLoader::load_from_source(&Source::File(test_wasm.into())).expect("Failed loading the runtime")
+		}
+
+		fn hash(kind: HashKind, bytes: &[u8]) -> String {
+			format!("0x{}", hex::encode(kind.hash(bytes)))
+		}
+
+		#[test]
+		fn it_shows_the_requested_hashes() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let wasm = load(test_wasm);
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["info", test_wasm, "--hashes", "sha256,validation_code_hash", "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let hashes: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the hashes");
+
+			if wasm.compression().compressed() {
+				assert_eq!(hash(HashKind::Sha256, wasm.original_bytes()), hashes["compressed"]["sha256"]);
+			} else {
+				assert_eq!(serde_json::Value::Null, hashes["compressed"]);
+			}
+			assert_eq!(hash(HashKind::Sha256, wasm.uncompressed_bytes()), hashes["decompressed"]["sha256"]);
+			assert_eq!(serde_json::Value::Null, hashes["decompressed"]["blake2_256"]);
+			assert_eq!(hash(HashKind::ValidationCode, wasm.original_bytes()), hashes["validation_code_hash"]);
+		}
+
+		#[test]
+		fn it_checks_the_expected_hash() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let wasm = load(test_wasm);
+			let sha256 = hash(HashKind::Sha256, wasm.original_bytes());
+			let blake2_256 = hash(HashKind::Blake2_256, wasm.original_bytes());
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd
+				.args(["info", test_wasm, "--json"])
+				.args(["--expect-hash", &format!("sha256:{sha256}")])
+				.args(["--expect-hash", &format!("blake2_256:{blake2_256}")])
+				.assert();
+			assert.success().code(0);
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert =
+				cmd.args(["info", test_wasm]).args(["--expect-hash", &format!("keccak_256:{sha256}")]).assert();
+			assert.failure();
+		}
 	}
 }
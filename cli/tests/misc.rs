@@ -11,5 +11,13 @@ mod cli_tests {
 			let assert = cmd.arg("--help").assert();
 			assert.success().code(0);
 		}
+
+		#[test]
+		fn it_shows_the_version() {
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.arg("-V").assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains(env!("CARGO_PKG_VERSION")));
+		}
 	}
 }
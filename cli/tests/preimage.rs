@@ -0,0 +1,32 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod preimage {
+		use assert_cmd::Command;
+		use subwasmlib::HashKind;
+
+		#[test]
+		fn it_writes_the_set_code_preimage() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let output = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert =
+				cmd.args(["preimage", test_wasm, "--wrap", "batch_all", "--output", &output, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let preimage: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the preimage");
+			assert_eq!("Utility.batch_all(System.set_code)", preimage["call"]);
+
+			let bytes = std::fs::read(&output).expect("Failed reading the preimage");
+			assert_eq!(preimage["len"], bytes.len());
+			assert_eq!(format!("0x{}", hex::encode(HashKind::Blake2_256.hash(&bytes))), preimage["hash"]);
+
+			// Utility::batch_all with a single System::set_code call, whose argument is the runtime as stored
+			let code = std::fs::read(test_wasm).expect("Failed reading the runtime");
+			assert_eq!(0x04, bytes[2]);
+			assert!(bytes.ends_with(&code));
+		}
+	}
+}
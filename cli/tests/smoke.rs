@@ -0,0 +1,51 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod smoke {
+		use assert_cmd::Command;
+
+		/// The development preset of the runtime under test, which sets up the authorities of its consensus
+		fn development_preset(test_wasm: &str) -> String {
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["genesis", test_wasm, "--list", "--json"]).assert();
+			let presets: Vec<String> = serde_json::from_slice(&assert.success().code(0).get_output().stdout)
+				.expect("Failed parsing the presets");
+			presets
+				.iter()
+				.find(|preset| preset.as_str() == "development")
+				.or(presets.first())
+				.expect("No genesis preset found")
+				.clone()
+		}
+
+		#[test]
+		fn it_produces_blocks_from_a_genesis_preset() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let preset = development_preset(test_wasm);
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["smoke", test_wasm, "--preset", &preset, "--blocks", "2", "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let report: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+
+			assert!(report["error"].is_null());
+			assert_eq!(format!("genesis preset {preset}"), report["state"]);
+			let blocks = report["blocks"].as_array().expect("Missing blocks");
+			assert_eq!(2, blocks.len());
+			assert_eq!(1, blocks[0]["number"]);
+			assert_eq!(2, blocks[1]["number"]);
+			assert!(blocks[0]["timestamp"].as_u64() < blocks[1]["timestamp"].as_u64());
+
+			for block in blocks {
+				let hash = block["hash"].as_str().expect("Missing hash");
+				assert!(hash.starts_with("0x") && hash.len() == 66);
+				// Every block applies at least the timestamp inherent, which emits System.ExtrinsicSuccess
+				assert!(block["extrinsics"].as_u64().is_some_and(|extrinsics| extrinsics > 0));
+				let events = block["events"].as_array().expect("Missing events");
+				assert!(events.iter().any(|event| event == "System.ExtrinsicSuccess"), "{events:?}");
+			}
+		}
+	}
+}
@@ -0,0 +1,32 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod strip {
+		use crate::test_utils::temp_file;
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_strips_a_runtime() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let stripped = temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["strip", test_wasm, &stripped, "--producers"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			assert!(stdout.contains("Removed sections: producers"));
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			cmd.args(["cmp", test_wasm, &stripped]).assert().success();
+		}
+
+		#[test]
+		fn it_fails_on_unknown_sections() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			cmd.args(["strip", test_wasm, &temp_file(), "--section", "foobar"]).assert().failure();
+		}
+	}
+}
@@ -0,0 +1,26 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod upgrade_check {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_runs_the_migrations() {
+			// This runtime has no try-runtime exports, the migrations run when initializing the block
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			// System.Number of 41
+			let state = crate::test_utils::temp_file();
+			std::fs::write(&state, "0x26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac 0x29000000\n")
+				.expect("Failed writing state");
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["upgrade-check", test_wasm, "--state", &state, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let report: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+			assert_eq!("Core_initialize_block", report["method"]);
+			assert!(report["storage"]["pallets"].as_array().is_some_and(|pallets| !pallets.is_empty()));
+		}
+	}
+}
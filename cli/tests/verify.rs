@@ -0,0 +1,105 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod verify {
+		use assert_cmd::Command;
+		use std::{
+			collections::HashMap,
+			io::{BufRead, BufReader, Read, Write},
+			net::TcpListener,
+		};
+
+		/// Answer `state_getStorage` over http with the `storage` hex values, null for the other keys
+		fn stub_node(storage: HashMap<String, String>) -> String {
+			let listener = TcpListener::bind("127.0.0.1:0").expect("Failed binding the stub node");
+			let url = format!("http://{}", listener.local_addr().expect("Failed getting the stub node address"));
+
+			std::thread::spawn(move || {
+				for stream in listener.incoming().flatten() {
+					let mut reader = BufReader::new(&stream);
+					let mut content_length = 0;
+					let mut line = String::new();
+					while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+						if let Some(length) = line.to_lowercase().strip_prefix("content-length:") {
+							content_length = length.trim().parse().expect("Invalid content length");
+						}
+						line.clear();
+					}
+					let mut body = vec![0; content_length];
+					reader.read_exact(&mut body).expect("Failed reading the request");
+
+					let request: serde_json::Value = serde_json::from_slice(&body).expect("Invalid request");
+					let key = request["params"][0].as_str().expect("Missing storage key");
+					let response =
+						serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": storage.get(key) })
+							.to_string();
+					write!(
+						&stream,
+						"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+						response.len()
+					)
+					.expect("Failed answering the request");
+				}
+			});
+
+			url
+		}
+
+		/// `Preimage::PreimageFor` of the `System.set_code` call of the runtime
+		fn set_code_preimage_key(test_wasm: &str) -> String {
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["preimage", test_wasm, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let preimage: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the preimage");
+			let hash = preimage["hash"].as_str().expect("Missing hash").trim_start_matches("0x");
+			let len = preimage["len"].as_u64().expect("Missing len") as u32;
+			format!(
+				"0xd8f314b7f4e6b095f0f8ee4656a448257c7dda85c9c297999fd02215e8c8f9de{hash}{}",
+				hex::encode(len.to_le_bytes())
+			)
+		}
+
+		#[test]
+		fn it_finds_the_preimage_on_chain() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let code = std::fs::read(test_wasm).expect("Failed reading the runtime");
+			let url = stub_node(HashMap::from([
+				// The runtime is enacted, another one is authorized
+				("0x3a636f6465".to_string(), format!("0x{}", hex::encode(code))),
+				(
+					"0x26aa394eea5630e07c48ae0c9558cef72fa9f1bf25567808771bff091dc89ecd".to_string(),
+					format!("0x{}", "01".repeat(32)),
+				),
+				(set_code_preimage_key(test_wasm), "0x00".to_string()),
+			]));
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["verify", test_wasm, "--rpc-url", &url, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let report: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+			let statuses: Vec<_> = report["checks"]
+				.as_array()
+				.expect("Missing checks")
+				.iter()
+				.map(|check| (check["item"].as_str().unwrap_or_default(), check["status"].as_str().unwrap_or_default()))
+				.collect();
+			assert_eq!(
+				vec![
+					(":code", "match"),
+					("System::AuthorizedUpgrade", "mismatch"),
+					("ParachainSystem::AuthorizedUpgrade", "missing")
+				],
+				statuses[..3]
+			);
+
+			// The other upgrade calls depend on the runtime, only the set_code preimage is noted
+			for (item, status) in &statuses[3..] {
+				let expected = if *item == "Preimage::PreimageFor of System.set_code" { "match" } else { "missing" };
+				assert_eq!(expected, *status, "{item}");
+			}
+			assert!(statuses.contains(&("Preimage::PreimageFor of System.set_code", "match")));
+		}
+	}
+}
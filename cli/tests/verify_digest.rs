@@ -0,0 +1,32 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod verify_digest {
+		use assert_cmd::Command;
+
+		#[test]
+		fn it_verifies_the_srtool_digest_of_info() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let digest = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["info", test_wasm, "--format", "srtool"]).assert();
+			let stdout = assert.success().code(0).get_output().stdout.clone();
+			let json: serde_json::Value = serde_json::from_slice(&stdout).expect("Failed parsing the digest");
+			assert_eq!(json["prop"], json["subwasm"]["proposal_hash"]);
+			assert_eq!(66, json["sha256"].as_str().map(str::len).unwrap_or_default());
+			std::fs::write(&digest, stdout).expect("Failed writing the digest");
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd.args(["verify-digest", test_wasm, &digest, "--json"]).assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let report: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+			let fields = report["fields"].as_array().expect("Missing fields");
+			assert!(fields.iter().all(|field| field["matches"] == true));
+			assert!(fields.iter().any(|field| field["field"] == "sha256"));
+			assert!(fields.iter().any(|field| field["field"] == "subwasm.core_version"));
+		}
+	}
+}
@@ -0,0 +1,38 @@
+mod test_utils;
+
+#[cfg(test)]
+mod cli_tests {
+	#[cfg(test)]
+	mod xcm_upgrade {
+		use assert_cmd::Command;
+		use subwasmlib::HashKind;
+
+		#[test]
+		fn it_sends_the_upgrade_call_over_xcm() {
+			let test_wasm: &str = &crate::test_utils::ensure_local_wasm();
+			let output = crate::test_utils::temp_file();
+
+			let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).expect("Failed getting test bin");
+			let assert = cmd
+				.args(["xcm-upgrade", test_wasm, "--relay", test_wasm, "--para-id", "1000", "--call", "set_code"])
+				.args(["--output", &output, "--json"])
+				.assert();
+			let stdout = String::from_utf8_lossy(&assert.success().code(0).get_output().stdout).to_string();
+			let upgrade: serde_json::Value = serde_json::from_str(&stdout).expect("Failed parsing the report");
+
+			// The encoding of the message depends on the XCM versions the relay chain supports, see the unit tests
+			let version = upgrade["xcm_version"].as_str().expect("Missing xcm_version");
+			assert!(version.starts_with('V'));
+			let call = upgrade["relay_call"]["call"].as_str().expect("Missing relay call");
+			assert!(call.starts_with("XcmPallet.send(Parachain(1000), "));
+			assert!(call.ends_with("Transact(System.set_code)])"));
+
+			// The message transacting set_code is the last argument of XcmPallet::send, and the runtime ends set_code
+			let bytes = std::fs::read(&output).expect("Failed reading the relay chain call");
+			let code = std::fs::read(test_wasm).expect("Failed reading the runtime");
+			assert_eq!(upgrade["relay_call"]["len"], bytes.len());
+			assert_eq!(format!("0x{}", hex::encode(HashKind::Blake2_256.hash(&bytes))), upgrade["relay_call"]["hash"]);
+			assert!(bytes.ends_with(&code));
+		}
+	}
+}
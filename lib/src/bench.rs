@@ -0,0 +1,328 @@
+use num_format::{Locale, ToFormattedString};
+use serde::Serialize;
+use std::{fmt::Display, time::Instant};
+use wasm_loader::{memory_layout, Source};
+use wasm_testbed::{AllocatorStats, WasmTestBed, WasmTestBedConfig};
+
+use crate::error::*;
+
+/// Runtime API functions benchmarked when none is given
+pub const DEFAULT_BENCH_METHODS: [&str; 2] = ["Core_version", "Metadata_metadata"];
+
+/// What to benchmark
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+	/// Runtime API functions to call
+	pub methods: Vec<String>,
+
+	/// Number of timed calls per function, after one warm up call
+	pub runs: usize,
+}
+
+impl Default for BenchOptions {
+	fn default() -> Self {
+		Self { methods: DEFAULT_BENCH_METHODS.iter().map(|m| m.to_string()).collect(), runs: 10 }
+	}
+}
+
+/// Distribution of a set of durations, in nanoseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Percentiles {
+	pub min: u64,
+	pub p50: u64,
+	pub p90: u64,
+	pub p99: u64,
+	pub max: u64,
+}
+
+impl Percentiles {
+	/// Compute the percentiles of `samples` using the nearest rank method
+	pub fn new(mut samples: Vec<u64>) -> Self {
+		if samples.is_empty() {
+			return Self::default();
+		}
+		samples.sort_unstable();
+		let rank = |p: usize| samples[((p * samples.len()).div_ceil(100)).clamp(1, samples.len()) - 1];
+
+		Self { min: samples[0], p50: rank(50), p90: rank(90), p99: rank(99), max: samples[samples.len() - 1] }
+	}
+}
+
+impl Display for Percentiles {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"min {}  p50 {}  p90 {}  p99 {}  max {}",
+			duration(self.min),
+			duration(self.p50),
+			duration(self.p90),
+			duration(self.p99),
+			duration(self.max)
+		)
+	}
+}
+
+/// Cost of the calls to one runtime API function
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodBench {
+	/// Name of the runtime API function, such as `Metadata_metadata`
+	pub method: String,
+
+	/// Time spent in the calls, instances being reused
+	pub time: Percentiles,
+
+	/// Peak number of 64KB pages of linear memory the call needed
+	pub peak_pages: u64,
+
+	/// Allocator statistics of the call that used the most memory
+	pub allocator: AllocatorStats,
+}
+
+/// Execution cost of a runtime in the testbed
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+	/// Name and version of the runtime
+	pub runtime: String,
+
+	/// Number of timed calls per function
+	pub runs: usize,
+
+	/// Time it took to compile the runtime and make the first call, in nanoseconds
+	pub load: u64,
+
+	/// Time it takes to create a new instance of the compiled runtime
+	pub instantiation: Percentiles,
+
+	/// Cost of the calls, per function
+	pub methods: Vec<MethodBench>,
+}
+
+impl BenchReport {
+	/// Load the runtime with the given [WasmTestBedConfig] and call each function of
+	/// [BenchOptions::methods] [BenchOptions::runs] times through the cached executor.
+	pub fn new(source: &Source, config: &WasmTestBedConfig, options: &BenchOptions) -> Result<Self> {
+		let start = Instant::now();
+		let testbed = WasmTestBed::with_config(source, config)?;
+		let load = start.elapsed().as_nanos() as u64;
+
+		let version = testbed.core_version();
+		let runtime = format!("{} v{}", version.spec_name, version.spec_version);
+		let layout = memory_layout(testbed.wasm()).map_err(|e| SubwasmLibError::Generic(e.to_string()))?;
+
+		let instantiation = (0..options.runs)
+			.map(|_| testbed.instantiation_time().map(|time| time.as_nanos() as u64))
+			.collect::<std::result::Result<Vec<_>, _>>()?;
+
+		let mut methods = Vec::with_capacity(options.methods.len());
+		for method in &options.methods {
+			// The first call may pay for the creation of an instance
+			testbed.call(method, &[]).map_err(|e| SubwasmLibError::CallFailed(method.clone(), e.to_string()))?;
+
+			let mut times = Vec::with_capacity(options.runs);
+			let mut allocator = AllocatorStats::default();
+			for _ in 0..options.runs {
+				let start = Instant::now();
+				let result = testbed
+					.call_with_logs(method, &[])
+					.map_err(|e| SubwasmLibError::CallFailed(method.clone(), e.to_string()))?;
+				times.push(start.elapsed().as_nanos() as u64);
+
+				if let Some(stats) = result.allocation.filter(|s| s.address_space_used >= allocator.address_space_used)
+				{
+					allocator = stats;
+				}
+			}
+
+			methods.push(MethodBench {
+				method: method.clone(),
+				time: Percentiles::new(times),
+				peak_pages: layout.pages_for(allocator.address_space_used),
+				allocator,
+			});
+		}
+
+		Ok(Self { runtime, runs: options.runs, load, instantiation: Percentiles::new(instantiation), methods })
+	}
+
+	/// Rows of metrics, used to show two reports side by side
+	fn rows(&self) -> Vec<(String, Metric)> {
+		let mut rows = vec![
+			("load".to_string(), Metric::Time(self.load)),
+			("instantiation p50".to_string(), Metric::Time(self.instantiation.p50)),
+		];
+		for method in &self.methods {
+			rows.push((format!("{} p50", method.method), Metric::Time(method.time.p50)));
+			rows.push((format!("{} p90", method.method), Metric::Time(method.time.p90)));
+			rows.push((format!("{} p99", method.method), Metric::Time(method.time.p99)));
+			rows.push((format!("{} peak pages", method.method), Metric::Count(method.peak_pages as u128)));
+			rows.push((
+				format!("{} allocated peak", method.method),
+				Metric::Bytes(method.allocator.bytes_allocated_peak as u128),
+			));
+			rows.push((
+				format!("{} allocated sum", method.method),
+				Metric::Bytes(method.allocator.bytes_allocated_sum),
+			));
+		}
+		rows
+	}
+}
+
+/// Format a duration given in nanoseconds with a unit that keeps it readable
+fn duration(ns: u64) -> String {
+	match ns {
+		0..=9_999 => format!("{ns} ns"),
+		10_000..=9_999_999 => format!("{:.1} µs", ns as f64 / 1e3),
+		10_000_000..=9_999_999_999 => format!("{:.1} ms", ns as f64 / 1e6),
+		_ => format!("{:.2} s", ns as f64 / 1e9),
+	}
+}
+
+/// A value shown in the comparison of two reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+	Time(u64),
+	Count(u128),
+	Bytes(u128),
+}
+
+impl Metric {
+	fn value(&self) -> f64 {
+		match self {
+			Self::Time(v) => *v as f64,
+			Self::Count(v) | Self::Bytes(v) => *v as f64,
+		}
+	}
+
+	/// Relative change from `self` to `other`, if it can be computed
+	fn change(&self, other: &Metric) -> Option<f64> {
+		(self.value() > 0.0).then(|| (other.value() - self.value()) / self.value() * 100.0)
+	}
+}
+
+impl Display for Metric {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Time(ns) => write!(f, "{}", duration(*ns)),
+			Self::Count(count) => write!(f, "{}", count.to_formatted_string(&Locale::en)),
+			Self::Bytes(bytes) => write!(f, "{} bytes", bytes.to_formatted_string(&Locale::en)),
+		}
+	}
+}
+
+impl Display for BenchReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Runtime: {} ({} runs)", self.runtime, self.runs)?;
+		writeln!(f, "Load:           {}", duration(self.load))?;
+		writeln!(f, "Instantiation:  {}", self.instantiation)?;
+		for method in &self.methods {
+			writeln!(f, "{}", method.method)?;
+			writeln!(f, "  time:         {}", method.time)?;
+			writeln!(f, "  peak memory:  {} pages", method.peak_pages)?;
+			writeln!(
+				f,
+				"  allocator:    peak {} bytes, sum {} bytes, address space {} bytes",
+				method.allocator.bytes_allocated_peak.to_formatted_string(&Locale::en),
+				method.allocator.bytes_allocated_sum.to_formatted_string(&Locale::en),
+				method.allocator.address_space_used.to_formatted_string(&Locale::en)
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// Two [BenchReport] made with the same options, shown side by side
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchComparison {
+	pub reference: BenchReport,
+	pub other: BenchReport,
+}
+
+impl Display for BenchComparison {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let other_rows = self.other.rows();
+		let rows: Vec<[String; 4]> = self
+			.reference
+			.rows()
+			.into_iter()
+			.map(|(name, reference)| {
+				let other = other_rows.iter().find(|(n, _)| *n == name).map(|(_, m)| *m);
+				let change = other.and_then(|other| reference.change(&other)).map(|c| format!("{c:+.1}%"));
+				[
+					name,
+					reference.to_string(),
+					other.map(|m| m.to_string()).unwrap_or_else(|| "-".into()),
+					change.unwrap_or_else(|| "-".into()),
+				]
+			})
+			.collect();
+
+		let header = [String::new(), self.reference.runtime.clone(), self.other.runtime.clone(), "change".into()];
+		let mut widths = [0; 4];
+		for row in std::iter::once(&header).chain(rows.iter()) {
+			for (width, cell) in widths.iter_mut().zip(row) {
+				*width = (*width).max(cell.chars().count());
+			}
+		}
+
+		for row in std::iter::once(&header).chain(rows.iter()) {
+			writeln!(
+				f,
+				"{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}",
+				row[0],
+				row[1],
+				row[2],
+				row[3],
+				w0 = widths[0],
+				w1 = widths[1],
+				w2 = widths[2],
+				w3 = widths[3]
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests_bench {
+	use super::*;
+
+	fn report(runtime: &str, p50: u64) -> BenchReport {
+		BenchReport {
+			runtime: runtime.into(),
+			runs: 1,
+			load: 1_000_000,
+			instantiation: Percentiles::new(vec![1000]),
+			methods: vec![MethodBench {
+				method: "Core_version".into(),
+				time: Percentiles::new(vec![p50]),
+				peak_pages: 20,
+				allocator: AllocatorStats::default(),
+			}],
+		}
+	}
+
+	#[test]
+	fn it_computes_percentiles() {
+		let percentiles = Percentiles::new((1..=100).rev().collect());
+		assert_eq!(Percentiles { min: 1, p50: 50, p90: 90, p99: 99, max: 100 }, percentiles);
+		assert_eq!(Percentiles { min: 7, p50: 7, p90: 7, p99: 7, max: 7 }, Percentiles::new(vec![7]));
+		assert_eq!(Percentiles::default(), Percentiles::new(vec![]));
+	}
+
+	#[test]
+	fn it_formats_durations() {
+		assert_eq!("999 ns", duration(999));
+		assert_eq!("12.3 µs", duration(12_345));
+		assert_eq!("12.3 ms", duration(12_345_678));
+		assert_eq!("12.35 s", duration(12_345_678_901));
+	}
+
+	#[test]
+	fn it_compares_reports() {
+		let comparison = BenchComparison { reference: report("kusama v1", 2000), other: report("kusama v2", 3000) };
+		let output = comparison.to_string();
+		assert!(output.contains("kusama v1"));
+		assert!(output.lines().any(|l| l.starts_with("Core_version p50") && l.ends_with("+50.0%")));
+	}
+}
@@ -0,0 +1,265 @@
+use num_format::{Locale, ToFormattedString};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display};
+use wasm_loader::{wasm_sizes, Source as WasmLoaderSource, WasmLoader, WasmSizes};
+
+use crate::error::*;
+
+/// Name used for the functions whose symbol does not tell the crate they come from
+const NO_CRATE: &str = "[unknown]";
+
+/// A named size, in bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeEntry {
+	pub name: String,
+	pub size: usize,
+}
+
+/// Breakdown of the size of a runtime by section, crate and function.
+#[derive(Debug, Clone, Serialize)]
+pub struct BloatReport {
+	/// Size of the decompressed wasm, in bytes
+	pub total: usize,
+
+	/// Whether the wasm has a name section. Without it, we cannot tell functions and crates apart.
+	pub named: bool,
+
+	/// Sizes of the sections, in the order they appear
+	pub sections: Vec<SizeEntry>,
+
+	/// Sizes of the functions grouped by crate, largest first
+	pub crates: Vec<SizeEntry>,
+
+	/// Sizes of the functions, largest first
+	pub functions: Vec<SizeEntry>,
+}
+
+/// Demangle a symbol if needed and drop the hash suffix
+fn demangle(symbol: &str) -> String {
+	format!("{:#}", rustc_demangle::demangle(symbol))
+}
+
+/// Find the crate a function comes from, based on its demangled name.
+/// For trait impls such as `<alloc::vec::Vec<T> as core::clone::Clone>::clone`,
+/// this is the crate of the type.
+fn crate_of(function: &str) -> &str {
+	let path = function.trim_start_matches(['<', '&', '*']);
+	let path = path.strip_prefix("mut ").or_else(|| path.strip_prefix("dyn ")).unwrap_or(path);
+
+	match path.split_once("::") {
+		Some((krate, _)) if !krate.is_empty() && krate.chars().all(|c| c.is_alphanumeric() || c == '_') => krate,
+		_ => NO_CRATE,
+	}
+}
+
+/// Sort by decreasing size, then by name
+fn sorted(map: BTreeMap<String, usize>) -> Vec<SizeEntry> {
+	let mut entries: Vec<SizeEntry> = map.into_iter().map(|(name, size)| SizeEntry { name, size }).collect();
+	entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+	entries
+}
+
+impl From<WasmSizes> for BloatReport {
+	fn from(sizes: WasmSizes) -> Self {
+		let named = sizes.functions.iter().any(|f| f.name.is_some());
+		let sections = sizes.sections.into_iter().map(|s| SizeEntry { name: s.name, size: s.size }).collect();
+
+		let mut crates = BTreeMap::new();
+		let mut functions = BTreeMap::new();
+		for function in sizes.functions {
+			let name = function.name.map(|n| demangle(&n)).unwrap_or_else(|| format!("func[{}]", function.index));
+			*crates.entry(crate_of(&name).to_string()).or_default() += function.size;
+			// Generic functions may show up several times under the same name
+			*functions.entry(name).or_default() += function.size;
+		}
+
+		let crates = if named { sorted(crates) } else { Vec::new() };
+		Self { total: sizes.total, named, sections, crates, functions: sorted(functions) }
+	}
+}
+
+impl BloatReport {
+	/// Load the runtime and compute the breakdown of its decompressed wasm
+	pub fn new(source: &WasmLoaderSource) -> Result<Self> {
+		let loader = WasmLoader::load_from_source(source)?;
+		let sizes = wasm_sizes(loader.uncompressed_bytes()).map_err(|e| SubwasmLibError::Generic(e.to_string()))?;
+		Ok(sizes.into())
+	}
+
+	/// Only keep the `top` largest crates and functions
+	pub fn top(mut self, top: usize) -> Self {
+		self.crates.truncate(top);
+		self.functions.truncate(top);
+		self
+	}
+}
+
+fn write_entries(
+	f: &mut std::fmt::Formatter<'_>,
+	title: &str,
+	entries: &[SizeEntry],
+	total: usize,
+) -> std::fmt::Result {
+	writeln!(f, "{title}:")?;
+	for entry in entries {
+		writeln!(
+			f,
+			"  {:>12} {:>6.2}%  {}",
+			entry.size.to_formatted_string(&Locale::en),
+			entry.size as f64 * 100.0 / total as f64,
+			entry.name
+		)?;
+	}
+	Ok(())
+}
+
+impl Display for BloatReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Decompressed size: {} bytes", self.total.to_formatted_string(&Locale::en))?;
+		write_entries(f, "Sections", &self.sections, self.total)?;
+		if self.named {
+			write_entries(f, "Crates", &self.crates, self.total)?;
+		} else {
+			writeln!(f, "No name section found, the functions cannot be attributed to crates.")?;
+		}
+		write_entries(f, "Functions", &self.functions, self.total)
+	}
+}
+
+/// Change of a named size between 2 runtimes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeDiff {
+	pub name: String,
+	pub before: usize,
+	pub after: usize,
+	pub delta: i64,
+}
+
+/// Size difference between a reference runtime and a new one, top growers first.
+#[derive(Debug, Clone, Serialize)]
+pub struct BloatDiff {
+	pub total: SizeDiff,
+	pub sections: Vec<SizeDiff>,
+	pub crates: Vec<SizeDiff>,
+	pub functions: Vec<SizeDiff>,
+}
+
+fn diff_entries(before: &[SizeEntry], after: &[SizeEntry]) -> Vec<SizeDiff> {
+	let mut sizes: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+	before.iter().for_each(|e| sizes.entry(&e.name).or_default().0 = e.size);
+	after.iter().for_each(|e| sizes.entry(&e.name).or_default().1 = e.size);
+
+	let mut diffs: Vec<SizeDiff> = sizes
+		.into_iter()
+		.filter(|(_, (before, after))| before != after)
+		.map(|(name, (before, after))| SizeDiff {
+			name: name.to_string(),
+			before,
+			after,
+			delta: after as i64 - before as i64,
+		})
+		.collect();
+	diffs.sort_by(|a, b| b.delta.cmp(&a.delta).then_with(|| a.name.cmp(&b.name)));
+	diffs
+}
+
+impl BloatDiff {
+	/// Compare the `reference` runtime with the `new` one. Both reports should contain all entries.
+	pub fn new(reference: &BloatReport, new: &BloatReport) -> Self {
+		Self {
+			total: SizeDiff {
+				name: "total".to_string(),
+				before: reference.total,
+				after: new.total,
+				delta: new.total as i64 - reference.total as i64,
+			},
+			sections: diff_entries(&reference.sections, &new.sections),
+			crates: diff_entries(&reference.crates, &new.crates),
+			functions: diff_entries(&reference.functions, &new.functions),
+		}
+	}
+
+	/// Only keep the `top` crates and functions that changed the most
+	pub fn top(mut self, top: usize) -> Self {
+		self.crates.truncate(top);
+		self.functions.truncate(top);
+		self
+	}
+}
+
+fn write_diffs(f: &mut std::fmt::Formatter<'_>, title: &str, diffs: &[SizeDiff]) -> std::fmt::Result {
+	writeln!(f, "{title}:")?;
+	for diff in diffs {
+		writeln!(
+			f,
+			"  {:>+12} {:>12} -> {:<12} {}",
+			diff.delta,
+			diff.before.to_formatted_string(&Locale::en),
+			diff.after.to_formatted_string(&Locale::en),
+			diff.name
+		)?;
+	}
+	Ok(())
+}
+
+impl Display for BloatDiff {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(
+			f,
+			"Decompressed size: {} -> {} bytes ({:+})",
+			self.total.before.to_formatted_string(&Locale::en),
+			self.total.after.to_formatted_string(&Locale::en),
+			self.total.delta
+		)?;
+		write_diffs(f, "Sections", &self.sections)?;
+		write_diffs(f, "Crates", &self.crates)?;
+		write_diffs(f, "Functions", &self.functions)
+	}
+}
+
+#[cfg(test)]
+mod tests_bloat {
+	use super::*;
+	use wasm_loader::{FunctionSize, SectionSize};
+
+	fn sizes(functions: &[(&str, usize)]) -> WasmSizes {
+		WasmSizes {
+			total: 1000,
+			sections: vec![SectionSize { name: "code".into(), size: functions.iter().map(|f| f.1).sum() }],
+			functions: functions
+				.iter()
+				.enumerate()
+				.map(|(i, (name, size))| FunctionSize { index: i as u32, name: Some(name.to_string()), size: *size })
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn it_finds_crates() {
+		assert_eq!("sp_io", crate_of("sp_io::storage::get"));
+		assert_eq!("alloc", crate_of("<alloc::vec::Vec<T> as core::clone::Clone>::clone"));
+		assert_eq!("core", crate_of("<&mut core::fmt::Formatter as core::fmt::Write>::write_str"));
+		assert_eq!(NO_CRATE, crate_of("memcpy"));
+	}
+
+	#[test]
+	fn it_groups_by_crate() {
+		let report =
+			BloatReport::from(sizes(&[("sp_io::storage::get", 10), ("sp_io::storage::set", 20), ("memcpy", 5)]));
+		assert!(report.named);
+		assert_eq!(SizeEntry { name: "sp_io".into(), size: 30 }, report.crates[0]);
+		assert_eq!(SizeEntry { name: "sp_io::storage::set".into(), size: 20 }, report.functions[0]);
+		assert_eq!(1, report.top(1).functions.len());
+	}
+
+	#[test]
+	fn it_diffs_reports() {
+		let before = BloatReport::from(sizes(&[("pallet_a::f", 10), ("pallet_b::f", 20)]));
+		let after = BloatReport::from(sizes(&[("pallet_a::f", 50), ("pallet_b::f", 20), ("pallet_c::f", 5)]));
+		let diff = BloatDiff::new(&before, &after);
+		assert_eq!(2, diff.functions.len());
+		assert_eq!("pallet_a::f", diff.functions[0].name);
+		assert_eq!(40, diff.functions[0].delta);
+		assert_eq!(45, diff.sections[0].delta);
+	}
+}
@@ -0,0 +1,87 @@
+use num_format::{Locale, ToFormattedString};
+use serde::Serialize;
+use std::fmt::Display;
+use wasm_testbed::{HostCall, WasmTestbedError};
+
+/// The outcome of a call into a runtime API, with the host calls it made if tracing was enabled
+#[derive(Debug, Clone, Serialize)]
+pub struct CallReport {
+	/// Name of the runtime API function, such as `Metadata_metadata`
+	pub method: String,
+
+	/// Hex encoded input of the call
+	pub input: String,
+
+	/// Hex encoded output of the call, if it succeeded
+	pub output: Option<String>,
+
+	/// Cause of the failure, if the call failed
+	pub error: Option<String>,
+
+	/// Host calls made by the runtime, in order
+	pub trace: Vec<HostCall>,
+}
+
+impl CallReport {
+	pub fn new(
+		method: &str,
+		input: &[u8],
+		result: std::result::Result<Vec<u8>, WasmTestbedError>,
+		trace: Vec<HostCall>,
+	) -> Self {
+		let (output, error) = match result {
+			Ok(output) => (Some(format!("0x{}", hex::encode(output))), None),
+			Err(e) => (None, Some(e.to_string())),
+		};
+		Self { method: method.to_string(), input: format!("0x{}", hex::encode(input)), output, error, trace }
+	}
+}
+
+impl Display for CallReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if !self.trace.is_empty() {
+			writeln!(f, "Host calls:")?;
+			for (index, call) in self.trace.iter().enumerate() {
+				writeln!(
+					f,
+					"  {:>6} {:<50} {:>10} in {:>10} out {:>10} ns",
+					index,
+					call.name,
+					call.bytes_in.to_formatted_string(&Locale::en),
+					call.bytes_out.to_formatted_string(&Locale::en),
+					call.elapsed_ns.to_formatted_string(&Locale::en),
+				)?;
+			}
+			let total: u64 = self.trace.iter().map(|call| call.elapsed_ns).sum();
+			writeln!(f, "{} host calls, {} ns in total", self.trace.len(), total.to_formatted_string(&Locale::en))?;
+		}
+
+		match (&self.output, &self.error) {
+			(_, Some(error)) => writeln!(f, "❌ {} failed: {error}", self.method),
+			(Some(output), None) => writeln!(f, "✅ {} returned: {output}", self.method),
+			(None, None) => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests_call {
+	use super::*;
+
+	#[test]
+	fn it_reports_calls() {
+		let call = HostCall { name: "ext_storage_get_version_1".into(), bytes_in: 4, bytes_out: 0, elapsed_ns: 100 };
+		let report = CallReport::new("Core_version", &[], Ok(vec![0x2a]), vec![call]);
+		assert_eq!(Some("0x2a".to_string()), report.output);
+		assert_eq!("0x", report.input);
+		assert!(report.to_string().contains("1 host calls"));
+	}
+
+	#[test]
+	fn it_reports_failures() {
+		let error = WasmTestbedError::Calling("Foo_bar".into(), "trap".into(), vec![]);
+		let report = CallReport::new("Foo_bar", &[1, 2], Err(error), vec![]);
+		assert!(report.output.is_none());
+		assert!(report.to_string().contains("❌ Foo_bar failed"));
+	}
+}
@@ -0,0 +1,254 @@
+use serde::Serialize;
+use sp_version::RuntimeVersion as SubstrateRuntimeVersion;
+use std::{cmp::Ordering, fmt::Display};
+use substrate_differ::differs::reduced::{reduced_diff_result::ReducedDiffResult, reduced_runtime::ReducedRuntime};
+use wasm_loader::CODE_BLOB_BOMB_LIMIT;
+use wasm_testbed::WasmTestBed;
+
+use crate::error::*;
+
+/// The outcome of one of the checks of an upgrade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckOutcome {
+	Pass,
+
+	/// The upgrade goes through but needs some care
+	Warning,
+
+	/// The chain rejects the upgrade, or the new runtime breaks the clients
+	Fail,
+}
+
+/// A field of the runtimes checked before an upgrade
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeRequirement {
+	/// Name of the check, such as `spec_version`
+	pub check: String,
+
+	pub current: String,
+	pub new: String,
+	pub outcome: CheckOutcome,
+
+	/// Why the check passed or failed
+	pub reason: String,
+}
+
+impl UpgradeRequirement {
+	fn new(check: &str, current: impl Display, new: impl Display, outcome: CheckOutcome, reason: &str) -> Self {
+		Self {
+			check: check.to_string(),
+			current: current.to_string(),
+			new: new.to_string(),
+			outcome,
+			reason: reason.to_string(),
+		}
+	}
+}
+
+/// Whether the chain running a runtime accepts the upgrade to a new one
+#[derive(Debug, Clone, Serialize)]
+pub struct CanUpgradeReport {
+	/// The runtime the chain runs, such as `polkadot v1002000`
+	pub current: String,
+
+	/// The runtime to upgrade to
+	pub new: String,
+
+	pub checks: Vec<UpgradeRequirement>,
+}
+
+impl CanUpgradeReport {
+	/// Check the upgrade from the runtime of `current` to the runtime of `new`. The stored runtime must not be
+	/// larger than `max_code_size`, such as the `max_code_size` of the relay chain for a parachain.
+	pub fn new(current: &WasmTestBed, new: &WasmTestBed, max_code_size: Option<usize>) -> Result<Self> {
+		let diff =
+			ReducedDiffResult::new(ReducedRuntime::from(current.metadata()?), ReducedRuntime::from(new.metadata()?));
+		let (current_version, new_version) = (current.core_version(), new.core_version());
+
+		let mut checks = version_checks(&current_version, &new_version, diff.require_transaction_version_bump());
+		checks.extend(size_checks(
+			(current.size(), current.wasm().len()),
+			(new.size(), new.wasm().len()),
+			max_code_size,
+		));
+
+		Ok(Self {
+			current: format!("{} v{}", current_version.spec_name, current_version.spec_version),
+			new: format!("{} v{}", new_version.spec_name, new_version.spec_version),
+			checks,
+		})
+	}
+
+	/// Whether none of the checks failed
+	pub fn is_ok(&self) -> bool {
+		self.checks.iter().all(|check| check.outcome != CheckOutcome::Fail)
+	}
+}
+
+/// The checks `frame_system::can_set_code` runs on the versions of the runtimes, along with the
+/// versions the clients rely on
+fn version_checks(
+	current: &SubstrateRuntimeVersion,
+	new: &SubstrateRuntimeVersion,
+	require_transaction_version_bump: bool,
+) -> Vec<UpgradeRequirement> {
+	use CheckOutcome::*;
+
+	let spec_name = if current.spec_name == new.spec_name {
+		(Pass, "The spec_name is unchanged")
+	} else {
+		(Fail, "frame_system rejects a runtime with another spec_name: InvalidSpecName")
+	};
+
+	let spec_version = if new.spec_version > current.spec_version {
+		(Pass, "The spec_version increases")
+	} else {
+		(Fail, "frame_system rejects a runtime whose spec_version does not increase: SpecVersionNeedsToIncrease")
+	};
+
+	let transaction_version =
+		match (require_transaction_version_bump, new.transaction_version.cmp(&current.transaction_version)) {
+			(_, Ordering::Less) => (Fail, "The transaction_version must not decrease"),
+			(true, Ordering::Equal) => {
+				(Fail, "The calls changed in a breaking way, the transaction_version must be bumped")
+			}
+			(true, Ordering::Greater) => {
+				(Pass, "The calls changed in a breaking way and the transaction_version is bumped")
+			}
+			(false, Ordering::Equal) => (Pass, "The calls are compatible and the transaction_version is unchanged"),
+			(false, Ordering::Greater) => (
+				Warning,
+				"The calls are compatible, bumping the transaction_version invalidates the transactions signed offline",
+			),
+		};
+
+	let state_version = if current.state_version == new.state_version {
+		(Pass, "The state_version is unchanged")
+	} else {
+		(Warning, "The trie of the chain must be migrated to the new state_version")
+	};
+
+	vec![
+		UpgradeRequirement::new("spec_name", &current.spec_name, &new.spec_name, spec_name.0, spec_name.1),
+		UpgradeRequirement::new("spec_version", current.spec_version, new.spec_version, spec_version.0, spec_version.1),
+		UpgradeRequirement::new(
+			"transaction_version",
+			current.transaction_version,
+			new.transaction_version,
+			transaction_version.0,
+			transaction_version.1,
+		),
+		UpgradeRequirement::new(
+			"state_version",
+			current.state_version,
+			new.state_version,
+			state_version.0,
+			state_version.1,
+		),
+	]
+}
+
+/// Check the sizes of the runtimes, as stored and decompressed, against the limits of the chain and the node
+fn size_checks(
+	(current_size, current_decompressed): (usize, usize),
+	(new_size, new_decompressed): (usize, usize),
+	max_code_size: Option<usize>,
+) -> Vec<UpgradeRequirement> {
+	use CheckOutcome::*;
+
+	let code_size = match max_code_size {
+		Some(max) if new_size > max => (Fail, format!("The runtime exceeds the max_code_size of {max} bytes")),
+		Some(max) => (Pass, format!("The runtime fits in the max_code_size of {max} bytes")),
+		None => (Pass, "No max_code_size given".to_string()),
+	};
+
+	let decompressed_size = if new_decompressed > CODE_BLOB_BOMB_LIMIT {
+		(Fail, format!("The node refuses to decompress a runtime larger than {CODE_BLOB_BOMB_LIMIT} bytes"))
+	} else {
+		(Pass, format!("The runtime decompresses within the {CODE_BLOB_BOMB_LIMIT} bytes the node allows"))
+	};
+
+	vec![
+		UpgradeRequirement::new("code_size", current_size, new_size, code_size.0, &code_size.1),
+		UpgradeRequirement::new(
+			"decompressed_size",
+			current_decompressed,
+			new_decompressed,
+			decompressed_size.0,
+			&decompressed_size.1,
+		),
+	]
+}
+
+impl Display for CanUpgradeReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Upgrade from {} to {}", self.current, self.new)?;
+		for check in &self.checks {
+			let icon = match check.outcome {
+				CheckOutcome::Pass => "✅",
+				CheckOutcome::Warning => "⚠️ ",
+				CheckOutcome::Fail => "❌",
+			};
+			writeln!(f, "{icon} {:<20} {:>12} -> {:<12} {}", check.check, check.current, check.new, check.reason)?;
+		}
+
+		let failures = self.checks.iter().filter(|check| check.outcome == CheckOutcome::Fail).count();
+		match failures {
+			0 => writeln!(f, "✅ The upgrade can be applied"),
+			_ => writeln!(f, "❌ {failures} of {} checks failed, the upgrade would be rejected", self.checks.len()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests_can_upgrade {
+	use super::*;
+
+	fn version(spec_name: &'static str, spec_version: u32, transaction_version: u32) -> SubstrateRuntimeVersion {
+		SubstrateRuntimeVersion { spec_name: spec_name.into(), spec_version, transaction_version, ..Default::default() }
+	}
+
+	fn outcomes(checks: &[UpgradeRequirement]) -> Vec<(&str, CheckOutcome)> {
+		checks.iter().map(|check| (check.check.as_str(), check.outcome)).collect()
+	}
+
+	#[test]
+	fn it_applies_the_frame_system_checks() {
+		use CheckOutcome::*;
+
+		let checks = version_checks(&version("polkadot", 1000, 25), &version("polkadot", 1001, 25), false);
+		assert_eq!(
+			vec![("spec_name", Pass), ("spec_version", Pass), ("transaction_version", Pass), ("state_version", Pass)],
+			outcomes(&checks)
+		);
+
+		let checks = version_checks(&version("polkadot", 1000, 25), &version("kusama", 1000, 25), false);
+		assert_eq!(Fail, checks[0].outcome);
+		assert_eq!(Fail, checks[1].outcome);
+	}
+
+	#[test]
+	fn it_checks_the_transaction_version() {
+		use CheckOutcome::*;
+
+		let check =
+			|current, new, bump| version_checks(&version("a", 1, current), &version("a", 2, new), bump)[2].outcome;
+		assert_eq!(Fail, check(25, 25, true));
+		assert_eq!(Pass, check(25, 26, true));
+		assert_eq!(Warning, check(25, 26, false));
+		assert_eq!(Fail, check(25, 24, false));
+	}
+
+	#[test]
+	fn it_checks_the_sizes() {
+		use CheckOutcome::*;
+
+		assert_eq!(
+			vec![("code_size", Pass), ("decompressed_size", Pass)],
+			outcomes(&size_checks((1, 2), (3, 4), None))
+		);
+		assert_eq!(Fail, size_checks((1, 2), (3, 4), Some(2))[0].outcome);
+		assert_eq!(Fail, size_checks((1, 2), (3, CODE_BLOB_BOMB_LIMIT + 1), None)[1].outcome);
+	}
+}
@@ -0,0 +1,233 @@
+use serde::Serialize;
+use serde_json::Value;
+use sp_core::hashing::sha2_256;
+use std::{fmt::Display, str::FromStr};
+use wasm_testbed::WasmTestBed;
+
+use crate::{error::*, RuntimeInfo};
+
+/// The fields of a runtime in a srtool digest, next to the `subwasm` info
+const DIGEST_FIELDS: [&str; 6] = ["size", "prop", "authorize_upgrade_hash", "blake2_256", "ipfs", "sha256"];
+
+/// The output format of the runtime info
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoFormat {
+	/// Human readable
+	Human,
+
+	/// The runtime info as json
+	Json,
+
+	/// A runtime of a srtool digest, with the runtime info under `subwasm`
+	Srtool,
+}
+
+impl FromStr for InfoFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"human" => Ok(Self::Human),
+			"json" => Ok(Self::Json),
+			"srtool" => Ok(Self::Srtool),
+			_ => Err(format!("Unknown format `{s}`, expected human, json or srtool")),
+		}
+	}
+}
+
+/// A runtime as described in the `runtimes` of the digests srtool builds
+#[derive(Debug, Clone, Serialize)]
+pub struct SrtoolDigest {
+	/// Size of the runtime, in bytes
+	pub size: String,
+
+	/// Hash of `System::set_code`
+	pub prop: String,
+
+	/// Hash of `ParachainSystem::authorize_upgrade`
+	pub authorize_upgrade_hash: String,
+
+	pub blake2_256: String,
+
+	/// IPFS CID of the runtime
+	pub ipfs: String,
+
+	pub sha256: String,
+
+	/// Path of the runtime
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub wasm: Option<String>,
+
+	/// The output of `subwasm info --json`
+	pub subwasm: Value,
+}
+
+impl SrtoolDigest {
+	pub fn new(testbed: &WasmTestBed, info: &RuntimeInfo, wasm: Option<String>) -> Result<Self> {
+		Ok(Self {
+			size: info.size.to_string(),
+			prop: info.proposal_hash.clone(),
+			authorize_upgrade_hash: info.parachain_authorize_upgrade_hash.clone(),
+			blake2_256: info.blake2_256.clone(),
+			ipfs: info.ipfs_hash.clone(),
+			sha256: format!("0x{}", hex::encode(sha2_256(testbed.raw_bytes()))),
+			wasm,
+			subwasm: serde_json::to_value(info)?,
+		})
+	}
+}
+
+/// A field of a digest compared with the value computed for the runtime
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestField {
+	/// Name of the field, such as `sha256` or `subwasm.core_version`
+	pub field: String,
+
+	pub expected: Value,
+	pub actual: Value,
+	pub matches: bool,
+}
+
+impl DigestField {
+	fn new(field: String, expected: &Value, actual: &Value) -> Self {
+		let matches = match (expected, actual) {
+			// srtool stores the sizes as strings
+			(Value::String(expected), Value::Number(actual)) | (Value::Number(actual), Value::String(expected)) => {
+				expected == &actual.to_string()
+			}
+			(expected, actual) => expected == actual,
+		};
+		Self { field, expected: expected.clone(), actual: actual.clone(), matches }
+	}
+}
+
+/// The fields of a srtool digest, compared one by one with the runtime
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestReport {
+	/// Which runtime of the digest was compared, such as `compressed`, if it has several
+	pub runtime: Option<String>,
+
+	pub fields: Vec<DigestField>,
+}
+
+impl DigestReport {
+	/// Compare `digest` with the digest computed for the runtime. The digest is either a full srtool digest,
+	/// whose `compressed` or `compact` runtime is picked according to the runtime compression, or a single runtime
+	/// such as the output of `info --format srtool`.
+	/// Only the fields that both know are compared.
+	pub fn new(actual: &SrtoolDigest, digest: &Value, compressed: bool) -> Result<Self> {
+		let (runtime, expected) = match digest.get("runtimes") {
+			Some(runtimes) => {
+				let names = if compressed { ["compressed", "compact"] } else { ["compact", "compressed"] };
+				let name = names
+					.into_iter()
+					.find(|name| runtimes.get(name).is_some())
+					.ok_or_else(|| SubwasmLibError::NotFound("runtimes.compressed or runtimes.compact".into()))?;
+				(Some(name.to_string()), &runtimes[name])
+			}
+			None => (None, digest),
+		};
+		let actual = serde_json::to_value(actual)?;
+
+		let mut fields = Vec::new();
+		for name in DIGEST_FIELDS {
+			if let (Some(expected), Some(actual)) = (expected.get(name), actual.get(name)) {
+				fields.push(DigestField::new(name.to_string(), expected, actual));
+			}
+		}
+		if let (Some(Value::Object(expected)), Some(actual)) = (expected.get("subwasm"), actual.get("subwasm")) {
+			for (name, expected) in expected {
+				if let Some(actual) = actual.get(name) {
+					fields.push(DigestField::new(format!("subwasm.{name}"), expected, actual));
+				}
+			}
+		}
+
+		if fields.is_empty() {
+			return Err(SubwasmLibError::Generic("The digest has no field to compare".into()));
+		}
+		Ok(Self { runtime, fields })
+	}
+
+	pub fn is_ok(&self) -> bool {
+		self.fields.iter().all(|field| field.matches)
+	}
+}
+
+impl Display for DigestReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let show = |value: &Value| match value {
+			Value::String(s) => s.clone(),
+			value => value.to_string(),
+		};
+
+		if let Some(runtime) = &self.runtime {
+			writeln!(f, "Runtime: {runtime}")?;
+		}
+		for field in &self.fields {
+			if field.matches {
+				writeln!(f, "✅ {:<45} {}", field.field, show(&field.actual))?;
+			} else {
+				writeln!(f, "❌ {:<45} expected {}, got {}", field.field, show(&field.expected), show(&field.actual))?;
+			}
+		}
+
+		let mismatches = self.fields.iter().filter(|field| !field.matches).count();
+		match mismatches {
+			0 => writeln!(f, "✅ All {} fields match", self.fields.len()),
+			_ => writeln!(f, "❌ {mismatches} of {} fields do not match", self.fields.len()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests_digest {
+	use super::*;
+	use serde_json::json;
+
+	fn actual() -> SrtoolDigest {
+		SrtoolDigest {
+			size: "42".into(),
+			prop: "0x01".into(),
+			authorize_upgrade_hash: "0x02".into(),
+			blake2_256: "0x03".into(),
+			ipfs: "Qm".into(),
+			sha256: "0x04".into(),
+			wasm: None,
+			subwasm: json!({ "size": 42, "core_version": { "specVersion": 1 } }),
+		}
+	}
+
+	#[test]
+	fn it_compares_the_compressed_runtime() {
+		let digest = json!({
+			"runtimes": {
+				"compact": { "sha256": "0xff" },
+				"compressed": {
+					"size": "42",
+					"sha256": "0x05",
+					"subwasm": { "size": 42, "core_version": { "specVersion": 1 }, "unknown": true }
+				}
+			}
+		});
+		let report = DigestReport::new(&actual(), &digest, true).expect("Failed comparing the digest");
+
+		assert_eq!(Some("compressed".to_string()), report.runtime);
+		let fields: Vec<_> = report.fields.iter().map(|field| (field.field.as_str(), field.matches)).collect();
+		assert_eq!(
+			vec![("size", true), ("sha256", false), ("subwasm.core_version", true), ("subwasm.size", true)],
+			fields
+		);
+		assert!(!report.is_ok());
+	}
+
+	#[test]
+	fn it_compares_a_single_runtime() {
+		let digest = serde_json::to_value(actual()).expect("Failed serializing the digest");
+		let report = DigestReport::new(&actual(), &digest, false).expect("Failed comparing the digest");
+
+		assert_eq!(None, report.runtime);
+		assert!(report.is_ok());
+		assert!(DigestReport::new(&actual(), &json!({}), false).is_err());
+	}
+}
@@ -74,6 +74,34 @@ pub enum SubwasmLibError {
 	#[error("Cannot resolve `{0}` to a known Source")]
 	UnknownSource(String),
 
+	/// The runtime imports host functions that are missing or have a different signature
+	#[error("Host functions missing or with a different signature: {0}")]
+	HostFunctionsMismatch(String),
+
+	/// Some optional features that should not make it to production are compiled in
+	#[error("The runtime was built with denied features: {0}")]
+	FeaturesDenied(String),
+
+	/// The runtime uses wasm proposals that the target executor does not support (target, proposals)
+	#[error("The runtime uses wasm proposals not supported by {0}: {1}")]
+	UnsupportedWasmProposals(String, String),
+
+	/// The runtimes are different, even after ignoring the custom sections that do not matter
+	#[error("The runtimes are different in the following sections: {0}")]
+	RuntimesDiffer(String),
+
+	/// A call into the runtime failed (method, cause)
+	#[error("The call to `{0}` failed: {1}")]
+	CallFailed(String, String),
+
+	/// The runtime does not have the expected hash (algorithm, expected, actual)
+	#[error("The {0} hash of the runtime is {2} instead of the expected {1}")]
+	HashMismatch(String, String, String),
+
+	/// The signature of a downloaded runtime is missing or invalid
+	#[error("Invalid signature: {0}")]
+	InvalidSignature(String),
+
 	/// Unknown error
 	#[error("Unknown error")]
 	Unknown(),
@@ -86,8 +114,8 @@ impl From<std::io::Error> for SubwasmLibError {
 }
 
 impl From<WasmTestbedError> for SubwasmLibError {
-	fn from(_e: WasmTestbedError) -> Self {
-		SubwasmLibError::Generic("WasmTestbedError".to_string())
+	fn from(e: WasmTestbedError) -> Self {
+		SubwasmLibError::Generic(e.to_string())
 	}
 }
 
@@ -0,0 +1,176 @@
+use scale::{Decode, Encode};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use wasm_loader::exports;
+use wasm_testbed::{BasicExternalities, WasmTestBed};
+
+use crate::error::*;
+
+/// Storage key of the runtime code
+const CODE_KEY: &[u8] = b":code";
+
+/// Functions of the `GenesisBuilder` runtime API, version 2 and later
+const PRESET_NAMES: &str = "GenesisBuilder_preset_names";
+const GET_PRESET: &str = "GenesisBuilder_get_preset";
+const BUILD_STATE: &str = "GenesisBuilder_build_state";
+
+/// Functions of the `GenesisBuilder` runtime API, version 1
+const CREATE_DEFAULT_CONFIG: &str = "GenesisBuilder_create_default_config";
+const BUILD_CONFIG: &str = "GenesisBuilder_build_config";
+
+/// Access to the genesis configs of a runtime through its `GenesisBuilder` runtime API.
+/// Those calls need no storage so they work in the testbed.
+pub struct Genesis<'a> {
+	testbed: &'a WasmTestBed,
+	exports: Vec<String>,
+}
+
+impl<'a> Genesis<'a> {
+	pub fn new(testbed: &'a WasmTestBed) -> Result<Self> {
+		let exports = exports(testbed.wasm()).map_err(|e| SubwasmLibError::Generic(e.to_string()))?;
+		if !exports.iter().any(|e| e == GET_PRESET || e == CREATE_DEFAULT_CONFIG) {
+			return Err(SubwasmLibError::NotFound("GenesisBuilder runtime API".to_string()));
+		}
+		Ok(Self { testbed, exports })
+	}
+
+	fn exports(&self, method: &str) -> bool {
+		self.exports.iter().any(|e| e == method)
+	}
+
+	fn call(&self, method: &str, input: &[u8]) -> Result<Vec<u8>> {
+		self.testbed.call(method, input).map_err(|e| SubwasmLibError::CallFailed(method.to_string(), e.to_string()))
+	}
+
+	fn decode<T: Decode>(method: &str, output: &[u8]) -> Result<T> {
+		T::decode(&mut &output[..]).map_err(|e| SubwasmLibError::Parsing(method.to_string(), e.to_string()))
+	}
+
+	fn parse_json(method: &str, json: &[u8]) -> Result<Value> {
+		serde_json::from_slice(json).map_err(|e| SubwasmLibError::Parsing(method.to_string(), e.to_string()))
+	}
+
+	/// Names of the presets of the runtime. Runtimes with the version 1 of the
+	/// `GenesisBuilder` API only have the default config and no preset.
+	pub fn presets(&self) -> Result<Vec<String>> {
+		if !self.exports(PRESET_NAMES) {
+			return Ok(Vec::new());
+		}
+		Self::decode(PRESET_NAMES, &self.call(PRESET_NAMES, &[])?)
+	}
+
+	/// Get the genesis config of the preset called `name`, or the default one.
+	/// Presets are patches on top of the default config, we return the whole config.
+	pub fn config(&self, name: Option<&str>) -> Result<Value> {
+		let mut config = if self.exports(GET_PRESET) {
+			let preset: Option<Vec<u8>> = Self::decode(GET_PRESET, &self.call(GET_PRESET, &None::<String>.encode())?)?;
+			let preset = preset.ok_or_else(|| SubwasmLibError::NotFound("default genesis preset".to_string()))?;
+			Self::parse_json(GET_PRESET, &preset)?
+		} else {
+			let config: Vec<u8> = Self::decode(CREATE_DEFAULT_CONFIG, &self.call(CREATE_DEFAULT_CONFIG, &[])?)?;
+			Self::parse_json(CREATE_DEFAULT_CONFIG, &config)?
+		};
+
+		if let Some(name) = name {
+			if !self.exports(GET_PRESET) {
+				return Err(SubwasmLibError::NotFound(format!("genesis preset {name}")));
+			}
+			let patch: Option<Vec<u8>> =
+				Self::decode(GET_PRESET, &self.call(GET_PRESET, &Some(name.to_string()).encode())?)?;
+			let patch = patch.ok_or_else(|| SubwasmLibError::NotFound(format!("genesis preset {name}")))?;
+			merge(&mut config, Self::parse_json(GET_PRESET, &patch)?);
+		}
+		Ok(config)
+	}
+
+	/// Build the genesis storage from the config of the preset called `name`, or the default one
+	pub fn storage(&self, name: Option<&str>) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+		let config = serde_json::to_vec(&self.config(name)?)?;
+		let method = if self.exports(BUILD_STATE) { BUILD_STATE } else { BUILD_CONFIG };
+
+		let mut state = BasicExternalities::default();
+		let output = self
+			.testbed
+			.call_with_state(method, &config.encode(), &mut state)
+			.map_err(|e| SubwasmLibError::CallFailed(method.to_string(), e.to_string()))?
+			.output;
+		let result: std::result::Result<(), String> = Self::decode(method, &output)?;
+		result.map_err(|e| SubwasmLibError::CallFailed(method.to_string(), e))?;
+
+		Ok(state.into_storages().top)
+	}
+
+	/// Build a raw chain spec with the genesis storage of the preset called `name`, or the default one,
+	/// and the runtime as `:code`. This is enough to start a development chain with a node.
+	pub fn raw_chain_spec(&self, name: Option<&str>) -> Result<Value> {
+		let mut storage = self.storage(name)?;
+		storage.insert(CODE_KEY.to_vec(), self.testbed.raw_bytes().to_vec());
+
+		let version = self.testbed.core_version();
+		Ok(raw_chain_spec(&version.spec_name, name.unwrap_or("development"), storage))
+	}
+}
+
+/// Merge `patch` into `value`. Objects are merged key by key, `null` removes a key
+/// and any other value replaces the existing one.
+fn merge(value: &mut Value, patch: Value) {
+	match (value, patch) {
+		(Value::Object(value), Value::Object(patch)) => {
+			for (key, patch) in patch {
+				match patch {
+					Value::Null => {
+						value.remove(&key);
+					}
+					patch => merge(value.entry(key).or_insert(Value::Null), patch),
+				}
+			}
+		}
+		(value, patch) => *value = patch,
+	}
+}
+
+/// A raw chain spec, as expected by the Substrate nodes, with the given genesis storage
+fn raw_chain_spec(spec_name: &str, preset: &str, storage: BTreeMap<Vec<u8>, Vec<u8>>) -> Value {
+	let top: Map<String, Value> = storage
+		.into_iter()
+		.map(|(key, value)| (format!("0x{}", hex::encode(key)), Value::String(format!("0x{}", hex::encode(value)))))
+		.collect();
+
+	json!({
+		"name": format!("{spec_name} {preset}"),
+		"id": format!("{spec_name}_{preset}").to_lowercase().replace(['-', ' '], "_"),
+		"chainType": "Development",
+		"bootNodes": [],
+		"telemetryEndpoints": null,
+		"protocolId": null,
+		"properties": null,
+		"codeSubstitutes": {},
+		"genesis": {
+			"raw": {
+				"top": top,
+				"childrenDefault": {},
+			}
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests_genesis {
+	use super::*;
+
+	#[test]
+	fn it_merges_patches() {
+		let mut config = json!({ "balances": { "balances": [["alice", 1]] }, "sudo": { "key": "alice" }, "old": 1 });
+		let patch = json!({ "balances": { "balances": [["bob", 2]] }, "sudo": { "key": "bob" }, "old": null });
+		merge(&mut config, patch);
+		assert_eq!(json!({ "balances": { "balances": [["bob", 2]] }, "sudo": { "key": "bob" } }), config);
+	}
+
+	#[test]
+	fn it_builds_raw_chain_specs() {
+		let storage = BTreeMap::from([(CODE_KEY.to_vec(), vec![0x00, 0x61])]);
+		let spec = raw_chain_spec("westend", "local_testnet", storage);
+		assert_eq!("westend_local_testnet", spec["id"]);
+		assert_eq!("0x0061", spec["genesis"]["raw"]["top"]["0x3a636f6465"]);
+	}
+}
@@ -0,0 +1,235 @@
+use serde::Serialize;
+use sp_core::hashing::{blake2_256, keccak_256, sha2_256};
+use std::{fmt::Display, str::FromStr};
+
+use crate::error::*;
+
+/// The hashes a runtime can be identified with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashKind {
+	Blake2_256,
+	Sha256,
+	Keccak256,
+
+	/// The `ValidationCodeHash` the relay chain knows a parachain runtime by
+	ValidationCode,
+}
+
+impl HashKind {
+	pub const ALL: [HashKind; 4] =
+		[HashKind::Blake2_256, HashKind::Sha256, HashKind::Keccak256, HashKind::ValidationCode];
+
+	/// Hash `bytes`, the runtime as stored for a [HashKind::ValidationCode]
+	pub fn hash(&self, bytes: &[u8]) -> [u8; 32] {
+		match self {
+			HashKind::Blake2_256 | HashKind::ValidationCode => blake2_256(bytes),
+			HashKind::Sha256 => sha2_256(bytes),
+			HashKind::Keccak256 => keccak_256(bytes),
+		}
+	}
+}
+
+impl Display for HashKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			HashKind::Blake2_256 => write!(f, "blake2_256"),
+			HashKind::Sha256 => write!(f, "sha256"),
+			HashKind::Keccak256 => write!(f, "keccak_256"),
+			HashKind::ValidationCode => write!(f, "validation_code_hash"),
+		}
+	}
+}
+
+impl FromStr for HashKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_lowercase().replace('-', "_").as_str() {
+			"blake2_256" | "blake2" => Ok(Self::Blake2_256),
+			"sha256" | "sha2_256" => Ok(Self::Sha256),
+			"keccak_256" | "keccak256" | "keccak" => Ok(Self::Keccak256),
+			"validation_code_hash" | "validation_code" => Ok(Self::ValidationCode),
+			_ => Err(format!("Unknown hash `{s}`, expected blake2_256, sha256, keccak_256 or validation_code_hash")),
+		}
+	}
+}
+
+/// A hash the runtime must have, given as `<algo>:<hex>` such as `sha256:0x1234...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedHash {
+	pub kind: HashKind,
+	pub hash: [u8; 32],
+}
+
+impl ExpectedHash {
+	/// Fail with [SubwasmLibError::HashMismatch] unless `bytes`, the runtime as stored, have the expected hash
+	pub fn check(&self, bytes: &[u8]) -> Result<()> {
+		let actual = self.kind.hash(bytes);
+		if actual == self.hash {
+			Ok(())
+		} else {
+			Err(SubwasmLibError::HashMismatch(
+				self.kind.to_string(),
+				format!("0x{}", hex::encode(self.hash)),
+				format!("0x{}", hex::encode(actual)),
+			))
+		}
+	}
+}
+
+impl FromStr for ExpectedHash {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		let (kind, hash) = s.split_once(':').ok_or_else(|| format!("Expected `<algo>:<hex>`, got `{s}`"))?;
+		let hash = hex::decode(hash.trim_start_matches("0x"))
+			.ok()
+			.and_then(|hash| <[u8; 32]>::try_from(hash).ok())
+			.ok_or_else(|| format!("Expected a 32 bytes hex hash, got `{hash}`"))?;
+		Ok(Self { kind: kind.parse()?, hash })
+	}
+}
+
+/// Hashes of a wasm blob
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BlobHashes {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub blake2_256: Option<String>,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sha256: Option<String>,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub keccak_256: Option<String>,
+}
+
+impl BlobHashes {
+	pub fn new(bytes: &[u8]) -> Self {
+		let hex = |hash: [u8; 32]| Some(format!("0x{}", hex::encode(hash)));
+		Self { blake2_256: hex(blake2_256(bytes)), sha256: hex(sha2_256(bytes)), keccak_256: hex(keccak_256(bytes)) }
+	}
+
+	fn filter(&self, kinds: &[HashKind]) -> Self {
+		let keep = |kind, hash: &Option<String>| if kinds.contains(&kind) { hash.clone() } else { None };
+		Self {
+			blake2_256: keep(HashKind::Blake2_256, &self.blake2_256),
+			sha256: keep(HashKind::Sha256, &self.sha256),
+			keccak_256: keep(HashKind::Keccak256, &self.keccak_256),
+		}
+	}
+
+	fn entries(&self) -> Vec<(&'static str, &String)> {
+		[("blake2_256", &self.blake2_256), ("sha256", &self.sha256), ("keccak_256", &self.keccak_256)]
+			.into_iter()
+			.filter_map(|(name, hash)| Some((name, hash.as_ref()?)))
+			.collect()
+	}
+}
+
+/// Hashes of a runtime, for the blob as stored and once decompressed, so the two are never confused
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuntimeHashes {
+	/// Hashes of the compressed runtime, if it is compressed
+	pub compressed: Option<BlobHashes>,
+
+	/// Hashes of the decompressed runtime, which is the runtime itself if it is not compressed
+	pub decompressed: BlobHashes,
+
+	/// The blake2-256 hash of the runtime as stored: what the relay chain uses as `ValidationCodeHash`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub validation_code_hash: Option<String>,
+}
+
+impl RuntimeHashes {
+	/// Hash the runtime as stored, `raw`, and `decompressed`
+	pub fn new(raw: &[u8], decompressed: &[u8], compressed: bool) -> Self {
+		let raw_hashes = BlobHashes::new(raw);
+		Self {
+			validation_code_hash: raw_hashes.blake2_256.clone(),
+			decompressed: if compressed { BlobHashes::new(decompressed) } else { raw_hashes.clone() },
+			compressed: compressed.then_some(raw_hashes),
+		}
+	}
+
+	/// Only keep the hashes of the given kinds
+	pub fn filter(&self, kinds: &[HashKind]) -> Self {
+		Self {
+			compressed: self.compressed.as_ref().map(|hashes| hashes.filter(kinds)),
+			decompressed: self.decompressed.filter(kinds),
+			validation_code_hash: self
+				.validation_code_hash
+				.clone()
+				.filter(|_| kinds.contains(&HashKind::ValidationCode)),
+		}
+	}
+}
+
+impl Display for RuntimeHashes {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if let Some(compressed) = &self.compressed {
+			for (name, hash) in compressed.entries() {
+				writeln!(f, "{:<30} {hash}", format!("{name} (compressed):"))?;
+			}
+		}
+		for (name, hash) in self.decompressed.entries() {
+			writeln!(f, "{:<30} {hash}", format!("{name} (decompressed):"))?;
+		}
+		if let Some(hash) = &self.validation_code_hash {
+			writeln!(f, "{:<30} {hash}", "validation_code_hash:")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests_hashes {
+	use super::*;
+
+	#[test]
+	fn it_hashes_both_blobs() {
+		let hashes = RuntimeHashes::new(b"compressed", b"abc", true);
+		assert_eq!(Some(BlobHashes::new(b"compressed")), hashes.compressed);
+		assert_eq!(
+			Some("0xba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+			hashes.decompressed.sha256.as_deref()
+		);
+		assert_eq!(
+			Some("0x4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+			hashes.decompressed.keccak_256.as_deref()
+		);
+		assert_eq!(hashes.compressed.and_then(|hashes| hashes.blake2_256), hashes.validation_code_hash);
+
+		let hashes = RuntimeHashes::new(b"abc", b"abc", false);
+		assert_eq!(None, hashes.compressed);
+		assert_eq!(hashes.decompressed.blake2_256, hashes.validation_code_hash);
+	}
+
+	#[test]
+	fn it_filters_hashes() {
+		let hashes = RuntimeHashes::new(b"compressed", b"abc", true).filter(&[HashKind::Sha256]);
+		assert_eq!(None, hashes.validation_code_hash);
+		assert_eq!(None, hashes.decompressed.blake2_256);
+		assert_eq!(
+			"sha256 (compressed):",
+			hashes.to_string().lines().next().and_then(|line| line.split("  ").next()).unwrap_or_default()
+		);
+		assert_eq!(2, hashes.to_string().lines().count());
+		assert_eq!(Ok(HashKind::Keccak256), "keccak-256".parse());
+	}
+
+	#[test]
+	fn it_checks_expected_hashes() {
+		let expected: ExpectedHash = "sha256:0xba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+			.parse()
+			.expect("Failed parsing the hash");
+		assert_eq!(HashKind::Sha256, expected.kind);
+		assert!(expected.check(b"abc").is_ok());
+		assert!(matches!(expected.check(b"abd"), Err(SubwasmLibError::HashMismatch(..))));
+
+		assert!("sha256".parse::<ExpectedHash>().is_err());
+		assert!("sha256:0x1234".parse::<ExpectedHash>().is_err());
+		assert!("md5:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+			.parse::<ExpectedHash>()
+			.is_err());
+	}
+}
@@ -0,0 +1,85 @@
+use std::{fs::File, path::PathBuf, str::FromStr};
+use wasm_loader::{HostFunction, HostFunctionsCheck, Source as WasmLoaderSource, WasmLoader};
+use wasm_testbed::WasmTestBed;
+
+use crate::{error::*, source::Source};
+
+/// A set of host functions the imports of a runtime can be checked against.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostFunctionSet {
+	/// The host functions of `sp_io`, as built into subwasm
+	SpIo,
+
+	/// A json file listing the host functions of a node release, in the
+	/// same format as the output of `subwasm hostfns --json`
+	Json(PathBuf),
+
+	/// The host functions imported by another runtime, usually the previous one
+	Runtime(Source),
+}
+
+impl FromStr for HostFunctionSet {
+	type Err = SubwasmLibError;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			"sp-io" | "sp_io" | "builtin" => Ok(Self::SpIo),
+			s if s.ends_with(".json") => Ok(Self::Json(PathBuf::from(s))),
+			s => Ok(Self::Runtime(Source::try_from(s)?)),
+		}
+	}
+}
+
+impl HostFunctionSet {
+	/// Load the list of host functions of this set
+	pub fn load(&self) -> Result<Vec<HostFunction>> {
+		match self {
+			Self::SpIo => Ok(WasmTestBed::builtin_host_functions()),
+			Self::Json(path) => {
+				let file = File::open(path)?;
+				serde_json::from_reader(file)
+					.map_err(|e| SubwasmLibError::Parsing(path.display().to_string(), e.to_string()))
+			}
+			Self::Runtime(source) => host_functions(&WasmLoaderSource::File(source.as_file()?)),
+		}
+	}
+}
+
+/// List the host functions imported by a runtime.
+///
+/// This only parses the wasm, the runtime is not instantiated.
+pub fn host_functions(source: &WasmLoaderSource) -> Result<Vec<HostFunction>> {
+	let loader = WasmLoader::load_from_source(source)?;
+	loader.host_functions().map_err(|e| SubwasmLibError::Generic(e.to_string()))
+}
+
+/// Check the host functions imported by a runtime against a given set.
+pub fn check_host_functions(source: &WasmLoaderSource, against: &HostFunctionSet) -> Result<HostFunctionsCheck> {
+	let imported = host_functions(source)?;
+	let provided = against.load()?;
+	Ok(HostFunctionsCheck::new(&imported, &provided))
+}
+
+#[cfg(test)]
+mod tests_host_functions {
+	use super::*;
+
+	#[test]
+	fn it_parses_host_function_sets() {
+		assert_eq!(HostFunctionSet::SpIo, HostFunctionSet::from_str("sp-io").expect("Failed parsing"));
+		assert_eq!(
+			HostFunctionSet::Json("node.json".into()),
+			HostFunctionSet::from_str("node.json").expect("Failed parsing")
+		);
+		assert_eq!(
+			HostFunctionSet::Runtime(Source::Alias("polkadot".into())),
+			HostFunctionSet::from_str("polkadot").expect("Failed parsing")
+		);
+	}
+
+	#[test]
+	fn it_loads_the_builtin_set() {
+		let functions = HostFunctionSet::SpIo.load().expect("Failed loading host functions");
+		assert!(functions.iter().any(|f| f.name == "ext_storage_get_version_1"));
+	}
+}
@@ -0,0 +1,366 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{debug, info, warn};
+use sp_core::{ed25519, hashing::blake2_512, Pair};
+use std::{fs, path::PathBuf, process::Command, str::FromStr};
+use url::Url;
+
+use crate::{error::*, fetch_at_url, get_output_file_tmp, ExpectedHash};
+
+/// Files a release may publish next to its runtimes, listing `<sha256>  <file name>` lines
+const CHECKSUM_FILES: [&str; 2] = ["SHA256SUMS", "sha256sums.txt"];
+
+/// How to check the integrity of a runtime before using it.
+///
+/// A downloaded runtime is always checked against the sha256 checksums and the `<url>.asc` gpg signature
+/// published along with it, when there are some. The options below make them required.
+#[derive(Debug, Clone, Default)]
+pub struct Integrity {
+	/// Hashes the runtime, as stored, must have
+	pub expected: Vec<ExpectedHash>,
+
+	/// When set, a downloaded runtime must come with a sha256 checksum,
+	/// `<url>.sha256` or a line of a checksum file of the release such as `SHA256SUMS`
+	pub checksums: bool,
+
+	/// The public key of the release. When set, a downloaded runtime must come with a valid `<url>.minisig`
+	pub minisign_key: Option<MinisignKey>,
+
+	/// When set, a downloaded runtime must come with a detached signature, `<url>.asc` or `<url>.sig`,
+	/// that `gpg` verifies with the keyring of the user
+	pub gpg: bool,
+}
+
+impl Integrity {
+	/// Check the expected hashes of a runtime, `bytes` being the runtime as stored
+	pub fn check(&self, bytes: &[u8]) -> Result<()> {
+		self.expected.iter().try_for_each(|expected| expected.check(bytes))
+	}
+
+	/// Check a runtime downloaded from `url`: the expected hashes, and the checksums and signatures published
+	/// along with the runtime. Those are checked whenever they exist and must exist when required.
+	pub fn check_download(&self, url: &Url, bytes: &[u8]) -> Result<()> {
+		self.check(bytes)?;
+
+		let checksums = release_checksums(url)?;
+		if self.checksums && checksums.is_empty() {
+			return Err(SubwasmLibError::NotFound(format!("No sha256 checksum published for {url}")));
+		}
+		for (checksum_url, expected) in checksums {
+			expected.check(bytes)?;
+			info!("✅ The runtime matches the checksum published at {checksum_url}");
+		}
+
+		let signature_url = sibling(url, ".minisig")?;
+		match (&self.minisign_key, fetch_optional(&signature_url)) {
+			(Some(key), Some(signature)) => {
+				key.verify(bytes, &String::from_utf8_lossy(&signature))?;
+				info!("✅ The runtime matches the minisign signature published at {signature_url}");
+			}
+			(Some(_), None) => {
+				return Err(SubwasmLibError::InvalidSignature(format!("No signature found at {signature_url}")))
+			}
+			(None, Some(_)) => {
+				warn!("The minisign signature at {signature_url} needs the key of the release to be checked")
+			}
+			(None, None) => {}
+		}
+
+		if self.gpg {
+			ensure_gpg()?;
+		}
+		// Only `.asc` is gpg for sure, a `.sig` file may come from another tool
+		let extensions: &[&str] = if self.gpg { &[".asc", ".sig"] } else { &[".asc"] };
+		let mut signature = None;
+		for extension in extensions {
+			let signature_url = sibling(url, extension)?;
+			if let Some(data) = fetch_optional(&signature_url) {
+				signature = Some((signature_url, data));
+				break;
+			}
+		}
+		match signature {
+			Some((signature_url, signature)) => {
+				if !self.gpg && !gpg_installed() {
+					warn!("A gpg signature is published at {signature_url} but gpg is not installed to check it");
+					return Ok(());
+				}
+				match verify_gpg(bytes, &signature)? {
+					GpgCheck::Valid => info!("✅ The runtime matches the gpg signature published at {signature_url}"),
+					GpgCheck::Unchecked(reason) if self.gpg => return Err(SubwasmLibError::InvalidSignature(reason)),
+					GpgCheck::Unchecked(reason) => {
+						warn!("The gpg signature published at {signature_url} could not be checked: {reason}")
+					}
+				}
+			}
+			None if self.gpg => {
+				return Err(SubwasmLibError::InvalidSignature(format!("No gpg signature found for {url}")))
+			}
+			None => {}
+		}
+		Ok(())
+	}
+}
+
+/// Fetch the runtime at `url` into `target`, or a tmp file if `None`, and check its integrity.
+/// The file is removed if the check fails.
+pub fn fetch_verified(url: Url, target: Option<PathBuf>, integrity: &Integrity) -> Result<PathBuf> {
+	let path = fetch_at_url(url.clone(), target)?;
+	let checked =
+		fs::read(&path).map_err(|_e| SubwasmLibError::Io).and_then(|bytes| integrity.check_download(&url, &bytes));
+	if let Err(e) = checked {
+		let _ = fs::remove_file(&path);
+		return Err(e);
+	}
+	Ok(path)
+}
+
+/// The sha256 checksums of the runtime at `url` published with it: `<url>.sha256` or a line of a
+/// checksum file of the release, such as `SHA256SUMS`
+fn release_checksums(url: &Url) -> Result<Vec<(Url, ExpectedHash)>> {
+	let name = url.path_segments().and_then(|mut segments| segments.next_back()).unwrap_or_default().to_string();
+	let parse = |checksum_url: &Url, hash: &str| {
+		format!("sha256:{hash}")
+			.parse::<ExpectedHash>()
+			.map_err(|e| SubwasmLibError::Parsing(checksum_url.to_string(), format!(" The checksum is invalid: {e}")))
+	};
+
+	let mut checksums = Vec::new();
+	let sidecar = sibling(url, ".sha256")?;
+	if let Some(content) = fetch_optional(&sidecar) {
+		let content = String::from_utf8_lossy(&content);
+		let hash = content.split_whitespace().next().unwrap_or_default();
+		checksums.push((sidecar.clone(), parse(&sidecar, hash)?));
+	}
+
+	for file in CHECKSUM_FILES {
+		let checksum_url = url.join(file)?;
+		let Some(content) = fetch_optional(&checksum_url) else {
+			continue;
+		};
+		// `sha256sum` marks the files hashed in binary mode with a `*`
+		let hash = String::from_utf8_lossy(&content).lines().find_map(|line| {
+			let (hash, file) = line.split_once(char::is_whitespace)?;
+			(file.trim().trim_start_matches('*') == name).then(|| hash.to_string())
+		});
+		match hash {
+			Some(hash) => checksums.push((checksum_url.clone(), parse(&checksum_url, &hash)?)),
+			None => debug!("{checksum_url} has no checksum for {name}"),
+		}
+	}
+	Ok(checksums)
+}
+
+/// The url of a file published next to the file at `url`, such as `<url>.sha256`
+fn sibling(url: &Url, extension: &str) -> Result<Url> {
+	Ok(Url::parse(&format!("{url}{extension}"))?)
+}
+
+/// Fetch the file at `url`, `None` if the server does not have it or cannot be reached
+fn fetch_optional(url: &Url) -> Option<Vec<u8>> {
+	debug!("Fetching {url}");
+	let fetched =
+		reqwest::blocking::get(url.to_owned()).and_then(|resp| resp.error_for_status()).and_then(|resp| resp.bytes());
+	match fetched {
+		Ok(bytes) => Some(bytes.to_vec()),
+		Err(e) => {
+			debug!("Could not fetch {url}: {e}");
+			None
+		}
+	}
+}
+
+/// Whether the `gpg` binary can be run
+fn gpg_installed() -> bool {
+	Command::new("gpg").arg("--version").output().is_ok_and(|output| output.status.success())
+}
+
+/// Fail with a clear error unless the `gpg` binary can be run
+fn ensure_gpg() -> Result<()> {
+	match gpg_installed() {
+		true => Ok(()),
+		false => Err(SubwasmLibError::InvalidSignature(
+			"Checking the gpg signature requires `gpg`, which could not be found in your PATH".to_string(),
+		)),
+	}
+}
+
+/// Outcome of checking a gpg signature that is not bad
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GpgCheck {
+	Valid,
+
+	/// The signature could not be checked, for instance without the public key in the keyring
+	Unchecked(String),
+}
+
+/// Check a detached gpg `signature` of `bytes` with the `gpg` binary and the keyring of the user.
+/// Fails if the signature does not match, a signature made with a key missing from the keyring is unchecked.
+fn verify_gpg(bytes: &[u8], signature: &[u8]) -> Result<GpgCheck> {
+	let data = get_output_file_tmp()?;
+	let signature_file = data.with_extension("wasm.sig");
+	fs::write(&data, bytes)?;
+	fs::write(&signature_file, signature)?;
+
+	let output =
+		Command::new("gpg").args(["--batch", "--status-fd", "1", "--verify"]).arg(&signature_file).arg(&data).output();
+	let _ = fs::remove_file(&data);
+	let _ = fs::remove_file(&signature_file);
+
+	let output = output.map_err(|e| SubwasmLibError::InvalidSignature(format!("Cannot run gpg: {e}")))?;
+	gpg_check(output.status.success(), &String::from_utf8_lossy(&output.stdout), &output.stderr)
+}
+
+/// Interpret the machine readable `status` output of `gpg --verify`
+fn gpg_check(success: bool, status: &str, stderr: &[u8]) -> Result<GpgCheck> {
+	let reason = || String::from_utf8_lossy(stderr).trim().to_string();
+	let has = |keyword: &str| status.lines().any(|line| line.starts_with(&format!("[GNUPG:] {keyword} ")));
+
+	if has("BADSIG") {
+		Err(SubwasmLibError::InvalidSignature(reason()))
+	} else if success && has("GOODSIG") {
+		Ok(GpgCheck::Valid)
+	} else {
+		Ok(GpgCheck::Unchecked(reason()))
+	}
+}
+
+/// A minisign public key, as found on the last line of the `.pub` file of the key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinisignKey {
+	key_id: [u8; 8],
+	public: [u8; 32],
+}
+
+impl FromStr for MinisignKey {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		let encoded = s.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or_default();
+		let decoded = STANDARD.decode(encoded).map_err(|e| format!("Invalid minisign public key: {e}"))?;
+		match decoded.as_slice() {
+			[b'E', b'd', rest @ ..] if rest.len() == 40 => Ok(Self {
+				key_id: rest[..8].try_into().map_err(|_| "Invalid key id")?,
+				public: rest[8..].try_into().map_err(|_| "Invalid public key")?,
+			}),
+			_ => Err("Invalid minisign public key, expected an Ed25519 key".into()),
+		}
+	}
+}
+
+impl MinisignKey {
+	/// Verify the minisign `signature` of `bytes`, the content of a `.minisig` file
+	pub fn verify(&self, bytes: &[u8], signature: &str) -> Result<()> {
+		let invalid = |reason: &str| SubwasmLibError::InvalidSignature(reason.to_string());
+		let mut lines = signature.lines().map(str::trim).filter(|line| !line.is_empty());
+		let (Some(_untrusted), Some(encoded), Some(trusted), Some(global)) =
+			(lines.next(), lines.next(), lines.next(), lines.next())
+		else {
+			return Err(invalid("The minisign signature is truncated"));
+		};
+		let trusted = trusted
+			.strip_prefix("trusted comment: ")
+			.ok_or_else(|| invalid("The minisign signature has no trusted comment"))?;
+
+		let decoded = STANDARD.decode(encoded).map_err(|_| invalid("The minisign signature is not valid base64"))?;
+		if decoded.len() != 74 {
+			return Err(invalid("The minisign signature has an unexpected length"));
+		}
+		let (algorithm, key_id, signature) = (&decoded[..2], &decoded[2..10], &decoded[10..]);
+		if key_id != self.key_id {
+			return Err(invalid("The runtime was signed with another key"));
+		}
+		// Signatures of large files are computed over their blake2b-512 hash
+		let message = match algorithm {
+			b"Ed" => bytes.to_vec(),
+			b"ED" => blake2_512(bytes).to_vec(),
+			_ => return Err(invalid("Unknown minisign signature algorithm")),
+		};
+		let global =
+			STANDARD.decode(global).map_err(|_| invalid("The minisign global signature is not valid base64"))?;
+
+		if !self.verify_ed25519(signature, &message) {
+			return Err(invalid("The minisign signature does not match the runtime"));
+		}
+		if !self.verify_ed25519(&global, &[signature, trusted.as_bytes()].concat()) {
+			return Err(invalid("The trusted comment of the minisign signature was tampered with"));
+		}
+		Ok(())
+	}
+
+	fn verify_ed25519(&self, signature: &[u8], message: &[u8]) -> bool {
+		let Ok(signature) = <[u8; 64]>::try_from(signature) else {
+			return false;
+		};
+		ed25519::Pair::verify(
+			&ed25519::Signature::from_raw(signature),
+			message,
+			&ed25519::Public::from_raw(self.public),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests_integrity {
+	use super::*;
+
+	const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+	fn key() -> (ed25519::Pair, MinisignKey) {
+		let pair = ed25519::Pair::from_seed(&[42; 32]);
+		let public = STANDARD.encode([&b"Ed"[..], &KEY_ID, &pair.public().0].concat());
+		let key = format!("untrusted comment: minisign public key\n{public}\n").parse().expect("Invalid key");
+		(pair, key)
+	}
+
+	/// Sign `bytes` the way `minisign -S` does
+	fn sign(pair: &ed25519::Pair, bytes: &[u8], trusted: &str) -> String {
+		let signature = pair.sign(&blake2_512(bytes)).0;
+		let global = pair.sign(&[&signature[..], trusted.as_bytes()].concat()).0;
+		format!(
+			"untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {trusted}\n{}\n",
+			STANDARD.encode([&b"ED"[..], &KEY_ID, &signature].concat()),
+			STANDARD.encode(global)
+		)
+	}
+
+	#[test]
+	fn it_verifies_minisign_signatures() {
+		let (pair, key) = key();
+		let signature = sign(&pair, b"runtime", "timestamp:1700000000");
+
+		assert!(key.verify(b"runtime", &signature).is_ok());
+		assert!(key.verify(b"another runtime", &signature).is_err());
+		assert!(key.verify(b"runtime", &signature.replace("timestamp:1700000000", "timestamp:0")).is_err());
+		assert!(key.verify(b"runtime", "untrusted comment: truncated").is_err());
+
+		let other = ed25519::Pair::from_seed(&[7; 32]);
+		assert!(key.verify(b"runtime", &sign(&other, b"runtime", "timestamp:1700000000")).is_err());
+	}
+
+	#[test]
+	fn it_parses_minisign_keys() {
+		let (pair, key) = key();
+		assert_eq!(pair.public().0, key.public);
+		assert!("RWQ=".parse::<MinisignKey>().is_err());
+	}
+
+	#[test]
+	fn it_reads_the_gpg_status() {
+		assert_eq!(GpgCheck::Valid, gpg_check(true, "[GNUPG:] GOODSIG 0123456789ABCDEF Release\n", b"").unwrap());
+		assert!(gpg_check(false, "[GNUPG:] BADSIG 0123456789ABCDEF Release\n", b"BAD signature").is_err());
+		assert_eq!(
+			GpgCheck::Unchecked("No public key".to_string()),
+			gpg_check(false, "[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n", b"No public key").unwrap()
+		);
+	}
+
+	#[test]
+	fn it_builds_sibling_urls() {
+		let url = Url::parse("https://example.com/v1.0.0/runtime.compact.compressed.wasm").expect("Invalid url");
+		assert_eq!(
+			"https://example.com/v1.0.0/runtime.compact.compressed.wasm.sha256",
+			sibling(&url, ".sha256").expect("Invalid sibling").as_str()
+		);
+		assert_eq!("https://example.com/v1.0.0/SHA256SUMS", url.join(CHECKSUM_FILES[0]).expect("Invalid url").as_str());
+	}
+}
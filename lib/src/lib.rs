@@ -3,16 +3,31 @@
 pub mod error;
 pub mod source;
 
+mod bench;
+mod bloat;
+mod call;
+mod can_upgrade;
 mod chain_info;
 mod chain_urls;
 mod convert;
+mod digest;
+mod genesis;
 mod github_ref;
+mod hashes;
+mod host_functions;
+mod integrity;
 mod macros;
 mod metadata_wrapper;
+mod preimage;
 mod runtime_info;
+mod smoke;
+mod strip;
 mod subwasm;
 mod types;
+mod upgrade_check;
 mod utils;
+mod verify;
+mod xcm_upgrade;
 
 use std::{fs::File, io::prelude::*, path::PathBuf, str::FromStr};
 use substrate_differ::differs::reduced::{reduced_diff_result::ReducedDiffResult, reduced_runtime::ReducedRuntime};
@@ -20,15 +35,38 @@ use url::Url;
 use wasm_loader::{BlockRef, Compression, NodeEndpoint, OnchainBlock, Source, WasmLoader};
 use wasm_testbed::WasmTestBed;
 
+pub use bench::*;
+pub use bloat::*;
+pub use call::*;
+pub use can_upgrade::*;
 pub use chain_info::*;
+pub use digest::*;
 pub use error::*;
+pub use genesis::*;
 pub use github_ref::*;
+pub use hashes::*;
+pub use host_functions::*;
+pub use integrity::*;
 pub use metadata_wrapper::OutputFormat;
+pub use preimage::*;
 pub use runtime_info::*;
+pub use smoke::*;
+pub use strip::*;
 pub use substrate_differ::differs::diff_method::DiffMethod;
 pub use subwasm::*;
 pub use types::*;
+pub use upgrade_check::*;
 pub use utils::*;
+pub use verify::*;
+pub use wasm_loader::{
+	DetectedFeature, FunctionDiff, HostFunction, HostFunctionsCheck, ProposalUse, RuntimeFeature, WasmComparison,
+	WasmProposal, WasmTarget,
+};
+pub use wasm_testbed::{
+	AllocatorStats, ExecutionMethod, HeapStrategy, HostCall, RuntimeLog, StateFormat, StateSnapshot, WasmTestBedConfig,
+	DEFAULT_EXTRA_PAGES,
+};
+pub use xcm_upgrade::*;
 
 /// Returns Some node url if possible, None otherwise.
 fn get_node_url(chain: &str) -> Result<Url> {
@@ -83,19 +121,28 @@ pub fn download_runtime(
 }
 
 /// Compute the diff of 2 runtimes
-pub fn reduced_diff(src_a: Source, src_b: Source) -> Result<ReducedDiffResult> {
+pub fn reduced_diff(src_a: Source, src_b: Source, config: &WasmTestBedConfig) -> Result<ReducedDiffResult> {
 	log::debug!("REDUCED: Loading WASM runtimes:");
 	log::info!("  🅰️  {:?}", src_a);
-	let runtime_a = WasmTestBed::new(&src_a)?;
+	let runtime_a = WasmTestBed::with_config(&src_a, config)?;
 	log::info!("  🅱️  {:?}", src_b);
-	let runtime_b = WasmTestBed::new(&src_b)?;
+	let runtime_b = WasmTestBed::with_config(&src_b, config)?;
 
-	let ra = ReducedRuntime::from(runtime_a.metadata());
-	let rb = ReducedRuntime::from(runtime_b.metadata());
+	let ra = ReducedRuntime::from(runtime_a.metadata()?);
+	let rb = ReducedRuntime::from(runtime_b.metadata()?);
 
 	Ok(ReducedDiffResult::new(ra, rb))
 }
 
+/// Compare 2 runtimes, section by section, after decompressing them
+pub fn compare_runtimes(src_a: &Source, src_b: &Source) -> Result<WasmComparison> {
+	let wasm_a = WasmLoader::load_from_source(src_a)?;
+	let wasm_b = WasmLoader::load_from_source(src_b)?;
+
+	wasm_loader::compare(wasm_a.uncompressed_bytes(), wasm_b.uncompressed_bytes())
+		.map_err(|e| SubwasmLibError::Generic(e.to_string()))
+}
+
 /// Compress a given runtime into a new file. You cannot compress
 /// a runtime that is already compressed.
 pub fn compress(input: PathBuf, output: PathBuf) -> Result<()> {
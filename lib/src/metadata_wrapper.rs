@@ -63,10 +63,8 @@ impl<'a> MetadataWrapper<'a> {
 			}
 
 			OutputFormat::Scale if filter.is_none() => {
-				match out.write_all(&self.runtime_metadata_prefixed().encode()) {
-					Ok(_) => {}
-					Err(_) => {} // Silence broken pipe errors
-				};
+				// Silence broken pipe errors
+				let _ = out.write_all(&self.runtime_metadata_prefixed().encode());
 			}
 
 			OutputFormat::HexScale if filter.is_none() => {
@@ -99,14 +97,14 @@ impl<'a> MetadataWrapper<'a> {
 		match self.runtime_metadata() {
 			RuntimeMetadata::V12(v12) => {
 				let mut modules = convert(&v12.modules).clone();
-				modules.sort_by(|a, b| a.index.cmp(&b.index));
+				modules.sort_by_key(|a| a.index);
 				modules.iter().try_for_each(|module| -> std::io::Result<()> {
 					writeln!(out, " - {:02}: {}", module.index, convert(&module.name))
 				})?;
 			}
 			RuntimeMetadata::V13(v13) => {
 				let mut modules = convert(&v13.modules).clone();
-				modules.sort_by(|a, b| a.index.cmp(&b.index));
+				modules.sort_by_key(|a| a.index);
 				modules.iter().try_for_each(|module| -> std::io::Result<()> {
 					writeln!(out, " - {:02}: {}", module.index, convert(&module.name))
 				})?;
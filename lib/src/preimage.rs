@@ -0,0 +1,288 @@
+use frame_metadata::RuntimeMetadata;
+use num_format::{Locale, ToFormattedString};
+use scale::{Compact, Encode};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde::Serialize;
+use sp_core::blake2_256;
+use std::{fmt::Display, str::FromStr};
+use substrate_runtime_proposal_hash::{call_info, encode_call, get_preimage_hash, CallInfo};
+use wasm_testbed::WasmTestBed;
+
+use crate::error::*;
+
+/// The calls upgrading the runtime of a chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeCall {
+	/// `System::set_code(code)`
+	SetCode,
+
+	/// `System::authorize_upgrade(code_hash)`
+	AuthorizeUpgrade,
+
+	/// `System::authorize_upgrade_without_checks(code_hash)`
+	AuthorizeUpgradeWithoutChecks,
+
+	/// `System::apply_authorized_upgrade(code)`
+	ApplyAuthorizedUpgrade,
+
+	/// `ParachainSystem::authorize_upgrade(code_hash, check_version)`
+	ParachainAuthorizeUpgrade,
+}
+
+impl UpgradeCall {
+	pub(crate) const ALL: [Self; 5] = [
+		Self::SetCode,
+		Self::AuthorizeUpgrade,
+		Self::AuthorizeUpgradeWithoutChecks,
+		Self::ApplyAuthorizedUpgrade,
+		Self::ParachainAuthorizeUpgrade,
+	];
+
+	pub(crate) fn pallet_and_call(&self) -> (&'static str, &'static str) {
+		match self {
+			Self::SetCode => ("System", "set_code"),
+			Self::AuthorizeUpgrade => ("System", "authorize_upgrade"),
+			Self::AuthorizeUpgradeWithoutChecks => ("System", "authorize_upgrade_without_checks"),
+			Self::ApplyAuthorizedUpgrade => ("System", "apply_authorized_upgrade"),
+			Self::ParachainAuthorizeUpgrade => ("ParachainSystem", "authorize_upgrade"),
+		}
+	}
+}
+
+impl FromStr for UpgradeCall {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"set_code" => Ok(Self::SetCode),
+			"authorize_upgrade" => Ok(Self::AuthorizeUpgrade),
+			"authorize_upgrade_without_checks" => Ok(Self::AuthorizeUpgradeWithoutChecks),
+			"apply_authorized_upgrade" => Ok(Self::ApplyAuthorizedUpgrade),
+			"parachain_authorize_upgrade" => Ok(Self::ParachainAuthorizeUpgrade),
+			_ => Err(format!(
+				"Unknown call `{s}`, expected set_code, authorize_upgrade, authorize_upgrade_without_checks, apply_authorized_upgrade or parachain_authorize_upgrade"
+			)),
+		}
+	}
+}
+
+impl Display for UpgradeCall {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let (pallet, call) = self.pallet_and_call();
+		write!(f, "{pallet}.{call}")
+	}
+}
+
+/// The calls a governance call can be wrapped in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallWrapper {
+	/// `Whitelist::dispatch_whitelisted_call_with_preimage(call)`
+	Whitelist,
+
+	/// `Sudo::sudo_unchecked_weight(call, weight)`, with a zero weight. `System::set_code` declares the
+	/// whole block weight, so a plain `Sudo::sudo` would be rejected for exhausting the resources.
+	Sudo,
+
+	/// `Utility::batch_all(calls)`, with the call as the only one of the batch
+	BatchAll,
+}
+
+impl CallWrapper {
+	fn pallet_and_call(&self) -> (&'static str, &'static str) {
+		match self {
+			Self::Whitelist => ("Whitelist", "dispatch_whitelisted_call_with_preimage"),
+			Self::Sudo => ("Sudo", "sudo_unchecked_weight"),
+			Self::BatchAll => ("Utility", "batch_all"),
+		}
+	}
+}
+
+impl FromStr for CallWrapper {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"whitelist" => Ok(Self::Whitelist),
+			"sudo" => Ok(Self::Sudo),
+			"batch_all" => Ok(Self::BatchAll),
+			_ => Err(format!("Unknown wrapper `{s}`, expected whitelist, sudo or batch_all")),
+		}
+	}
+}
+
+/// An encoded call upgrading the runtime, ready to be noted with `Preimage::note_preimage`
+/// and referenced as `Lookup { hash, len }`
+#[derive(Debug, Clone, Serialize)]
+pub struct Preimage {
+	/// The call, such as `Sudo.sudo_unchecked_weight(System.set_code)`
+	pub call: String,
+
+	/// Hash of the encoded call
+	pub hash: String,
+
+	/// Length of the encoded call, in bytes
+	pub len: usize,
+
+	/// The encoded call
+	#[serde(skip)]
+	pub bytes: Vec<u8>,
+}
+
+impl Preimage {
+	/// Encode `call` for the runtime of `testbed`, then wrap it in the `wrappers`, innermost first.
+	/// The indices come from `chain`, the metadata of the runtime executing the call, or the metadata of the
+	/// runtime of `testbed` if not given.
+	pub fn new(
+		testbed: &WasmTestBed,
+		chain: Option<&RuntimeMetadata>,
+		call: UpgradeCall,
+		wrappers: &[CallWrapper],
+	) -> Result<Self> {
+		let metadata = match chain {
+			Some(metadata) => metadata,
+			None => testbed.metadata()?,
+		};
+		let code = testbed.raw_bytes();
+
+		let bytes = match call {
+			UpgradeCall::SetCode | UpgradeCall::ApplyAuthorizedUpgrade => {
+				encode_call(find(metadata, call.pallet_and_call())?.prefix, &code.encode())
+			}
+			UpgradeCall::AuthorizeUpgrade | UpgradeCall::AuthorizeUpgradeWithoutChecks => {
+				encode_call(find(metadata, call.pallet_and_call())?.prefix, &blake2_256(code))
+			}
+			// The env variables may override the indices and the signature, as for the hash shown by `info`
+			UpgradeCall::ParachainAuthorizeUpgrade => {
+				let authorize_upgrade = testbed.authorize_upgrade(Some(metadata))?;
+				let mut arguments = blake2_256(code).to_vec();
+				arguments.extend(authorize_upgrade.check_version.map(|check_version| check_version as u8));
+				encode_call(authorize_upgrade.prefix, &arguments)
+			}
+		};
+		let (bytes, description) = wrap(metadata, bytes, call.to_string(), wrappers)?;
+		Self::from_call(description, bytes)
+	}
+
+	/// Hash the encoded `call`, described by `description`
+	pub fn from_call(description: String, bytes: Vec<u8>) -> Result<Self> {
+		let hash = get_preimage_hash(&bytes).map_err(|_| SubwasmLibError::HashError())?;
+		Ok(Self { call: description, hash: format!("0x{}", hex::encode(hash)), len: bytes.len(), bytes })
+	}
+
+	/// The encoded call as hex
+	pub fn hex(&self) -> String {
+		format!("0x{}", hex::encode(&self.bytes))
+	}
+}
+
+/// Wrap the encoded call in the `wrappers`, innermost first, and return it along with its description
+pub(crate) fn wrap(
+	metadata: &RuntimeMetadata,
+	mut bytes: Vec<u8>,
+	mut description: String,
+	wrappers: &[CallWrapper],
+) -> Result<(Vec<u8>, String)> {
+	for wrapper in wrappers {
+		let (pallet, name) = wrapper.pallet_and_call();
+		let info = find(metadata, (pallet, name))?;
+		let types = registry(metadata).ok_or(SubwasmLibError::UnsupportedRuntimeVersion())?;
+
+		// The first argument is the wrapped call, the others are set to zero
+		let mut arguments = match wrapper {
+			CallWrapper::BatchAll => Compact(1u32).encode(),
+			_ => Vec::new(),
+		};
+		arguments.extend(bytes);
+		for ty in info.argument_types.iter().skip(1) {
+			let zero = zero_value(types, *ty)
+				.ok_or_else(|| SubwasmLibError::Generic(format!("Cannot encode the arguments of {pallet}.{name}")))?;
+			arguments.extend(zero);
+		}
+		bytes = encode_call(info.prefix, &arguments);
+		description = format!("{pallet}.{name}({description})");
+	}
+	Ok((bytes, description))
+}
+
+pub(crate) fn find(metadata: &RuntimeMetadata, (pallet, call): (&str, &str)) -> Result<CallInfo> {
+	call_info(metadata, pallet, call).ok_or_else(|| SubwasmLibError::NotFound(format!("{pallet}::{call}")))
+}
+
+pub(crate) fn registry(metadata: &RuntimeMetadata) -> Option<&PortableRegistry> {
+	match metadata {
+		RuntimeMetadata::V14(v14) => Some(&v14.types),
+		RuntimeMetadata::V15(v15) => Some(&v15.types),
+		_ => None,
+	}
+}
+
+/// Encode the zero value of the type `id`: zero numbers, empty collections and the first variant of enums
+pub(crate) fn zero_value(types: &PortableRegistry, id: u32) -> Option<Vec<u8>> {
+	let concat = |ids: &mut dyn Iterator<Item = u32>| -> Option<Vec<u8>> {
+		ids.map(|id| zero_value(types, id)).collect::<Option<Vec<_>>>().map(|values| values.concat())
+	};
+
+	match &types.resolve(id)?.type_def {
+		TypeDef::Composite(def) => concat(&mut def.fields.iter().map(|field| field.ty.id)),
+		TypeDef::Variant(def) => {
+			let variant = def.variants.iter().min_by_key(|variant| variant.index)?;
+			let fields = concat(&mut variant.fields.iter().map(|field| field.ty.id))?;
+			Some([vec![variant.index], fields].concat())
+		}
+		TypeDef::Array(def) => concat(&mut (0..def.len).map(|_| def.type_param.id)),
+		TypeDef::Tuple(def) => concat(&mut def.fields.iter().map(|field| field.id)),
+		TypeDef::Sequence(_) | TypeDef::Compact(_) | TypeDef::BitSequence(_) => Some(vec![0]),
+		TypeDef::Primitive(primitive) => Some(vec![
+			0;
+			match primitive {
+				TypeDefPrimitive::Str => 1,
+				TypeDefPrimitive::Bool | TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => 1,
+				TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 2,
+				TypeDefPrimitive::U32 | TypeDefPrimitive::I32 | TypeDefPrimitive::Char => 4,
+				TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 8,
+				TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => 16,
+				TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => 32,
+			}
+		]),
+	}
+}
+
+impl Display for Preimage {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Call:   {}", self.call)?;
+		writeln!(f, "Hash:   {}", self.hash)?;
+		writeln!(f, "Length: {} bytes", self.len.to_formatted_string(&Locale::en))
+	}
+}
+
+#[cfg(test)]
+mod tests_preimage {
+	use super::*;
+	use scale_info::{meta_type, Registry, TypeInfo};
+
+	#[allow(dead_code)]
+	#[derive(TypeInfo)]
+	struct Weight {
+		ref_time: Compact<u64>,
+		proof_size: Compact<u64>,
+	}
+
+	#[test]
+	fn it_encodes_zero_values() {
+		let mut registry = Registry::new();
+		let weight = registry.register_type(&meta_type::<Weight>()).id;
+		let legacy = registry.register_type(&meta_type::<(u64, Option<u32>)>()).id;
+		let types: PortableRegistry = registry.into();
+
+		assert_eq!(Some(vec![0, 0]), zero_value(&types, weight));
+		assert_eq!(Some(vec![0; 9]), zero_value(&types, legacy));
+	}
+
+	#[test]
+	fn it_parses_calls() {
+		assert_eq!(Ok(UpgradeCall::ApplyAuthorizedUpgrade), "apply_authorized_upgrade".parse());
+		assert_eq!("ParachainSystem.authorize_upgrade", UpgradeCall::ParachainAuthorizeUpgrade.to_string());
+		assert_eq!(Ok(CallWrapper::BatchAll), "batch_all".parse());
+		assert!("sudo_as".parse::<CallWrapper>().is_err());
+	}
+}
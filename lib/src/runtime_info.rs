@@ -1,11 +1,12 @@
-use crate::error::*;
+use crate::{error::*, RuntimeHashes};
+use frame_metadata::RuntimeMetadata;
 use ipfs_hasher::IpfsHasher;
 use num_format::{Locale, ToFormattedString};
 use serde::Serialize;
 use sp_version::RuntimeVersion as SubstrateRuntimeVersion;
 use std::fmt::Display;
-use wasm_loader::Compression;
-use wasm_testbed::{ReservedMeta, WasmTestBed};
+use wasm_loader::{Compression, DetectedFeature, ProposalUse, RuntimeFeature, WasmTarget};
+use wasm_testbed::{AuthorizeUpgrade, ReservedMeta, SystemUpgradeCall, WasmTestBed};
 
 /// Describe the summary information of a runtime.
 ///
@@ -13,7 +14,7 @@ use wasm_testbed::{ReservedMeta, WasmTestBed};
 pub struct RuntimeInfo {
 	/// Size of the runtime, in bytes. Runtimes can be compressed or not. They are
 	/// usually compressed and the size will then returned as "store on disk".
-	size: usize,
+	pub(crate) size: usize,
 
 	/// Whether the runtime is compressed or not
 	compression: Compression,
@@ -33,42 +34,113 @@ pub struct RuntimeInfo {
 
 	/// The proposal hash is the hash of the extrinsic as it will appear
 	/// on-chain when calling `System.setCode(<runtime>)`
-	proposal_hash: String,
+	pub(crate) proposal_hash: String,
 
 	/// This is the hash of the extrinsic to authorize a parachain upgrade
-	parachain_authorize_upgrade_hash: String,
+	pub(crate) parachain_authorize_upgrade_hash: String,
+
+	/// The `authorize_upgrade` call the hash is computed for
+	authorize_upgrade: AuthorizeUpgrade,
+
+	/// Hashes of the `System` calls authorizing and applying the upgrade, for the chains that have them
+	system_upgrade_calls: Vec<SystemUpgradeCall>,
 
 	/// This is the IPFS hash of the runtime. That does **not** guaranty the
 	/// runtime to be seeded, but if it is, you can fetch it with this hash
-	ipfs_hash: String,
+	pub(crate) ipfs_hash: String,
 
 	/// The blake2_256 hash of the runtime
-	blake2_256: String,
+	pub(crate) blake2_256: String,
+
+	/// The hashes of the runtime as stored and decompressed, including the `ValidationCodeHash`
+	hashes: RuntimeHashes,
+
+	/// Optional features such as `runtime-benchmarks` that appear to be compiled in
+	features: Vec<DetectedFeature>,
+
+	/// The wasm proposals the runtime uses, floats included
+	wasm_proposals: Vec<ProposalUse>,
+
+	/// Why the wasm proposals could not be found, such as a runtime that does not validate
+	#[serde(skip_serializing_if = "Option::is_none")]
+	wasm_proposals_error: Option<String>,
 }
 
 impl RuntimeInfo {
 	pub fn new(testbed: &WasmTestBed) -> Result<Self> {
+		Self::with_chain(testbed, None)
+	}
+
+	/// Same as [RuntimeInfo::new] but the upgrade calls are for the chain whose runtime has the metadata `chain`
+	pub fn with_chain(testbed: &WasmTestBed, chain: Option<&RuntimeMetadata>) -> Result<Self> {
 		let core_version = testbed.core_version();
 		let hasher = IpfsHasher::default();
-		let proposal_hash = testbed.proposal_hash()?;
+		let proposal_hash = testbed.proposal_hash_for(chain)?;
 		let blake2_256 = testbed.blake2_256_hash()?;
-		let parachain_authorize_upgrade_hash = testbed.parachain_authorize_upgrade_hash()?;
+		let authorize_upgrade = testbed.authorize_upgrade(chain)?;
+		let system_upgrade_calls = testbed.system_upgrade_calls(chain)?;
 		let ipfs_hash = hasher.compute(testbed.raw_bytes())?;
+		let (wasm_proposals, wasm_proposals_error) = match testbed.proposals() {
+			Ok(proposals) => (proposals, None),
+			Err(e) => (Vec::new(), Some(e.to_string())),
+		};
 
 		Ok(Self {
 			size: testbed.size(),
 			compression: testbed.compression(),
-			reserved_meta: testbed.reserved_meta(),
-			reserved_meta_valid: testbed.reserved_meta_valid(),
-			metadata_version: *testbed.metadata_version(),
+			reserved_meta: testbed.reserved_meta()?,
+			reserved_meta_valid: testbed.reserved_meta_valid()?,
+			metadata_version: testbed.metadata_version()?,
 			core_version,
 			proposal_hash,
-			parachain_authorize_upgrade_hash,
+			parachain_authorize_upgrade_hash: authorize_upgrade.hash.clone(),
+			authorize_upgrade,
+			system_upgrade_calls,
 			ipfs_hash,
 			blake2_256,
+			hashes: RuntimeHashes::new(testbed.raw_bytes(), testbed.wasm(), testbed.compression().compressed()),
+			features: testbed.features()?,
+			wasm_proposals,
+			wasm_proposals_error,
 		})
 	}
 
+	/// The hashes of the runtime as stored and decompressed
+	pub fn hashes(&self) -> &RuntimeHashes {
+		&self.hashes
+	}
+
+	/// Fail if one of the `denied` features appears to be compiled in.
+	/// Passing no feature denies them all.
+	pub fn deny_features(&self, denied: &[RuntimeFeature]) -> Result<()> {
+		let found: Vec<String> = self
+			.features
+			.iter()
+			.filter(|f| denied.is_empty() || denied.contains(&f.feature))
+			.map(|f| f.feature.to_string())
+			.collect();
+
+		if found.is_empty() {
+			Ok(())
+		} else {
+			Err(SubwasmLibError::FeaturesDenied(found.join(", ")))
+		}
+	}
+
+	/// Fail if the runtime uses wasm proposals the `target` does not support.
+	pub fn check_wasm_target(&self, target: WasmTarget) -> Result<()> {
+		if let Some(e) = &self.wasm_proposals_error {
+			return Err(SubwasmLibError::Generic(format!("Cannot check the wasm proposals: {e}")));
+		}
+		let unsupported: Vec<String> = target.unsupported(&self.wasm_proposals).iter().map(|p| p.to_string()).collect();
+
+		if unsupported.is_empty() {
+			Ok(())
+		} else {
+			Err(SubwasmLibError::UnsupportedWasmProposals(target.to_string(), unsupported.join(", ")))
+		}
+	}
+
 	/// Print the RuntimeInfo either using the Display impl
 	/// or serde as json.
 	pub fn print(&self, json: bool) -> Result<()> {
@@ -81,18 +153,37 @@ impl RuntimeInfo {
 		Ok(())
 	}
 
+	#[deprecated(note = "The core version does not require computing the whole info, use `Subwasm::print_version`")]
 	pub fn print_version(&self, json: bool) -> Result<()> {
-		if json {
-			let serialized = serde_json::to_string_pretty(&self.core_version)?;
-			println!("{serialized}");
-		} else {
-			println!("specifications : {} v{}", self.core_version.spec_name, self.core_version.spec_version);
-			println!("implementation : {} v{}", self.core_version.impl_name, self.core_version.impl_version);
-			println!("transaction    : v{}", self.core_version.transaction_version);
-			println!("authoring      : v{}", self.core_version.authoring_version);
-		}
-		Ok(())
+		print_core_version(&self.core_version, json)
+	}
+}
+
+/// Print the core version either in a human readable form or as json.
+pub(crate) fn print_core_version(core_version: &SubstrateRuntimeVersion, json: bool) -> Result<()> {
+	if json {
+		let serialized = serde_json::to_string_pretty(core_version)?;
+		println!("{serialized}");
+	} else {
+		println!("specifications : {} v{}", core_version.spec_name, core_version.spec_version);
+		println!("implementation : {} v{}", core_version.impl_name, core_version.impl_version);
+		println!("transaction    : v{}", core_version.transaction_version);
+		println!("authoring      : v{}", core_version.authoring_version);
 	}
+	Ok(())
+}
+
+/// Name of a call the way the UIs show it, such as `authorizeUpgrade` for `authorize_upgrade`
+fn snake_to_camel(name: &str) -> String {
+	let mut parts = name.split('_');
+	let first = parts.next().unwrap_or_default().to_string();
+	parts.fold(first, |camel, part| {
+		let mut chars = part.chars();
+		match chars.next() {
+			Some(c) => camel + &c.to_uppercase().collect::<String>() + chars.as_str(),
+			None => camel,
+		}
+	})
 }
 
 impl Display for RuntimeInfo {
@@ -139,9 +230,82 @@ impl Display for RuntimeInfo {
 			"{:<width_emoji$} {:<width_title$} {}",
 			"🗳️ ", "authorizeUpgrade hash:", self.parachain_authorize_upgrade_hash
 		)?;
+		writeln!(fmt, "{:<width_emoji$} {:<width_title$} {}", "🗳️ ", "authorizeUpgrade call:", self.authorize_upgrade)?;
+		for call in &self.system_upgrade_calls {
+			writeln!(
+				fmt,
+				"{:<width_emoji$} {:<width_title$} {}",
+				"🗳️ ",
+				format!("system.{}:", snake_to_camel(&call.call)),
+				call.hash
+			)?;
+		}
 		writeln!(fmt, "{:<width_emoji$} {:<width_title$} {}", "🗳️ ", "Blake2-256 hash:", self.blake2_256)?;
+		let stored = self.hashes.compressed.as_ref().unwrap_or(&self.hashes.decompressed);
+		writeln!(
+			fmt,
+			"{:<width_emoji$} {:<width_title$} {}",
+			"#️⃣ ",
+			"Sha256 hash:",
+			stored.sha256.as_deref().unwrap_or_default()
+		)?;
+		writeln!(
+			fmt,
+			"{:<width_emoji$} {:<width_title$} {}",
+			"#️⃣ ",
+			"Keccak-256 hash:",
+			stored.keccak_256.as_deref().unwrap_or_default()
+		)?;
+		if self.hashes.compressed.is_some() {
+			for (title, hash) in [
+				("Decompressed blake2-256:", &self.hashes.decompressed.blake2_256),
+				("Decompressed sha256:", &self.hashes.decompressed.sha256),
+				("Decompressed keccak-256:", &self.hashes.decompressed.keccak_256),
+			] {
+				writeln!(
+					fmt,
+					"{:<width_emoji$} {:<width_title$} {}",
+					"#️⃣ ",
+					title,
+					hash.as_deref().unwrap_or_default()
+				)?;
+			}
+		}
+		writeln!(
+			fmt,
+			"{:<width_emoji$} {:<width_title$} {}",
+			"⛓️ ",
+			"Validation code hash:",
+			self.hashes.validation_code_hash.as_deref().unwrap_or_default()
+		)?;
 		let ipfs_url = format!("https://www.ipfs.io/ipfs/{cid}", cid = self.ipfs_hash);
 		writeln!(fmt, "{:<width_emoji$} {:<width_title$} {ipfs_url}", "📦", "IPFS:")?;
+		if self.features.is_empty() {
+			writeln!(fmt, "{:<width_emoji$} {:<width_title$} None", "🧩", "Optional features:")?;
+		} else {
+			let features: Vec<String> = self.features.iter().map(|f| f.feature.to_string()).collect();
+			writeln!(fmt, "{:<width_emoji$} {:<width_title$} {} ⚠️", "🧩", "Optional features:", features.join(", "))?;
+		}
+		if let Some(e) = &self.wasm_proposals_error {
+			writeln!(fmt, "{:<width_emoji$} {:<width_title$} {e} ⚠️", "🧪", "Wasm proposals:")?;
+		} else if self.wasm_proposals.is_empty() {
+			writeln!(fmt, "{:<width_emoji$} {:<width_title$} None", "🧪", "Wasm proposals:")?;
+		} else {
+			let proposals: Vec<String> =
+				self.wasm_proposals.iter().map(|u| format!("{} ({})", u.proposal, u.count)).collect();
+			writeln!(fmt, "{:<width_emoji$} {:<width_title$} {}", "🧪", "Wasm proposals:", proposals.join(", "))?;
+		}
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests_runtime_info {
+	use super::*;
+
+	#[test]
+	fn it_converts_call_names() {
+		assert_eq!("authorizeUpgradeWithoutChecks", snake_to_camel("authorize_upgrade_without_checks"));
+		assert_eq!("remark", snake_to_camel("remark"));
+	}
+}
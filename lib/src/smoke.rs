@@ -0,0 +1,414 @@
+use frame_metadata::{
+	v14::{RuntimeMetadataV14, StorageEntryType},
+	RuntimeMetadata,
+};
+use scale::{Compact, Decode, Encode};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive, Variant};
+use serde::Serialize;
+use sp_core::{storage::Storage, twox_128};
+use sp_runtime::{
+	generic::{Digest, DigestItem, Header as GenericHeader},
+	traits::{BlakeTwo256, Header as _},
+};
+use std::{collections::BTreeMap, fmt::Display};
+use wasm_loader::exports;
+use wasm_testbed::{BasicExternalities, Externalities, StateSnapshot, WasmTestBed};
+
+use crate::{error::*, Genesis};
+
+type Header = GenericHeader<u32, BlakeTwo256>;
+
+/// Storage key of the runtime code
+const CODE_KEY: &[u8] = b":code";
+
+/// Identifier of the timestamp inherent
+const TIMESTAMP_INHERENT: [u8; 8] = *b"timstap0";
+
+/// Identifier of the inherent of the relay chains with the parachain candidates, bitfields and disputes
+const PARACHAINS_INHERENT: [u8; 8] = *b"parachn0";
+
+/// Slot duration used when the runtime has neither BABE nor Aura, in milliseconds
+const DEFAULT_SLOT_DURATION: u64 = 6000;
+
+/// How the blocks are produced
+#[derive(Debug, Clone)]
+pub struct SmokeOptions {
+	/// Number of blocks to produce
+	pub blocks: u32,
+
+	/// Genesis preset to start from, the default genesis config if not set
+	pub preset: Option<String>,
+}
+
+impl Default for SmokeOptions {
+	fn default() -> Self {
+		Self { blocks: 3, preset: None }
+	}
+}
+
+/// The consensus engine whose pre-runtime digest the runtime expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Consensus {
+	Babe,
+	Aura,
+	None,
+}
+
+impl Consensus {
+	/// Pre-runtime digest of a block authored in `slot` by the first authority
+	fn pre_digest(&self, slot: u64) -> Option<DigestItem> {
+		match self {
+			// `PreDigest::SecondaryPlain { authority_index: 0, slot }`
+			Self::Babe => Some(DigestItem::PreRuntime(*b"BABE", (2u8, 0u32, slot).encode())),
+			Self::Aura => Some(DigestItem::PreRuntime(*b"aura", slot.encode())),
+			Self::None => None,
+		}
+	}
+}
+
+/// A block produced during the smoke test
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockReport {
+	pub number: u32,
+
+	/// Hash of the header returned by `BlockBuilder_finalize_block`
+	pub hash: String,
+
+	/// Timestamp set by the inherent, in milliseconds
+	pub timestamp: u64,
+
+	/// Number of inherent extrinsics applied
+	pub extrinsics: usize,
+
+	/// Events emitted in the block, as `Pallet.Event`
+	pub events: Vec<String>,
+}
+
+/// Outcome of producing a few blocks with a runtime, from its genesis state or a state snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct SmokeReport {
+	/// Name and version of the runtime
+	pub runtime: String,
+
+	/// Where the initial state comes from
+	pub state: String,
+
+	/// Blocks produced successfully
+	pub blocks: Vec<BlockReport>,
+
+	/// Cause of the failure, such as a panic in the runtime
+	pub error: Option<String>,
+}
+
+impl SmokeReport {
+	/// Produce [SmokeOptions::blocks] blocks with the runtime of `testbed`. The initial state is built with
+	/// the `GenesisBuilder` runtime API unless a `state` is given. Each block only contains the inherents.
+	pub fn new(testbed: &WasmTestBed, state: Option<&StateSnapshot>, options: &SmokeOptions) -> Result<Self> {
+		let (mut storage, origin) = match state {
+			Some(state) => (state.storage.clone(), format!("{} snapshot", state.format)),
+			None => {
+				let preset = options.preset.as_deref();
+				let top = Genesis::new(testbed)?.storage(preset)?;
+				(Storage { top, ..Default::default() }, format!("genesis preset {}", preset.unwrap_or("default")))
+			}
+		};
+		storage.top.insert(CODE_KEY.to_vec(), testbed.raw_bytes().to_vec());
+
+		let version = testbed.core_version();
+		let mut report = Self {
+			runtime: format!("{} v{}", version.spec_name, version.spec_version),
+			state: origin,
+			blocks: Vec::new(),
+			error: None,
+		};
+
+		let mut producer = BlockProducer::new(testbed, BasicExternalities::new(storage))?;
+		for _ in 0..options.blocks {
+			match producer.produce() {
+				Ok(block) => report.blocks.push(block),
+				Err(e) => {
+					report.error = Some(e.to_string());
+					break;
+				}
+			}
+		}
+		Ok(report)
+	}
+
+	pub fn is_ok(&self) -> bool {
+		self.error.is_none()
+	}
+}
+
+/// Builds blocks on top of a state, the way a node would with an empty transaction pool
+struct BlockProducer<'a> {
+	testbed: &'a WasmTestBed,
+	ext: BasicExternalities,
+	consensus: Consensus,
+	slot_duration: u64,
+	metadata: Option<&'a RuntimeMetadataV14>,
+	parent: Header,
+}
+
+impl<'a> BlockProducer<'a> {
+	fn new(testbed: &'a WasmTestBed, mut ext: BasicExternalities) -> Result<Self> {
+		let exports = exports(testbed.wasm()).map_err(|e| SubwasmLibError::Generic(e.to_string()))?;
+		let (consensus, method) = if exports.iter().any(|e| e == "BabeApi_configuration") {
+			(Consensus::Babe, Some("BabeApi_configuration"))
+		} else if exports.iter().any(|e| e == "AuraApi_slot_duration") {
+			(Consensus::Aura, Some("AuraApi_slot_duration"))
+		} else {
+			(Consensus::None, None)
+		};
+
+		// The slot duration is the first field of the BABE configuration and the only output for Aura
+		let slot_duration = match method {
+			Some(method) => {
+				let output = call(testbed, method, &[], &mut ext)?;
+				u64::decode(&mut &output[..])
+					.map_err(|e| SubwasmLibError::Parsing(method.to_string(), e.to_string()))?
+			}
+			None => DEFAULT_SLOT_DURATION,
+		};
+
+		let metadata = match testbed.metadata() {
+			Ok(RuntimeMetadata::V14(v14)) => Some(v14),
+			_ => None,
+		};
+		// We do not know the header of the last block of the state, we make one up with its number
+		let number = ext.storage(&storage_key("System", "Number")).and_then(decode).unwrap_or_default();
+		let parent =
+			Header::new(number, Default::default(), Default::default(), Default::default(), Default::default());
+		Ok(Self { testbed, ext, consensus, slot_duration: slot_duration.max(1), metadata, parent })
+	}
+
+	fn produce(&mut self) -> Result<BlockReport> {
+		let number = self.parent.number + 1;
+		let slot = self.read::<u64>("Timestamp", "Now").unwrap_or_default() / self.slot_duration + 1;
+		let timestamp = slot * self.slot_duration;
+
+		let digest = Digest { logs: self.consensus.pre_digest(slot).into_iter().collect() };
+		let header = Header::new(number, Default::default(), Default::default(), self.parent.hash(), digest);
+		self.call("Core_initialize_block", &header.encode())?;
+
+		// Relay chains expect the parachains inherent in every block, the other runtimes ignore it
+		let parachains = (Vec::<()>::new(), Vec::<()>::new(), Vec::<()>::new(), &self.parent);
+		let inherent_data =
+			BTreeMap::from([(TIMESTAMP_INHERENT, timestamp.encode()), (PARACHAINS_INHERENT, parachains.encode())]);
+		let extrinsics: Vec<Vec<u8>> = self.decode("BlockBuilder_inherent_extrinsics", &inherent_data.encode())?;
+		for extrinsic in &extrinsics {
+			// `ApplyExtrinsicResult` is `Ok(Ok(()))` when the extrinsic was applied and succeeded
+			let output = self.call("BlockBuilder_apply_extrinsic", &extrinsic.encode())?;
+			if !output.starts_with(&[0, 0]) {
+				return Err(SubwasmLibError::CallFailed(
+					"BlockBuilder_apply_extrinsic".to_string(),
+					format!("inherent 0x{} was rejected: 0x{}", hex::encode(extrinsic), hex::encode(output)),
+				));
+			}
+		}
+
+		self.parent = self.decode("BlockBuilder_finalize_block", &[])?;
+		// The events stay in the storage until the next block is initialized
+		let events = self.events();
+
+		Ok(BlockReport {
+			number,
+			hash: format!("{:?}", self.parent.hash()),
+			timestamp,
+			extrinsics: extrinsics.len(),
+			events,
+		})
+	}
+
+	fn call(&mut self, method: &str, input: &[u8]) -> Result<Vec<u8>> {
+		call(self.testbed, method, input, &mut self.ext)
+	}
+
+	fn decode<T: Decode>(&mut self, method: &str, input: &[u8]) -> Result<T> {
+		let output = self.call(method, input)?;
+		T::decode(&mut &output[..]).map_err(|e| SubwasmLibError::Parsing(method.to_string(), e.to_string()))
+	}
+
+	fn read<T: Decode>(&self, pallet: &str, item: &str) -> Option<T> {
+		self.ext.storage(&storage_key(pallet, item)).and_then(decode)
+	}
+
+	/// Names of the events stored in `System::Events`. Events that cannot be decoded
+	/// with the metadata are counted but not named.
+	fn events(&self) -> Vec<String> {
+		let Some(bytes) = self.ext.storage(&storage_key("System", "Events")) else {
+			return Vec::new();
+		};
+		if let Some(names) = self.metadata.and_then(|metadata| event_names(metadata, &bytes)) {
+			return names;
+		}
+		let count = Compact::<u32>::decode(&mut &bytes[..]).map(|c| c.0).unwrap_or_default();
+		vec!["<unknown>".to_string(); count as usize]
+	}
+}
+
+fn call(testbed: &WasmTestBed, method: &str, input: &[u8], ext: &mut BasicExternalities) -> Result<Vec<u8>> {
+	testbed
+		.call_with_state(method, input, ext)
+		.map(|result| result.output)
+		.map_err(|e| SubwasmLibError::CallFailed(method.to_string(), e.to_string()))
+}
+
+fn decode<T: Decode>(bytes: Vec<u8>) -> Option<T> {
+	T::decode(&mut &bytes[..]).ok()
+}
+
+/// Storage key of a storage value
+fn storage_key(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+/// Decode the names of the events in the value of `System::Events`, a `Vec<EventRecord>`
+fn event_names(metadata: &RuntimeMetadataV14, bytes: &[u8]) -> Option<Vec<String>> {
+	let types = &metadata.types;
+	let entry = metadata
+		.pallets
+		.iter()
+		.find(|pallet| pallet.name == "System")?
+		.storage
+		.as_ref()?
+		.entries
+		.iter()
+		.find(|entry| entry.name == "Events")?;
+	let StorageEntryType::Plain(ty) = &entry.ty else {
+		return None;
+	};
+	let TypeDef::Sequence(sequence) = &types.resolve(ty.id)?.type_def else {
+		return None;
+	};
+	let TypeDef::Composite(record) = &types.resolve(sequence.type_param.id)?.type_def else {
+		return None;
+	};
+
+	let input = &mut &bytes[..];
+	let count = Compact::<u32>::decode(input).ok()?.0;
+	let mut names = Vec::with_capacity(count as usize);
+	for _ in 0..count {
+		for field in &record.fields {
+			if field.name.as_deref() != Some("event") {
+				skip(types, field.ty.id, input)?;
+				continue;
+			}
+			// The outer event enum has one variant per pallet, wrapping the event enum of the pallet
+			let pallet = variant(types, field.ty.id, input)?;
+			match pallet.fields.as_slice() {
+				[inner] => {
+					let event = variant(types, inner.ty.id, input)?;
+					event.fields.iter().try_for_each(|field| skip(types, field.ty.id, input))?;
+					names.push(format!("{}.{}", pallet.name, event.name));
+				}
+				fields => {
+					fields.iter().try_for_each(|field| skip(types, field.ty.id, input))?;
+					names.push(pallet.name.clone());
+				}
+			}
+		}
+	}
+	input.is_empty().then_some(names)
+}
+
+/// Read the index of an enum and return the matching variant, without its fields
+fn variant<'a>(types: &'a PortableRegistry, id: u32, input: &mut &[u8]) -> Option<&'a Variant<PortableForm>> {
+	let TypeDef::Variant(def) = &types.resolve(id)?.type_def else {
+		return None;
+	};
+	let index = u8::decode(input).ok()?;
+	def.variants.iter().find(|variant| variant.index == index)
+}
+
+/// Move `input` past a value of the type `id`
+fn skip(types: &PortableRegistry, id: u32, input: &mut &[u8]) -> Option<()> {
+	match &types.resolve(id)?.type_def {
+		TypeDef::Composite(def) => def.fields.iter().try_for_each(|field| skip(types, field.ty.id, input)),
+		TypeDef::Variant(_) => {
+			let variant = variant(types, id, input)?;
+			variant.fields.iter().try_for_each(|field| skip(types, field.ty.id, input))
+		}
+		TypeDef::Sequence(def) => {
+			let len = Compact::<u32>::decode(input).ok()?.0;
+			(0..len).try_for_each(|_| skip(types, def.type_param.id, input))
+		}
+		TypeDef::Array(def) => (0..def.len).try_for_each(|_| skip(types, def.type_param.id, input)),
+		TypeDef::Tuple(def) => def.fields.iter().try_for_each(|field| skip(types, field.id, input)),
+		TypeDef::Compact(_) => Compact::<u128>::decode(input).ok().map(|_| ()),
+		TypeDef::Primitive(primitive) => match primitive {
+			TypeDefPrimitive::Bool | TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => advance(input, 1),
+			TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => advance(input, 2),
+			TypeDefPrimitive::U32 | TypeDefPrimitive::I32 | TypeDefPrimitive::Char => advance(input, 4),
+			TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => advance(input, 8),
+			TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => advance(input, 16),
+			TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => advance(input, 32),
+			TypeDefPrimitive::Str => {
+				let len = Compact::<u32>::decode(input).ok()?.0;
+				advance(input, len as usize)
+			}
+		},
+		TypeDef::BitSequence(_) => None,
+	}
+}
+
+fn advance(input: &mut &[u8], len: usize) -> Option<()> {
+	*input = input.get(len..)?;
+	Some(())
+}
+
+impl Display for SmokeReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Runtime: {}", self.runtime)?;
+		writeln!(f, "State:   {}", self.state)?;
+		for block in &self.blocks {
+			writeln!(
+				f,
+				"Block #{} {}: {} extrinsics, {} events",
+				block.number,
+				block.hash,
+				block.extrinsics,
+				block.events.len()
+			)?;
+			for event in &block.events {
+				writeln!(f, "  {event}")?;
+			}
+		}
+
+		match &self.error {
+			Some(error) => writeln!(f, "❌ Block production failed after {} blocks: {error}", self.blocks.len()),
+			None => writeln!(f, "✅ {} blocks produced", self.blocks.len()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests_smoke {
+	use super::*;
+	use scale_info::{meta_type, Registry};
+
+	#[test]
+	fn it_skips_values() {
+		type Value = (u32, Vec<u8>, Option<u64>, Compact<u64>, String);
+		let mut registry = Registry::new();
+		let id = registry.register_type(&meta_type::<Value>()).id;
+		let types: PortableRegistry = registry.into();
+
+		let mut bytes = (7u32, vec![1u8, 2, 3], Some(5u64), Compact(1_000_000u64), "smoke".to_string()).encode();
+		bytes.push(0xff);
+		let input = &mut &bytes[..];
+		assert_eq!(Some(()), skip(&types, id, input));
+		assert_eq!(&[0xff], input);
+		assert_eq!(None, skip(&types, id, &mut &bytes[..4]));
+	}
+
+	#[test]
+	fn it_builds_pre_digests() {
+		assert_eq!(
+			Some(DigestItem::PreRuntime(*b"BABE", vec![2, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0])),
+			Consensus::Babe.pre_digest(9)
+		);
+		assert_eq!(Some(DigestItem::PreRuntime(*b"aura", 9u64.encode())), Consensus::Aura.pre_digest(9));
+		assert_eq!(None, Consensus::None.pre_digest(9));
+	}
+}
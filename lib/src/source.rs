@@ -6,10 +6,11 @@ use wasm_loader::{BlockRef, OnchainBlock, Source as WasmLoaderSource};
 use crate::chain_urls::get_chain_urls;
 use crate::download_runtime;
 use crate::error;
-use crate::fetch_at_url;
+use crate::fetch_verified;
 use crate::github_ref::GithubRef;
 use crate::is_wasm_from_url;
 use crate::ChainInfo;
+use crate::Integrity;
 
 /// The [wasm_loader::WasmLoader] provides a basic Source struct that
 /// can handle only a file or RPC endpoint.
@@ -53,11 +54,8 @@ impl TryFrom<&str> for Source {
 
 		// First we deal with the easy case of files
 		let src = WasmLoaderSource::from_str(s);
-		if let Ok(s) = &src {
-			match s {
-				WasmLoaderSource::File(f) => return Ok(Source::File(f.to_owned())),
-				_ => {}
-			}
+		if let Ok(WasmLoaderSource::File(f)) = &src {
+			return Ok(Source::File(f.to_owned()));
 		}
 
 		// This is where we try to be smart about URLs...
@@ -79,11 +77,8 @@ impl TryFrom<&str> for Source {
 			// if src.is_ok_and(|s| matches!(s, WasmLoaderSource::Chain(c))) {
 			// 	return Ok(Source::Chain(c));
 			// }
-			if let Ok(s) = &src {
-				match s {
-					WasmLoaderSource::Chain(c) => return Ok(Source::Chain(c.to_owned())),
-					_ => {}
-				}
+			if let Ok(WasmLoaderSource::Chain(c)) = &src {
+				return Ok(Source::Chain(c.to_owned()));
 			}
 		}
 
@@ -129,11 +124,11 @@ impl Source {
 			// That one is easy :)
 			Source::File(i) => Ok(i.to_owned()),
 
-			// Fetch from a URL and store the file in a tmp dir
-			Source::URL(u) => fetch_at_url(u.to_owned(), None),
+			// Fetch from a URL and store the file in a tmp dir, once checked against the published checksums and signatures
+			Source::URL(u) => fetch_verified(u.to_owned(), None, &Integrity::default()),
 
 			// Generate the URL and fetch the file to a tmp dir
-			Source::Github(gh) => fetch_at_url(gh.as_url(), None),
+			Source::Github(gh) => fetch_verified(gh.as_url(), None, &Integrity::default()),
 
 			// Use the wasm_loader to download the runtime from a node
 			Source::Chain(ocb) => download_runtime(ocb.endpoint.to_owned(), ocb.block_ref.to_owned(), None),
@@ -0,0 +1,203 @@
+use num_format::{Locale, ToFormattedString};
+use scale::{Decode, Encode};
+use serde::Serialize;
+use std::{fmt::Display, fs::File, io::Write, path::PathBuf};
+use wasm_loader::{
+	custom_section, custom_sections, rewrite_custom_sections, Compression, Source, WasmLoader, SEMANTIC_CUSTOM_SECTIONS,
+};
+
+use crate::error::*;
+
+/// Name of the custom section holding the encoded `RuntimeVersion`
+const RUNTIME_VERSION_SECTION: &str = "runtime_version";
+
+/// What to remove or change when stripping a runtime
+#[derive(Debug, Clone, Default)]
+pub struct StripOptions {
+	/// Remove all custom sections except the ones the node reads, such as `runtime_version` and `runtime_apis`
+	pub all: bool,
+
+	/// Custom sections to remove
+	pub sections: Vec<String>,
+
+	/// New `spec_name` to write in the `runtime_version` section
+	pub spec_name: Option<String>,
+
+	/// New `spec_version` to write in the `runtime_version` section
+	pub spec_version: Option<u32>,
+}
+
+impl StripOptions {
+	fn rewrites_version(&self) -> bool {
+		self.spec_name.is_some() || self.spec_version.is_some()
+	}
+}
+
+/// Sizes and changes of a stripped runtime
+#[derive(Debug, Clone, Serialize)]
+pub struct StripResult {
+	/// Whether the runtime was compressed, in which case the output is compressed as well
+	pub compressed: bool,
+
+	/// Size of the input file, in bytes
+	pub size_before: usize,
+
+	/// Size of the output file, in bytes
+	pub size_after: usize,
+
+	/// Size of the decompressed wasm before stripping, in bytes
+	pub wasm_size_before: usize,
+
+	/// Size of the decompressed wasm after stripping, in bytes
+	pub wasm_size_after: usize,
+
+	/// Custom sections that got removed
+	pub removed: Vec<String>,
+
+	/// Whether the `runtime_version` section was rewritten
+	pub version_rewritten: bool,
+}
+
+impl Display for StripResult {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let size = |s: usize| s.to_formatted_string(&Locale::en);
+
+		if self.removed.is_empty() {
+			writeln!(f, "Removed sections: none")?;
+		} else {
+			writeln!(f, "Removed sections: {}", self.removed.join(", "))?;
+		}
+		if self.version_rewritten {
+			writeln!(f, "Rewritten sections: {RUNTIME_VERSION_SECTION}")?;
+		}
+		if self.compressed {
+			writeln!(f, "Compressed size: {} -> {} bytes", size(self.size_before), size(self.size_after))?;
+		}
+		writeln!(f, "Decompressed size: {} -> {} bytes", size(self.wasm_size_before), size(self.wasm_size_after))
+	}
+}
+
+/// Re-encode the content of the `runtime_version` section with a new `spec_name` and/or `spec_version`.
+/// The fields after `spec_version` depend on the version of the `Core` api, so we keep their bytes as is.
+fn rewrite_runtime_version(section: &[u8], spec_name: Option<&str>, spec_version: Option<u32>) -> Result<Vec<u8>> {
+	let input = &mut &section[..];
+	let map_err = |e: scale::Error| SubwasmLibError::Parsing(RUNTIME_VERSION_SECTION.to_string(), e.to_string());
+
+	let name = String::decode(input).map_err(map_err)?;
+	let impl_name = String::decode(input).map_err(map_err)?;
+	let authoring_version = u32::decode(input).map_err(map_err)?;
+	let version = u32::decode(input).map_err(map_err)?;
+
+	let mut output = Vec::with_capacity(section.len());
+	spec_name.map(String::from).unwrap_or(name).encode_to(&mut output);
+	impl_name.encode_to(&mut output);
+	authoring_version.encode_to(&mut output);
+	spec_version.unwrap_or(version).encode_to(&mut output);
+	output.extend_from_slice(input);
+	Ok(output)
+}
+
+/// Remove custom sections from a runtime and optionally change its `runtime_version`,
+/// for instance to deploy it on a test network. The output is compressed if the input was.
+pub fn strip(input: PathBuf, output: PathBuf, options: &StripOptions) -> Result<StripResult> {
+	let wasm = WasmLoader::load_from_source(&Source::File(input))?;
+	let bytes = wasm.uncompressed_bytes();
+	let map_err = |e: wasm_loader::WasmLoaderError| SubwasmLibError::Generic(e.to_string());
+
+	let present = custom_sections(bytes).map_err(map_err)?;
+	if let Some(missing) = options.sections.iter().find(|s| !present.contains(s)) {
+		return Err(SubwasmLibError::NotFound(format!("custom section {missing}")));
+	}
+	let removed: Vec<String> = match options.all {
+		true => present.into_iter().filter(|s| !SEMANTIC_CUSTOM_SECTIONS.contains(&s.as_str())).collect(),
+		false => options.sections.clone(),
+	};
+
+	let mut replace = Vec::new();
+	if options.rewrites_version() {
+		let section = custom_section(bytes, RUNTIME_VERSION_SECTION)
+			.map_err(map_err)?
+			.ok_or_else(|| SubwasmLibError::NotFound(format!("custom section {RUNTIME_VERSION_SECTION}")))?;
+		let section = rewrite_runtime_version(section, options.spec_name.as_deref(), options.spec_version)?;
+		replace.push((RUNTIME_VERSION_SECTION.to_string(), section));
+	}
+
+	let stripped = rewrite_custom_sections(bytes, &removed, &replace).map_err(map_err)?;
+	let compressed = wasm.compression().compressed();
+	let output_bytes = match compressed {
+		true => Compression::compress(&stripped).map_err(|_e| SubwasmLibError::CompressionFailed())?,
+		false => stripped.clone(),
+	};
+
+	log::info!("Saving stripped runtime to {:?}", output);
+	let mut buffer = File::create(output)?;
+	buffer.write_all(&output_bytes)?;
+
+	Ok(StripResult {
+		compressed,
+		size_before: wasm.original_bytes().len(),
+		size_after: output_bytes.len(),
+		wasm_size_before: bytes.len(),
+		wasm_size_after: stripped.len(),
+		removed,
+		version_rewritten: options.rewrites_version(),
+	})
+}
+
+#[cfg(test)]
+mod tests_strip {
+	use super::*;
+
+	fn section(spec_name: &str, spec_version: u32) -> Vec<u8> {
+		let mut bytes = (spec_name.to_string(), "impl".to_string(), 1u32, spec_version, 7u32).encode();
+		// empty apis, transaction_version and state_version
+		bytes.extend([0x00, 0x01, 0x00, 0x00, 0x00, 0x01]);
+		bytes
+	}
+
+	#[test]
+	fn it_rewrites_the_runtime_version() {
+		let rewritten = rewrite_runtime_version(&section("kusama", 9190), Some("testnet"), Some(9999))
+			.expect("Failed rewriting the runtime version");
+		assert_eq!(section("testnet", 9999), rewritten);
+
+		let unchanged = rewrite_runtime_version(&section("kusama", 9190), None, None)
+			.expect("Failed rewriting the runtime version");
+		assert_eq!(section("kusama", 9190), unchanged);
+	}
+
+	/// A custom section whose content is shorter than 128 bytes
+	fn custom(name: &str, data: &[u8]) -> Vec<u8> {
+		[&[0x00, (1 + name.len() + data.len()) as u8, name.len() as u8], name.as_bytes(), data].concat()
+	}
+
+	#[test]
+	fn it_keeps_the_sections_when_only_rewriting_the_version() {
+		let wasm = [
+			&[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00][..],
+			&custom(RUNTIME_VERSION_SECTION, &section("kusama", 9190)),
+			&custom("name", &[0; 16]),
+		]
+		.concat();
+		let (input, output) = (crate::get_output_file_tmp().unwrap(), crate::get_output_file_tmp().unwrap());
+		std::fs::write(&input, wasm).expect("Failed writing the runtime");
+
+		let options = StripOptions { spec_version: Some(9999), ..Default::default() };
+		let result = strip(input.clone(), output.clone(), &options).expect("Failed stripping the runtime");
+		let stripped = std::fs::read(&output).expect("Failed reading the stripped runtime");
+		let _ = (std::fs::remove_file(input), std::fs::remove_file(output));
+
+		assert!(result.removed.is_empty());
+		assert!(result.version_rewritten);
+		assert_eq!(vec!["runtime_version", "name"], custom_sections(&stripped).expect("Failed parsing wasm"));
+		assert_eq!(
+			Some(&section("kusama", 9999)[..]),
+			custom_section(&stripped, RUNTIME_VERSION_SECTION).expect("Failed parsing wasm")
+		);
+	}
+
+	#[test]
+	fn it_rejects_invalid_runtime_versions() {
+		assert!(rewrite_runtime_version(&[0x01], None, Some(1)).is_err());
+	}
+}
@@ -1,25 +1,37 @@
-use std::io::Write;
+use sp_version::RuntimeVersion as SubstrateRuntimeVersion;
+use std::{io::Write, sync::OnceLock};
 use substrate_differ::differs::reduced::{reduced_runtime::ReducedRuntime, reduced_runtime_summary::*};
-use wasm_loader::Source;
-use wasm_testbed::{WasmTestBed, WasmTestbedError};
+use wasm_loader::{OnchainBlock, Source};
+use wasm_testbed::{RuntimeLog, StateSnapshot, WasmTestBed, WasmTestBedConfig, WasmTestbedError};
 
 use crate::{
 	error,
 	error::*,
 	metadata_wrapper::{self, MetadataWrapper},
+	runtime_info::print_core_version,
 	utils::print_big_output_safe,
-	RuntimeInfo,
+	CallReport, CallWrapper, CanUpgradeReport, DigestReport, Genesis, Integrity, Preimage, RuntimeInfo, SmokeOptions,
+	SmokeReport, SrtoolDigest, UpgradeCall, UpgradeCheckReport, UpgradeCheckSelect, VerifyReport, XcmUpgrade,
+	XcmUpgradeOptions,
 };
 
 /// The main `subwasm` object
 pub struct Subwasm {
 	testbed: WasmTestBed,
-	runtime_info: RuntimeInfo,
+
+	/// The [RuntimeInfo] is only computed when requested as computing
+	/// all the hashes takes a while.
+	runtime_info: OnceLock<RuntimeInfo>,
 }
 
 impl Subwasm {
 	pub fn new(source: &Source) -> Result<Self> {
-		let testbed = WasmTestBed::new(source).map_err(|e| {
+		Self::with_config(source, &WasmTestBedConfig::default())
+	}
+
+	/// Load the runtime using the provided [WasmTestBedConfig]
+	pub fn with_config(source: &Source, config: &WasmTestBedConfig) -> Result<Self> {
+		let testbed = WasmTestBed::with_config(source, config).map_err(|e| {
 			eprintln!("{e}");
 			if let WasmTestbedError::Decoding(data) = e {
 				WasmTestBed::print_magic_and_version(&data);
@@ -33,12 +45,113 @@ impl Subwasm {
 
 			SubwasmLibError::Generic("Failed loading runtime".to_string())
 		})?;
-		let runtime_info = RuntimeInfo::new(&testbed)?;
-		Ok(Self { testbed, runtime_info })
+		Ok(Self { testbed, runtime_info: OnceLock::new() })
+	}
+
+	/// Run the calls against the storage of `state` instead of an empty one
+	pub fn with_state(self, state: StateSnapshot) -> Self {
+		Self { testbed: self.testbed.with_state(state), ..self }
+	}
+
+	/// Run the migrations of the runtime against `state`, as if it replaced the runtime of the chain
+	pub fn upgrade_check(&self, state: &StateSnapshot, checks: UpgradeCheckSelect) -> Result<UpgradeCheckReport> {
+		UpgradeCheckReport::new(&self.testbed, state, checks)
+	}
+
+	/// Produce a few blocks with the runtime, from its genesis state or from `state`
+	pub fn smoke(&self, state: Option<&StateSnapshot>, options: &SmokeOptions) -> Result<SmokeReport> {
+		SmokeReport::new(&self.testbed, state, options)
+	}
+
+	/// Get the [RuntimeInfo], computing it the first time
+	pub fn runtime_info(&self) -> Result<&RuntimeInfo> {
+		if let Some(runtime_info) = self.runtime_info.get() {
+			return Ok(runtime_info);
+		}
+
+		let runtime_info = RuntimeInfo::new(&self.testbed)?;
+		Ok(self.runtime_info.get_or_init(|| runtime_info))
+	}
+
+	/// Compute the [RuntimeInfo] for an upgrade of the chain running `current`,
+	/// the upgrade calls being encoded with the metadata of `current`
+	pub fn runtime_info_for(&self, current: &Subwasm) -> Result<RuntimeInfo> {
+		RuntimeInfo::with_chain(&self.testbed, Some(current.testbed.metadata()?))
+	}
+
+	/// Encode the `call` upgrading to this runtime, wrapped in `wrappers`, for the chain running `current`,
+	/// or this runtime if not given
+	pub fn preimage(&self, current: Option<&Subwasm>, call: UpgradeCall, wrappers: &[CallWrapper]) -> Result<Preimage> {
+		let chain = current.map(|current| current.testbed.metadata()).transpose()?;
+		Preimage::new(&self.testbed, chain, call, wrappers)
+	}
+
+	/// Encode the relay chain call upgrading a parachain to this runtime over XCM, with the types of the `relay` runtime
+	pub fn xcm_upgrade(&self, relay: &Subwasm, options: &XcmUpgradeOptions) -> Result<XcmUpgrade> {
+		XcmUpgrade::new(&self.testbed, relay.testbed.metadata()?, options)
+	}
+
+	/// Compare this runtime with what the chain at `reference` runs, authorized or noted as a preimage.
+	/// The preimages are encoded for the chain running `current`, or this runtime if not given.
+	pub fn verify(&self, reference: &OnchainBlock, current: Option<&Subwasm>) -> Result<VerifyReport> {
+		let chain = current.map(|current| current.testbed.metadata()).transpose()?;
+		VerifyReport::new(&self.testbed, chain, reference)
+	}
+
+	/// Describe this runtime the way srtool does, `info` being its runtime info and `wasm` its path
+	pub fn srtool_digest(&self, info: &RuntimeInfo, wasm: Option<String>) -> Result<SrtoolDigest> {
+		SrtoolDigest::new(&self.testbed, info, wasm)
+	}
+
+	/// Compare a srtool `digest` with this runtime, field by field
+	pub fn verify_digest(&self, digest: &serde_json::Value) -> Result<DigestReport> {
+		let actual = self.srtool_digest(self.runtime_info()?, None)?;
+		DigestReport::new(&actual, digest, self.testbed.compression().compressed())
+	}
+
+	/// Check whether the chain running `current` accepts the upgrade to this runtime, the way `frame_system` does
+	pub fn can_upgrade_from(&self, current: &Subwasm, max_code_size: Option<usize>) -> Result<CanUpgradeReport> {
+		CanUpgradeReport::new(&current.testbed, &self.testbed, max_code_size)
+	}
+
+	/// Check the expected hashes of `integrity` against the runtime as stored
+	pub fn check_integrity(&self, integrity: &Integrity) -> Result<()> {
+		integrity.check(self.testbed.raw_bytes())
+	}
+
+	/// Take the logs the runtime emitted since they were last taken, up to [WasmTestBedConfig::log_level]
+	pub fn take_runtime_logs(&self) -> Vec<RuntimeLog> {
+		self.testbed.take_logs()
+	}
+
+	/// Print the logs the runtime emitted since they were last taken on stderr, so they do not mix with the
+	/// json output
+	pub fn print_runtime_logs(&self) {
+		for log in self.take_runtime_logs() {
+			eprintln!("{log}");
+		}
+	}
+
+	/// Call a runtime API function with the SCALE encoded `input`. The host calls are only
+	/// traced if the runtime was loaded with [WasmTestBedConfig::trace].
+	pub fn call(&self, method: &str, input: &[u8]) -> CallReport {
+		let result = self.testbed.call(method, input);
+		CallReport::new(method, input, result, self.testbed.last_trace())
+	}
+
+	/// Access the genesis configs of the runtime, if it has the `GenesisBuilder` runtime API
+	pub fn genesis(&self) -> Result<Genesis<'_>> {
+		Genesis::new(&self.testbed)
+	}
+
+	/// Get the core version of the runtime
+	pub fn core_version(&self) -> SubstrateRuntimeVersion {
+		self.testbed.core_version()
 	}
 
-	pub fn runtime_info(&self) -> &RuntimeInfo {
-		&self.runtime_info
+	/// Print the core version either in a human readable form or as json.
+	pub fn print_version(&self, json: bool) -> Result<()> {
+		print_core_version(&self.core_version(), json)
 	}
 
 	pub fn write_metadata<O: Write>(
@@ -47,13 +160,13 @@ impl Subwasm {
 		filter: Option<String>,
 		out: &mut O,
 	) -> error::Result<()> {
-		let metadata = self.testbed.runtime_metadata_prefixed();
+		let metadata = self.testbed.runtime_metadata_prefixed()?;
 		let wrapper = MetadataWrapper(metadata);
 		wrapper.write(fmt, filter, out)
 	}
 
 	pub fn display_reduced_runtime(&self, json: bool) -> error::Result<()> {
-		let reduced_runtime: ReducedRuntime = self.testbed.metadata().into();
+		let reduced_runtime: ReducedRuntime = self.testbed.metadata()?.into();
 
 		if json {
 			let serialized = serde_json::to_string_pretty(&reduced_runtime)?;
@@ -64,7 +177,7 @@ impl Subwasm {
 	}
 
 	pub fn display_reduced_pallet(&self, pallet: &str, json: bool) -> error::Result<()> {
-		let reduced_runtime: ReducedRuntime = self.testbed.metadata().into();
+		let reduced_runtime: ReducedRuntime = self.testbed.metadata()?.into();
 		let pallet_maybe = reduced_runtime.get_pallet_by_name(pallet);
 
 		if let Some(reduced_pallet) = pallet_maybe {
@@ -82,7 +195,7 @@ impl Subwasm {
 	}
 
 	pub fn display_reduced_summary(&self, json: bool) -> Result<()> {
-		let reduced_runtime: ReducedRuntime = self.testbed.metadata().into();
+		let reduced_runtime: ReducedRuntime = self.testbed.metadata()?.into();
 		let reduced_runtime_summary: ReducedRuntimeSummary = ReducedRuntimeSummary::from(&reduced_runtime);
 		if json {
 			let serialized = serde_json::to_string_pretty(&reduced_runtime_summary)?;
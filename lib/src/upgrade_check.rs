@@ -0,0 +1,361 @@
+use frame_metadata::RuntimeMetadata;
+use num_format::{Locale, ToFormattedString};
+use scale::{Compact, Decode, Encode};
+use serde::Serialize;
+use sp_core::twox_128;
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+use wasm_testbed::{StateSnapshot, WasmTestBed};
+
+use crate::error::*;
+
+/// Function of the `TryRuntime` runtime API running the migrations
+const ON_RUNTIME_UPGRADE: &str = "TryRuntime_on_runtime_upgrade";
+
+/// Function of the `Core` runtime API, running the migrations when the runtime version changed
+const INITIALIZE_BLOCK: &str = "Core_initialize_block";
+
+/// Group of the storage keys that are not under a pallet prefix, such as `:code`
+const WELL_KNOWN_KEYS: &str = "[well known keys]";
+
+/// The checks `TryRuntime_on_runtime_upgrade` runs around the migrations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpgradeCheckSelect {
+	None,
+	All,
+	#[default]
+	PreAndPost,
+	TryState,
+}
+
+impl FromStr for UpgradeCheckSelect {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"none" => Ok(Self::None),
+			"all" => Ok(Self::All),
+			"pre-and-post" => Ok(Self::PreAndPost),
+			"try-state" => Ok(Self::TryState),
+			_ => Err(format!("Unknown checks `{s}`, expected none, all, pre-and-post or try-state")),
+		}
+	}
+}
+
+impl Display for UpgradeCheckSelect {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::None => write!(f, "none"),
+			Self::All => write!(f, "all"),
+			Self::PreAndPost => write!(f, "pre-and-post"),
+			Self::TryState => write!(f, "try-state"),
+		}
+	}
+}
+
+/// Weight of some execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Weight {
+	pub ref_time: u64,
+	pub proof_size: u64,
+}
+
+type WeightDecoder = fn(&mut &[u8]) -> std::result::Result<Weight, scale::Error>;
+
+impl Weight {
+	fn decode_v2(input: &mut &[u8]) -> std::result::Result<Self, scale::Error> {
+		Ok(Self { ref_time: Compact::<u64>::decode(input)?.0, proof_size: Compact::<u64>::decode(input)?.0 })
+	}
+
+	fn decode_v1(input: &mut &[u8]) -> std::result::Result<Self, scale::Error> {
+		u64::decode(input).map(|ref_time| Self { ref_time, proof_size: 0 })
+	}
+
+	/// Decode `count` weights, in their current encoding or as `u64` for runtimes older than the weights v2
+	fn decode_all(bytes: &[u8], count: usize) -> Option<Vec<Self>> {
+		let decoders: [WeightDecoder; 2] = [Self::decode_v2, Self::decode_v1];
+		decoders.iter().find_map(|decode| {
+			let input = &mut &bytes[..];
+			let weights = (0..count).map(|_| decode(input)).collect::<std::result::Result<Vec<_>, _>>().ok()?;
+			input.is_empty().then_some(weights)
+		})
+	}
+}
+
+impl Display for Weight {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"ref_time {}, proof_size {}",
+			self.ref_time.to_formatted_string(&Locale::en),
+			self.proof_size.to_formatted_string(&Locale::en)
+		)
+	}
+}
+
+/// Number of storage keys a pallet had changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PalletChanges {
+	pub pallet: String,
+	pub keys: usize,
+}
+
+/// The storage keys touched by the upgrade
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StorageChanges {
+	pub added: usize,
+	pub changed: usize,
+	pub removed: usize,
+
+	/// Keys touched, grouped by pallet, most touched first
+	pub pallets: Vec<PalletChanges>,
+}
+
+impl StorageChanges {
+	/// Compare the storage before and after the upgrade. `pallets` maps the storage prefixes to the pallet names.
+	fn new(
+		before: &BTreeMap<Vec<u8>, Vec<u8>>,
+		after: &BTreeMap<Vec<u8>, Vec<u8>>,
+		pallets: &BTreeMap<[u8; 16], String>,
+	) -> Self {
+		let mut changes = Self::default();
+		let mut touched: BTreeMap<String, usize> = BTreeMap::new();
+		let mut touch = |key: &[u8]| {
+			let pallet = match key.get(..16).and_then(|prefix| pallets.get(prefix)) {
+				Some(pallet) => pallet.clone(),
+				None if key.starts_with(b":") => WELL_KNOWN_KEYS.to_string(),
+				None => format!("0x{}", hex::encode(&key[..key.len().min(16)])),
+			};
+			*touched.entry(pallet).or_default() += 1;
+		};
+
+		for (key, value) in after {
+			match before.get(key) {
+				None => changes.added += 1,
+				Some(previous) if previous != value => changes.changed += 1,
+				Some(_) => continue,
+			}
+			touch(key);
+		}
+		for key in before.keys().filter(|key| !after.contains_key(*key)) {
+			changes.removed += 1;
+			touch(key);
+		}
+
+		changes.pallets = touched.into_iter().map(|(pallet, keys)| PalletChanges { pallet, keys }).collect();
+		changes.pallets.sort_by(|a, b| b.keys.cmp(&a.keys).then_with(|| a.pallet.cmp(&b.pallet)));
+		changes
+	}
+}
+
+/// Outcome of running the migrations of a runtime against a state snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeCheckReport {
+	/// Name and version of the new runtime
+	pub runtime: String,
+
+	/// The runtime API function that ran the migrations
+	pub method: String,
+
+	/// The checks `TryRuntime_on_runtime_upgrade` ran, if it was called
+	pub checks: Option<UpgradeCheckSelect>,
+
+	/// Weight consumed by the migrations, or by the whole block initialization
+	pub weight: Option<Weight>,
+
+	/// Maximum weight of a block, as reported by `TryRuntime_on_runtime_upgrade`
+	pub max_weight: Option<Weight>,
+
+	pub storage: StorageChanges,
+
+	/// Logs the runtime emitted
+	pub logs: Vec<String>,
+
+	/// Cause of the failure, such as a panic in a migration
+	pub error: Option<String>,
+}
+
+impl UpgradeCheckReport {
+	/// Run the migrations of the runtime of `testbed` against `state`, with `:code` replaced by the new runtime.
+	/// `TryRuntime_on_runtime_upgrade` is used when the runtime was built with try-runtime,
+	/// `Core_initialize_block` otherwise.
+	pub fn new(testbed: &WasmTestBed, state: &StateSnapshot, checks: UpgradeCheckSelect) -> Result<Self> {
+		let exports = wasm_loader::exports(testbed.wasm()).map_err(|e| SubwasmLibError::Generic(e.to_string()))?;
+		let try_runtime = exports.iter().any(|e| e == ON_RUNTIME_UPGRADE);
+
+		let mut before = state.storage.clone();
+		before.top.insert(b":code".to_vec(), testbed.raw_bytes().to_vec());
+		let mut ext = wasm_testbed::BasicExternalities::new(before.clone());
+
+		let (method, input) = match try_runtime {
+			true => (ON_RUNTIME_UPGRADE, (checks as u8).encode()),
+			false => (INITIALIZE_BLOCK, next_header(&before.top)),
+		};
+		let result = testbed.call_with_state(method, &input, &mut ext);
+		let logs = match &result {
+			Ok(result) => &result.logs[..],
+			Err(e) => e.logs(),
+		};
+		let logs = logs.iter().map(|log| log.to_string()).collect();
+		let after = ext.into_storages();
+
+		let (weight, max_weight) = match &result {
+			Ok(result) if try_runtime => match Weight::decode_all(&result.output, 2).as_deref() {
+				Some([weight, max_weight]) => (Some(*weight), Some(*max_weight)),
+				_ => (None, None),
+			},
+			Ok(_) => (block_weight(&after.top), None),
+			Err(_) => (None, None),
+		};
+
+		let version = testbed.core_version();
+		Ok(Self {
+			runtime: format!("{} v{}", version.spec_name, version.spec_version),
+			method: method.to_string(),
+			checks: try_runtime.then_some(checks),
+			weight,
+			max_weight,
+			storage: StorageChanges::new(&before.top, &after.top, &pallet_prefixes(testbed)),
+			logs,
+			error: result.err().map(|e| e.to_string()),
+		})
+	}
+
+	pub fn is_ok(&self) -> bool {
+		self.error.is_none()
+	}
+}
+
+/// Storage key of a storage value of the `System` pallet
+fn system_key(item: &str) -> Vec<u8> {
+	[twox_128(b"System"), twox_128(item.as_bytes())].concat()
+}
+
+/// Header of the block following the one of the state. We do not know the hash of the last block
+/// nor the roots, the runtime does not check them when initializing a block.
+fn next_header(storage: &BTreeMap<Vec<u8>, Vec<u8>>) -> Vec<u8> {
+	let number = storage.get(&system_key("Number")).and_then(|n| u32::decode(&mut &n[..]).ok()).unwrap_or_default();
+
+	let mut header = [0u8; 32].encode();
+	Compact(number + 1).encode_to(&mut header);
+	header.extend([0u8; 64]);
+	// empty digest
+	Compact(0u32).encode_to(&mut header);
+	header
+}
+
+/// Total weight of the block being built, read from `System::BlockWeight`
+fn block_weight(storage: &BTreeMap<Vec<u8>, Vec<u8>>) -> Option<Weight> {
+	let weights = Weight::decode_all(storage.get(&system_key("BlockWeight"))?, 3)?;
+	Some(weights.iter().fold(Weight::default(), |total, w| Weight {
+		ref_time: total.ref_time.saturating_add(w.ref_time),
+		proof_size: total.proof_size.saturating_add(w.proof_size),
+	}))
+}
+
+/// Map the storage prefixes of the pallets to their names, using the metadata of the runtime
+fn pallet_prefixes(testbed: &WasmTestBed) -> BTreeMap<[u8; 16], String> {
+	match testbed.metadata() {
+		Ok(RuntimeMetadata::V14(v14)) => v14
+			.pallets
+			.iter()
+			.filter_map(|pallet| pallet.storage.as_ref().map(|s| (twox_128(s.prefix.as_bytes()), pallet.name.clone())))
+			.collect(),
+		_ => BTreeMap::new(),
+	}
+}
+
+impl Display for UpgradeCheckReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Runtime: {}", self.runtime)?;
+		match self.checks {
+			Some(checks) => writeln!(f, "Ran {} with checks {checks}", self.method)?,
+			None => writeln!(f, "Ran {} as the runtime was not built with try-runtime", self.method)?,
+		}
+		if let Some(weight) = &self.weight {
+			write!(f, "Weight: {weight}")?;
+			if let Some(max) = self.max_weight.filter(|max| max.ref_time > 0) {
+				write!(f, " ({:.2}% of the block ref_time)", weight.ref_time as f64 / max.ref_time as f64 * 100.0)?;
+			}
+			writeln!(f)?;
+		}
+
+		let storage = &self.storage;
+		writeln!(f, "Storage: {} keys added, {} changed, {} removed", storage.added, storage.changed, storage.removed)?;
+		for pallet in &storage.pallets {
+			writeln!(f, "  {:<40} {:>10}", pallet.pallet, pallet.keys.to_formatted_string(&Locale::en))?;
+		}
+
+		if !self.logs.is_empty() {
+			writeln!(f, "Logs:")?;
+			for log in &self.logs {
+				writeln!(f, "  {log}")?;
+			}
+		}
+
+		match &self.error {
+			Some(error) => writeln!(f, "❌ The upgrade failed: {error}"),
+			None => writeln!(f, "✅ The upgrade succeeded"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests_upgrade_check {
+	use super::*;
+
+	#[test]
+	fn it_decodes_weights() {
+		let v2 = (Compact(10u64), Compact(20u64), Compact(30u64), Compact(40u64)).encode();
+		assert_eq!(
+			Some(vec![Weight { ref_time: 10, proof_size: 20 }, Weight { ref_time: 30, proof_size: 40 }]),
+			Weight::decode_all(&v2, 2)
+		);
+
+		let v1 = (1_000_000_000u64, 2_000_000_000u64).encode();
+		assert_eq!(
+			Some(vec![
+				Weight { ref_time: 1_000_000_000, proof_size: 0 },
+				Weight { ref_time: 2_000_000_000, proof_size: 0 }
+			]),
+			Weight::decode_all(&v1, 2)
+		);
+		assert_eq!(None, Weight::decode_all(&[0x01], 2));
+	}
+
+	#[test]
+	fn it_builds_the_next_header() {
+		let storage = BTreeMap::from([(system_key("Number"), 41u32.encode())]);
+		let header = next_header(&storage);
+		assert_eq!(32 + 1 + 64 + 1, header.len());
+		assert_eq!(Compact(42u32).encode(), header[32..33]);
+	}
+
+	#[test]
+	fn it_groups_storage_changes() {
+		let key = |pallet: &str, item: &str| [twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat();
+		let before = BTreeMap::from([
+			(key("System", "Number"), vec![1]),
+			(key("Balances", "TotalIssuance"), vec![1]),
+			(b":code".to_vec(), vec![0]),
+		]);
+		let after = BTreeMap::from([
+			(key("System", "Number"), vec![2]),
+			(key("System", "BlockWeight"), vec![1]),
+			(b":code".to_vec(), vec![0]),
+		]);
+		let pallets = BTreeMap::from([(twox_128(b"System"), "System".to_string())]);
+
+		let changes = StorageChanges::new(&before, &after, &pallets);
+		assert_eq!((1, 1, 1), (changes.added, changes.changed, changes.removed));
+		assert_eq!(PalletChanges { pallet: "System".into(), keys: 2 }, changes.pallets[0]);
+		assert_eq!(2, changes.pallets.len());
+	}
+
+	#[test]
+	fn it_parses_checks() {
+		assert_eq!(Ok(UpgradeCheckSelect::TryState), "try-state".parse());
+		assert!("everything".parse::<UpgradeCheckSelect>().is_err());
+		assert_eq!(2, UpgradeCheckSelect::PreAndPost as u8);
+	}
+}
@@ -0,0 +1,165 @@
+use frame_metadata::RuntimeMetadata;
+use scale::Encode;
+use serde::Serialize;
+use sp_core::{blake2_256, twox_128};
+use std::fmt::Display;
+use substrate_runtime_proposal_hash::call_info;
+use wasm_loader::{OnchainBlock, WasmLoader, CODE};
+use wasm_testbed::WasmTestBed;
+
+use crate::{error::*, preimage::*};
+
+/// How the hash found on chain compares with the hash of the local runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+	/// The chain holds the local runtime
+	Match,
+
+	/// The chain holds another runtime
+	Mismatch,
+
+	/// Nothing is stored on chain
+	Missing,
+}
+
+/// One of the storage items checked on chain
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyCheck {
+	/// The storage item, such as `System::AuthorizedUpgrade`
+	pub item: String,
+
+	/// The hash found on chain
+	pub on_chain: Option<String>,
+
+	pub status: CheckStatus,
+}
+
+/// What the chain has enacted, authorized or noted of a local runtime
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+	/// Hash of the local runtime, as used by `authorize_upgrade`
+	pub runtime: String,
+
+	/// The node the storage was read from
+	pub endpoint: String,
+
+	/// The block the storage was read at, the best block if not given
+	pub block: Option<String>,
+
+	pub checks: Vec<VerifyCheck>,
+}
+
+impl VerifyReport {
+	/// Read the storage of the chain at `reference` and compare it with the runtime of `testbed`.
+	/// The preimages are looked up for the upgrade calls encoded with the indices of `chain`, the metadata of
+	/// the runtime the chain currently runs, or the metadata of the runtime of `testbed` if not given.
+	pub fn new(testbed: &WasmTestBed, chain: Option<&RuntimeMetadata>, reference: &OnchainBlock) -> Result<Self> {
+		let metadata = match chain {
+			Some(metadata) => metadata,
+			None => testbed.metadata()?,
+		};
+		let hash = blake2_256(testbed.raw_bytes());
+		let fetch = |key: &str| WasmLoader::fetch_storage(reference, key).map_err(SubwasmLibError::from);
+
+		let mut checks = vec![VerifyCheck::new(":code", &hash, fetch(CODE)?.map(|code| blake2_256(&code)))];
+		for pallet in ["System", "ParachainSystem"] {
+			// Both versions of `ParachainSystem::AuthorizedUpgrade`, the hash alone or in a `CodeUpgradeAuthorization`,
+			// start with the code hash
+			let authorized = fetch(&storage_key(pallet, "AuthorizedUpgrade", &[]))?
+				.map(|value| value.get(..32).and_then(|hash| <[u8; 32]>::try_from(hash).ok()).unwrap_or_default());
+			checks.push(VerifyCheck::new(&format!("{pallet}::AuthorizedUpgrade"), &hash, authorized));
+		}
+
+		for call in UpgradeCall::ALL {
+			let (pallet, name) = call.pallet_and_call();
+			if call_info(metadata, pallet, name).is_none() {
+				continue;
+			}
+			let preimage = Preimage::new(testbed, Some(metadata), call, &[])?;
+			let key = (blake2_256(&preimage.bytes), preimage.len as u32).encode();
+			let noted = fetch(&storage_key("Preimage", "PreimageFor", &key))?;
+			checks.push(VerifyCheck {
+				item: format!("Preimage::PreimageFor of {call}"),
+				status: if noted.is_some() { CheckStatus::Match } else { CheckStatus::Missing },
+				on_chain: noted.map(|_| preimage.hash),
+			});
+		}
+
+		Ok(Self {
+			runtime: format!("0x{}", hex::encode(hash)),
+			endpoint: reference.endpoint.to_string(),
+			block: reference.block_ref.clone(),
+			checks,
+		})
+	}
+
+	/// The chain has enacted or authorized the runtime, or noted one of its upgrade calls
+	pub fn is_ok(&self) -> bool {
+		self.checks.iter().any(|check| check.status == CheckStatus::Match)
+	}
+}
+
+impl VerifyCheck {
+	fn new(item: &str, expected: &[u8; 32], on_chain: Option<[u8; 32]>) -> Self {
+		let status = match on_chain {
+			Some(hash) if &hash == expected => CheckStatus::Match,
+			Some(_) => CheckStatus::Mismatch,
+			None => CheckStatus::Missing,
+		};
+		Self { item: item.to_string(), on_chain: on_chain.map(|hash| format!("0x{}", hex::encode(hash))), status }
+	}
+}
+
+/// Hex key of a storage item, `suffix` being the hashed key of a map entry
+fn storage_key(pallet: &str, item: &str, suffix: &[u8]) -> String {
+	format!("0x{}", hex::encode([&twox_128(pallet.as_bytes())[..], &twox_128(item.as_bytes()), suffix].concat()))
+}
+
+impl Display for VerifyReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Runtime:  {}", self.runtime)?;
+		writeln!(f, "Endpoint: {}", self.endpoint)?;
+		if let Some(block) = &self.block {
+			writeln!(f, "Block:    {block}")?;
+		}
+		for check in &self.checks {
+			let status = match check.status {
+				CheckStatus::Match => "✅",
+				CheckStatus::Mismatch => "❌",
+				CheckStatus::Missing => "➖",
+			};
+			writeln!(f, "{status} {:<55} {}", check.item, check.on_chain.as_deref().unwrap_or("-"))?;
+		}
+
+		if self.is_ok() {
+			writeln!(f, "✅ The chain knows this runtime")
+		} else {
+			writeln!(f, "❌ The chain neither runs, authorized nor noted this runtime")
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests_verify {
+	use super::*;
+
+	#[test]
+	fn it_builds_storage_keys() {
+		assert_eq!(
+			"0x26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac",
+			storage_key("System", "Number", &[])
+		);
+		assert_eq!(
+			"0x26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac2a",
+			storage_key("System", "Number", &[0x2a])
+		);
+	}
+
+	#[test]
+	fn it_compares_hashes() {
+		assert_eq!(CheckStatus::Match, VerifyCheck::new(":code", &[1; 32], Some([1; 32])).status);
+		assert_eq!(CheckStatus::Mismatch, VerifyCheck::new(":code", &[1; 32], Some([2; 32])).status);
+		assert_eq!(CheckStatus::Missing, VerifyCheck::new(":code", &[1; 32], None).status);
+	}
+}
@@ -0,0 +1,287 @@
+use frame_metadata::RuntimeMetadata;
+use scale::{Compact, Encode};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive, Variant};
+use serde::Serialize;
+use std::fmt::Display;
+use substrate_runtime_proposal_hash::{call_info, encode_call};
+use wasm_testbed::WasmTestBed;
+
+use crate::{error::*, preimage::*};
+
+/// Names of the XCM pallet in the relay chain runtimes, then in the parachain runtimes
+const XCM_PALLETS: [&str; 2] = ["XcmPallet", "PolkadotXcm"];
+
+/// Versions of the locations and messages we can encode, latest first
+const LOCATION_VERSIONS: [&str; 4] = ["V5", "V4", "V3", "V1"];
+const XCM_VERSIONS: [&str; 4] = ["V5", "V4", "V3", "V2"];
+
+/// What to send to the parachain
+#[derive(Debug, Clone)]
+pub struct XcmUpgradeOptions {
+	/// Id of the parachain to upgrade
+	pub para_id: u32,
+
+	/// The upgrade call executed by the parachain
+	pub call: UpgradeCall,
+
+	/// Weight limit of the `Transact` instruction
+	pub ref_time: u64,
+	pub proof_size: u64,
+
+	/// Calls to wrap the relay chain call in, innermost first
+	pub wrappers: Vec<CallWrapper>,
+}
+
+/// The relay chain call sending an upgrade call to a parachain with `XcmPallet::send`.
+/// The message pays no fees and executes the call as the relay chain: `UnpaidExecution` and
+/// `Transact` with the `Superuser` origin.
+#[derive(Debug, Clone, Serialize)]
+pub struct XcmUpgrade {
+	/// Id of the parachain to upgrade
+	pub para_id: u32,
+
+	/// Version of the XCM message, the latest the relay chain supports
+	pub xcm_version: String,
+
+	/// The call executed by the parachain
+	pub parachain_call: Preimage,
+
+	/// The call to submit to the relay chain governance
+	pub relay_call: Preimage,
+}
+
+impl XcmUpgrade {
+	/// Encode the upgrade of `parachain` with the indices of its own metadata, then the XCM message with the
+	/// types of the `relay` chain metadata.
+	pub fn new(parachain: &WasmTestBed, relay: &RuntimeMetadata, options: &XcmUpgradeOptions) -> Result<Self> {
+		let parachain_call = Preimage::new(parachain, None, options.call, &[])?;
+
+		let types = registry(relay).ok_or(SubwasmLibError::UnsupportedRuntimeVersion())?;
+		let (pallet, send) = XCM_PALLETS
+			.iter()
+			.find_map(|pallet| call_info(relay, pallet, "send").map(|send| (*pallet, send)))
+			.ok_or_else(|| SubwasmLibError::NotFound("XcmPallet::send".into()))?;
+		let &[destination, message] = &send.argument_types[..] else {
+			return Err(SubwasmLibError::Generic(format!("Unexpected arguments for {pallet}::send")));
+		};
+
+		let destination = encode_destination(types, destination, options.para_id).ok_or_else(|| {
+			SubwasmLibError::Generic("Cannot encode the parachain location for the relay chain".into())
+		})?;
+		let (xcm_version, instructions, message) =
+			encode_message(types, message, &parachain_call.bytes, (options.ref_time, options.proof_size))
+				.ok_or_else(|| SubwasmLibError::Generic("Cannot encode the XCM message for the relay chain".into()))?;
+
+		let description = format!(
+			"{pallet}.send(Parachain({}), {xcm_version}[{}({})])",
+			options.para_id,
+			instructions.join(", "),
+			parachain_call.call
+		);
+		let (bytes, description) =
+			wrap(relay, encode_call(send.prefix, &[destination, message].concat()), description, &options.wrappers)?;
+
+		Ok(Self {
+			para_id: options.para_id,
+			xcm_version: xcm_version.to_string(),
+			parachain_call,
+			relay_call: Preimage::from_call(description, bytes)?,
+		})
+	}
+}
+
+fn variant<'a>(types: &'a PortableRegistry, id: u32, name: &str) -> Option<&'a Variant<PortableForm>> {
+	let TypeDef::Variant(def) = &types.resolve(id)?.type_def else {
+		return None;
+	};
+	def.variants.iter().find(|variant| variant.name == name)
+}
+
+/// The latest of the `versions` of the versioned type `id`, with its index and the type it wraps
+fn latest(types: &PortableRegistry, id: u32, versions: &[&'static str]) -> Option<(&'static str, u8, u32)> {
+	versions.iter().find_map(|version| {
+		let variant = variant(types, id, version)?;
+		Some((*version, variant.index, variant.fields.first()?.ty.id))
+	})
+}
+
+/// Encode the location of the parachain, as seen from the relay chain: `{ parents: 0, interior: X1(Parachain(id)) }`
+fn encode_destination(types: &PortableRegistry, id: u32, para_id: u32) -> Option<Vec<u8>> {
+	let (_, index, location) = latest(types, id, &LOCATION_VERSIONS)?;
+	let TypeDef::Composite(location) = &types.resolve(location)?.type_def else {
+		return None;
+	};
+	let interior = location.fields.iter().find(|field| field.name.as_deref() == Some("interior"))?.ty.id;
+	let x1 = variant(types, interior, "X1")?;
+
+	// Since XCM v4, X1 holds an array of one junction
+	let mut junction = x1.fields.first()?.ty.id;
+	if let TypeDef::Array(array) = &types.resolve(junction)?.type_def {
+		junction = array.type_param.id;
+	}
+	let parachain = variant(types, junction, "Parachain")?;
+
+	Some([vec![index, 0, x1.index, parachain.index], Compact(para_id).encode()].concat())
+}
+
+/// Encode the message executing `call` on the parachain, and return it with its version and the names of its instructions
+fn encode_message(
+	types: &PortableRegistry,
+	id: u32,
+	call: &[u8],
+	(ref_time, proof_size): (u64, u64),
+) -> Option<(&'static str, Vec<&'static str>, Vec<u8>)> {
+	let (version, index, xcm) = latest(types, id, &XCM_VERSIONS)?;
+	let TypeDef::Composite(xcm) = &types.resolve(xcm)?.type_def else {
+		return None;
+	};
+	let TypeDef::Sequence(instructions) = &types.resolve(xcm.fields.first()?.ty.id)?.type_def else {
+		return None;
+	};
+	let instruction = instructions.type_param.id;
+
+	let mut names = Vec::new();
+	let mut encoded = Vec::new();
+
+	// Since XCM v3, the system parachains only execute messages of the relay chain asking for no fees.
+	// The zero values are an unlimited weight and no origin check.
+	if let Some(unpaid) = variant(types, instruction, "UnpaidExecution") {
+		encoded.push(unpaid.index);
+		for field in &unpaid.fields {
+			encoded.extend(zero_value(types, field.ty.id)?);
+		}
+		names.push("UnpaidExecution");
+	}
+
+	let transact = variant(types, instruction, "Transact")?;
+	encoded.push(transact.index);
+	for field in &transact.fields {
+		match field.name.as_deref()? {
+			"origin_kind" | "origin_type" => encoded.push(variant(types, field.ty.id, "Superuser")?.index),
+			"require_weight_at_most" => encoded.extend(match &types.resolve(field.ty.id)?.type_def {
+				TypeDef::Primitive(TypeDefPrimitive::U64) => ref_time.encode(),
+				TypeDef::Compact(_) => Compact(ref_time).encode(),
+				TypeDef::Composite(_) => [Compact(ref_time).encode(), Compact(proof_size).encode()].concat(),
+				_ => return None,
+			}),
+			"fallback_max_weight" => encoded.push(variant(types, field.ty.id, "None")?.index),
+			"call" => encoded.extend(call.encode()),
+			_ => return None,
+		}
+	}
+	names.push("Transact");
+
+	let encoded = [vec![index], Compact(names.len() as u32).encode(), encoded].concat();
+	Some((version, names, encoded))
+}
+
+impl Display for XcmUpgrade {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Parachain call: {}", self.parachain_call.call)?;
+		writeln!(f, "Parachain hash: {}", self.parachain_call.hash)?;
+		writeln!(f, "XCM version:    {}", self.xcm_version)?;
+		write!(f, "{}", self.relay_call)
+	}
+}
+
+#[cfg(test)]
+mod tests_xcm_upgrade {
+	use super::*;
+	use scale_info::{meta_type, Registry};
+
+	#[allow(dead_code)]
+	mod xcm {
+		use scale_info::TypeInfo;
+
+		#[derive(TypeInfo)]
+		pub enum Junction {
+			Parachain(#[codec(compact)] u32),
+			OnlyChild,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum Junctions {
+			Here,
+			X1([Junction; 1]),
+		}
+
+		#[derive(TypeInfo)]
+		pub struct Location {
+			parents: u8,
+			interior: Junctions,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum VersionedLocation {
+			#[codec(index = 4)]
+			V4(Location),
+		}
+
+		#[derive(TypeInfo)]
+		pub struct Weight {
+			#[codec(compact)]
+			ref_time: u64,
+			#[codec(compact)]
+			proof_size: u64,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum WeightLimit {
+			Unlimited,
+			Limited(Weight),
+		}
+
+		#[derive(TypeInfo)]
+		pub enum OriginKind {
+			Native,
+			SovereignAccount,
+			Superuser,
+			Xcm,
+		}
+
+		#[derive(TypeInfo)]
+		pub struct DoubleEncoded {
+			encoded: Vec<u8>,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum Instruction {
+			#[codec(index = 6)]
+			Transact { origin_kind: OriginKind, require_weight_at_most: Weight, call: DoubleEncoded },
+			#[codec(index = 47)]
+			UnpaidExecution { weight_limit: WeightLimit, check_origin: Option<Location> },
+		}
+
+		#[derive(TypeInfo)]
+		pub struct Xcm(Vec<Instruction>);
+
+		#[derive(TypeInfo)]
+		pub enum VersionedXcm {
+			#[codec(index = 4)]
+			V4(Xcm),
+		}
+	}
+
+	#[test]
+	fn it_encodes_the_xcm_v4_send_arguments() {
+		let mut registry = Registry::new();
+		let location = registry.register_type(&meta_type::<xcm::VersionedLocation>()).id;
+		let message = registry.register_type(&meta_type::<xcm::VersionedXcm>()).id;
+		let types: PortableRegistry = registry.into();
+
+		assert_eq!(Some(vec![4, 0, 1, 0, 0xa1, 0x0f]), encode_destination(&types, location, 1000));
+		assert_eq!(
+			Some(("V4", vec!["UnpaidExecution", "Transact"], vec![4, 8, 47, 0, 0, 6, 2, 40, 80, 12, 1, 2, 3])),
+			encode_message(&types, message, &[1, 2, 3], (10, 20))
+		);
+	}
+
+	#[test]
+	fn it_rejects_unknown_versions() {
+		let mut registry = Registry::new();
+		let location = registry.register_type(&meta_type::<xcm::Location>()).id;
+		let types: PortableRegistry = registry.into();
+
+		assert_eq!(None, encode_destination(&types, location, 1000));
+	}
+}
@@ -41,8 +41,16 @@ mod test_diffanalyzer {
 		let a = rf1.try_into().expect("Runtime file should exist");
 		let b = rf2.try_into().expect("Runtime file should exist");
 
-		let ra = WasmTestBed::new(&Source::File(a)).expect("Failed loading runtime").metadata().into();
-		let rb = WasmTestBed::new(&Source::File(b)).expect("Failed loading runtime").metadata().into();
+		let ra = WasmTestBed::new(&Source::File(a))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
+		let rb = WasmTestBed::new(&Source::File(b))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
 		ReducedDiffResult::new(ra, rb).changes.map(DiffAnalyzer::new)
 	}
 
@@ -50,8 +58,8 @@ mod test_diffanalyzer {
 		let a = WasmTestBed::new(&Source::File(runtime_a)).expect("Failed loading runtime");
 		let b = WasmTestBed::new(&Source::File(runtime_b)).expect("Failed loading runtime");
 
-		let ra = a.metadata().into();
-		let rb = b.metadata().into();
+		let ra = a.metadata().expect("Failed decoding metadata").into();
+		let rb = b.metadata().expect("Failed decoding metadata").into();
 		let diff_result = ReducedDiffResult::new(ra, rb);
 		diff_result.require_transaction_version_bump()
 	}
@@ -60,8 +68,8 @@ mod test_diffanalyzer {
 		let a = WasmTestBed::new(&Source::File(runtime_a)).expect("Failed loading runtime");
 		let b = WasmTestBed::new(&Source::File(runtime_b)).expect("Failed loading runtime");
 
-		let ra = a.metadata().into();
-		let rb = b.metadata().into();
+		let ra = a.metadata().expect("Failed decoding metadata").into();
+		let rb = b.metadata().expect("Failed decoding metadata").into();
 		let diff_result = ReducedDiffResult::new(ra, rb);
 		diff_result.compatible()
 	}
@@ -192,7 +200,10 @@ mod test_diffanalyzer {
 	fn test_different_variants_v13_v14() {
 		let a = WasmTestBed::new(&Source::File(PathBuf::from(RUNTIME_V13_1))).expect("Failed loading runtime");
 		let b = WasmTestBed::new(&Source::File(PathBuf::from(RUNTIME_V14))).expect("Failed loading runtime");
-		let _differ = ReducedDiffer::new(a.metadata(), b.metadata());
+		let _differ = ReducedDiffer::new(
+			a.metadata().expect("Failed decoding metadata"),
+			b.metadata().expect("Failed decoding metadata"),
+		);
 	}
 
 	#[test]
@@ -257,8 +268,16 @@ mod test_diffanalyzer {
 		// assert!(!analyzer.require_tx_version_bump());
 		// assert!(analyzer.compatible());
 
-		let ra = WasmTestBed::new(&Source::File(a.clone())).expect("Failed loading runtime").metadata().into();
-		let rb = WasmTestBed::new(&Source::File(a.clone())).expect("Failed loading runtime").metadata().into();
+		let ra = WasmTestBed::new(&Source::File(a.clone()))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
+		let rb = WasmTestBed::new(&Source::File(a.clone()))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
 
 		let diff_result = ReducedDiffResult::new(ra, rb);
 		assert!(!diff_result.require_transaction_version_bump());
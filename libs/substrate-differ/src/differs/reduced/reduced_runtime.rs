@@ -227,8 +227,11 @@ mod test_reduced_runtime {
 		use wasm_testbed::WasmTestBed;
 
 		let runtime_file = RuntimeFile::new(Chain::Polkadot, 14, 9290).try_into().expect("Runtime file should exist");
-		let _reduced_runtime: ReducedRuntime =
-			WasmTestBed::new(&Source::File(runtime_file)).expect("Failed loading runtime").metadata().into();
+		let _reduced_runtime: ReducedRuntime = WasmTestBed::new(&Source::File(runtime_file))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
 	}
 
 	#[test]
@@ -240,8 +243,11 @@ mod test_reduced_runtime {
 		use wasm_testbed::WasmTestBed;
 
 		let runtime_file = RuntimeFile::new(Chain::Polkadot, 14, 9290).try_into().expect("Runtime file should exist");
-		let reduced_runtime: ReducedRuntime =
-			WasmTestBed::new(&Source::File(runtime_file)).expect("Failed loading runtime").metadata().into();
+		let reduced_runtime: ReducedRuntime = WasmTestBed::new(&Source::File(runtime_file))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
 		assert_eq!(0_u32, reduced_runtime.get_pallet_by_name("System").expect("Failed getting pallet by name").index);
 		assert_eq!(
 			1_u32,
@@ -257,8 +263,11 @@ mod test_reduced_runtime {
 		use wasm_testbed::WasmTestBed;
 
 		let runtime_file = RuntimeFile::new(Chain::Polkadot, 14, 9290).try_into().expect("Runtime file should exist");
-		let reduced_runtime: ReducedRuntime =
-			WasmTestBed::new(&Source::File(runtime_file)).expect("Failed loading runtime").metadata().into();
+		let reduced_runtime: ReducedRuntime = WasmTestBed::new(&Source::File(runtime_file))
+			.expect("Failed loading runtime")
+			.metadata()
+			.expect("Failed decoding metadata")
+			.into();
 
 		println!("reduced_runtime = {:#?}", reduced_runtime);
 		// println!("extrinsics = {:#?}", reduced_runtime.extrinsic);
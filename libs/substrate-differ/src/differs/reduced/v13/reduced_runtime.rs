@@ -148,7 +148,7 @@ mod test_reduced_conversion {
 	fn test_reduce_v13() {
 		let runtime_v13 = get_runtime_file(RuntimeFile::new(Chain::Polkadot, 13, 9030)).unwrap();
 		let testbed = WasmTestBed::new(&Source::File(PathBuf::from(runtime_v13))).unwrap();
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 		match metadata {
 			V13(v13) => {
 				let rrtm = reduced_runtime::ReducedRuntime::from_v13(v13).unwrap();
@@ -162,7 +162,7 @@ mod test_reduced_conversion {
 	#[test]
 	fn test_reduce_v13() {
 		let testbed = WasmTestBed::new(&Source::File(PathBuf::from(RUNTIME_V14))).unwrap();
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			V13(v13) => {
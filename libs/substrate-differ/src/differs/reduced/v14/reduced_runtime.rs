@@ -17,11 +17,11 @@ mod test_reduced_conversion {
 	fn test_reduce_v14_first_pallet_first_call() {
 		let rtm1 = RuntimeFile::new(Chain::Polkadot, 14, 9260).try_into().expect("Runtime file should exist");
 		let testbed = WasmTestBed::new(&Source::File(rtm1)).expect("Failed loading runtime");
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			RuntimeMetadata::V14(v14) => {
-				let rrtm = reduced_runtime::ReducedRuntime::from_v14(&v14).expect("Failed reducing runtime");
+				let rrtm = reduced_runtime::ReducedRuntime::from_v14(v14).expect("Failed reducing runtime");
 				assert_eq!(rrtm.pallets.len(), 51);
 
 				let first_pallet = &rrtm.pallets[&0];
@@ -41,11 +41,11 @@ mod test_reduced_conversion {
 		let rtm1 = RuntimeFile::new(Chain::Polkadot, 14, 9260).try_into().expect("Runtime file should exist");
 
 		let testbed = WasmTestBed::new(&Source::File(rtm1)).expect("Failed loading runtime");
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			RuntimeMetadata::V14(v14) => {
-				let rrtm = reduced_runtime::ReducedRuntime::from_v14(&v14).expect("Failed reducing runtime");
+				let rrtm = reduced_runtime::ReducedRuntime::from_v14(v14).expect("Failed reducing runtime");
 				assert_eq!(rrtm.pallets.len(), 51);
 
 				let first_pallet = &rrtm.pallets[&0];
@@ -64,7 +64,7 @@ mod test_reduced_conversion {
 	fn test_reduce_v14_events() {
 		let rtm1 = RuntimeFile::new(Chain::Polkadot, 14, 9260).try_into().expect("Runtime file should exist");
 		let testbed = WasmTestBed::new(&Source::File(rtm1)).expect("Failed loading runtime");
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			RuntimeMetadata::V14(v14) => {
@@ -87,7 +87,7 @@ mod test_reduced_conversion {
 	fn test_reduce_v14_global() {
 		let rtm1 = RuntimeFile::new(Chain::Polkadot, 14, 9260).try_into().expect("Runtime file should exist");
 		let testbed = WasmTestBed::new(&Source::File(rtm1)).expect("Failed loading wasm");
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			RuntimeMetadata::V14(v14) => {
@@ -116,7 +116,7 @@ mod test_reduced_conversion {
 	fn test_reduce_v14_storages() {
 		let rtm1 = RuntimeFile::new(Chain::Polkadot, 14, 9260).try_into().expect("Runtime file should exist");
 		let testbed = WasmTestBed::new(&Source::File(rtm1)).expect("Failed loading wasm");
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			RuntimeMetadata::V14(v14) => {
@@ -139,7 +139,7 @@ mod test_reduced_conversion {
 	fn test_reduce_v14_constants() {
 		let rtm1 = RuntimeFile::new(Chain::Polkadot, 14, 9260).try_into().expect("Runtime file should exist");
 		let testbed = WasmTestBed::new(&Source::File(rtm1)).expect("Failed loading wasm");
-		let metadata = testbed.metadata();
+		let metadata = testbed.metadata().expect("Failed decoding metadata");
 
 		match metadata {
 			RuntimeMetadata::V14(v14) => {
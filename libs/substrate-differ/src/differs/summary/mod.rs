@@ -32,8 +32,8 @@ impl<'a> RuntimeSummaryDiffer<'a> {
 		}
 
 		// METADATA VERSIONS
-		let metadata_a_version = self.r1.metadata_version();
-		let metadata_b_version = self.r2.metadata_version();
+		let metadata_a_version = self.r1.metadata_version().ok();
+		let metadata_b_version = self.r2.metadata_version().ok();
 		println!("Checking metadata versions:");
 		if metadata_a_version == metadata_b_version {
 			println!("  ✅ Both metadata versions are identical: V{metadata_a_version:?}");
@@ -58,8 +58,8 @@ impl<'a> RuntimeSummaryDiffer<'a> {
 		}
 
 		println!("Checking runtime metadata:");
-		let metadata_a = self.r1.metadata();
-		let metadata_b = self.r2.metadata();
+		let metadata_a = self.r1.metadata().ok();
+		let metadata_b = self.r2.metadata().ok();
 
 		if metadata_a == metadata_b {
 			println!("  ✅  The metadata are identical");
@@ -4,6 +4,8 @@ use blake2::digest::{Update, VariableOutput};
 use blake2::Blake2bVar;
 use codec::Encode;
 use error::*;
+use frame_metadata::RuntimeMetadata;
+use scale_info::{PortableRegistry, TypeDef};
 use sp_core::Hasher;
 use sp_runtime::traits::BlakeTwo256;
 
@@ -13,11 +15,29 @@ pub const SIZE: usize = 32;
 /// Type for our Proposal hash
 pub type CalllHash = [u8; SIZE];
 
-type Prefix = (u8, u8);
+/// Index of the pallet and index of the call in the pallet, they are prepended to the arguments of a call
+pub type Prefix = (u8, u8);
 
-/// The PREFIX is prepended to the data before hashing
+/// The PREFIX is prepended to the data before hashing.
+/// This is only used when the metadata of the runtime does not tell where `System::set_code` is.
 pub const PREFIX_SYSTEM_SETCODE: Prefix = (0x00, 0x02);
 
+pub const SYSTEM_PALLET: &str = "System";
+pub const SET_CODE_CALL: &str = "set_code";
+
+pub const PARACHAIN_SYSTEM_PALLET: &str = "ParachainSystem";
+pub const AUTHORIZE_UPGRADE_CALL: &str = "authorize_upgrade";
+
+pub const AUTHORIZE_UPGRADE_WITHOUT_CHECKS_CALL: &str = "authorize_upgrade_without_checks";
+pub const APPLY_AUTHORIZED_UPGRADE_CALL: &str = "apply_authorized_upgrade";
+
+/// Calls of the `System` pallet of the recent chains, upgrading the runtime in two steps
+pub const SYSTEM_UPGRADE_CALLS: [&str; 3] =
+	[AUTHORIZE_UPGRADE_CALL, AUTHORIZE_UPGRADE_WITHOUT_CHECKS_CALL, APPLY_AUTHORIZED_UPGRADE_CALL];
+
+/// Argument of `authorize_upgrade` added in Cumulus v0.9.41
+pub const CHECK_VERSION_ARGUMENT: &str = "check_version";
+
 pub const PARACHAIN_PALLET_ID_ENV: &str = "PARACHAIN_PALLET_ID";
 pub const DEFAULT_PARACHAIN_PALLET_ID: &str = "0x01";
 
@@ -43,14 +63,63 @@ pub fn concatenate_arrays<T: Clone>(x: &[T], y: &[T]) -> Vec<T> {
 	concat
 }
 
+/// Where a call is and what arguments it takes, according to the metadata of a runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInfo {
+	pub prefix: Prefix,
+
+	/// Names of the arguments of the call, in order
+	pub arguments: Vec<String>,
+
+	/// Type ids of the arguments of the call in the type registry of the metadata, in order
+	pub argument_types: Vec<u32>,
+}
+
+impl CallInfo {
+	pub fn has_argument(&self, name: &str) -> bool {
+		self.arguments.iter().any(|argument| argument == name)
+	}
+}
+
+/// Find `pallet::call` in the metadata of a runtime.
+/// Only the metadata V14 and later describe the calls with their indices.
+pub fn call_info(metadata: &RuntimeMetadata, pallet: &str, call: &str) -> Option<CallInfo> {
+	let find = |types: &PortableRegistry, index: u8, calls: Option<u32>| {
+		let TypeDef::Variant(calls) = &types.resolve(calls?)?.type_def else {
+			return None;
+		};
+		calls.variants.iter().find(|variant| variant.name == call).map(|variant| CallInfo {
+			prefix: (index, variant.index),
+			arguments: variant.fields.iter().filter_map(|field| field.name.clone()).collect(),
+			argument_types: variant.fields.iter().map(|field| field.ty.id).collect(),
+		})
+	};
+
+	match metadata {
+		RuntimeMetadata::V14(v14) => v14
+			.pallets
+			.iter()
+			.find(|p| p.name == pallet)
+			.and_then(|p| find(&v14.types, p.index, p.calls.as_ref().map(|c| c.ty.id))),
+		RuntimeMetadata::V15(v15) => v15
+			.pallets
+			.iter()
+			.find(|p| p.name == pallet)
+			.and_then(|p| find(&v15.types, p.index, p.calls.as_ref().map(|c| c.ty.id))),
+		_ => None,
+	}
+}
+
+/// Find the pallet index and the call index of `pallet::call` in the metadata of a runtime
+pub fn call_prefix(metadata: &RuntimeMetadata, pallet: &str, call: &str) -> Option<Prefix> {
+	call_info(metadata, pallet, call).map(|info| info.prefix)
+}
+
 /// Generate our result object
 pub fn get_result(prefix: Prefix, buffer: &[u8]) -> Result<SrhResult> {
-	buffer.using_encoded(|wasm_blob: &[u8]| {
-		let result = match get_call_hash(prefix, wasm_blob) {
-			Ok(hash) => Ok(SrhResult { hash, encoded_hash: hex::encode(hash) }),
-			Err(_e) => Err(RuntimePropHashError::HashComputing()),
-		};
-		result
+	buffer.using_encoded(|wasm_blob: &[u8]| match get_call_hash(prefix, wasm_blob) {
+		Ok(hash) => Ok(SrhResult { hash, encoded_hash: hex::encode(hash) }),
+		Err(_e) => Err(RuntimePropHashError::HashComputing()),
 	})
 }
 
@@ -60,6 +129,7 @@ pub fn get_result(prefix: Prefix, buffer: &[u8]) -> Result<SrhResult> {
 /// * `wasm_blob` - The WASM blob
 /// # Returns
 /// * `CalllHash` - The hash of the proposal as calculated on chain
+///
 /// This function replaces the deprecated `get_proposal_hash`
 pub fn get_system_setcode(wasm_blob: &[u8]) -> Result<CalllHash> {
 	get_call_hash(PREFIX_SYSTEM_SETCODE, wasm_blob)
@@ -81,6 +151,31 @@ pub fn get_parachainsystem_authorize_upgrade(
 	Ok(call_hash)
 }
 
+/// Calculate the hash of `System::authorize_upgrade(code_hash)` or `System::authorize_upgrade_without_checks(code_hash)`
+pub fn get_system_authorize_upgrade(prefix: Prefix, wasm_blob: &[u8]) -> Result<CalllHash> {
+	get_parachainsystem_authorize_upgrade(prefix, wasm_blob, None)
+}
+
+/// Calculate the hash of `System::apply_authorized_upgrade(code)`, which takes the code the same way as `set_code`
+pub fn get_system_apply_authorized_upgrade(prefix: Prefix, wasm_blob: &[u8]) -> Result<CalllHash> {
+	get_result(prefix, wasm_blob).map(|result| result.hash)
+}
+
+/// Encode a call from its pallet and call indices and its SCALE encoded arguments.
+/// Those are the bytes to note as a preimage.
+pub fn encode_call(prefix: Prefix, arguments: &[u8]) -> Vec<u8> {
+	[[prefix.0, prefix.1].as_slice(), arguments].concat()
+}
+
+/// Calculate the hash of an encoded call, which is the hash of its preimage
+pub fn get_preimage_hash(call: &[u8]) -> Result<CalllHash> {
+	let mut hasher = Blake2bVar::new(SIZE)?;
+	hasher.update(call);
+	let mut result: CalllHash = [0; SIZE];
+	hasher.finalize_variable(&mut result)?;
+	Ok(result)
+}
+
 fn get_call_hash(prefix: Prefix, wasm_blob: &[u8]) -> Result<CalllHash> {
 	let mut hasher = Blake2bVar::new(SIZE)?;
 	let prefix_array = vec![prefix.0, prefix.1];
@@ -93,6 +188,46 @@ fn get_call_hash(prefix: Prefix, wasm_blob: &[u8]) -> Result<CalllHash> {
 #[cfg(test)]
 mod prop_hash_tests {
 	use super::*;
+	use frame_metadata::v14::{ExtrinsicMetadata, PalletCallMetadata, PalletMetadata, RuntimeMetadataV14};
+	use scale_info::{meta_type, TypeInfo};
+
+	#[allow(dead_code, non_camel_case_types)]
+	#[derive(TypeInfo)]
+	enum ParachainSystemCall {
+		#[codec(index = 0)]
+		set_validation_data,
+		#[codec(index = 2)]
+		authorize_upgrade { code_hash: [u8; 32], check_version: bool },
+	}
+
+	fn metadata() -> RuntimeMetadata {
+		let pallet = PalletMetadata {
+			name: PARACHAIN_SYSTEM_PALLET,
+			storage: None,
+			calls: Some(PalletCallMetadata { ty: meta_type::<ParachainSystemCall>() }),
+			event: None,
+			constants: vec![],
+			error: None,
+			index: 42,
+		};
+		let extrinsic = ExtrinsicMetadata { ty: meta_type::<()>(), version: 4, signed_extensions: vec![] };
+		RuntimeMetadata::V14(RuntimeMetadataV14::new(vec![pallet], extrinsic, meta_type::<()>()))
+	}
+
+	#[test]
+	fn it_finds_call_prefixes() {
+		let metadata = metadata();
+		assert_eq!(Some((42, 2)), call_prefix(&metadata, PARACHAIN_SYSTEM_PALLET, AUTHORIZE_UPGRADE_CALL));
+		assert_eq!(None, call_prefix(&metadata, PARACHAIN_SYSTEM_PALLET, "enact_authorized_upgrade"));
+		assert_eq!(None, call_prefix(&metadata, SYSTEM_PALLET, SET_CODE_CALL));
+	}
+
+	#[test]
+	fn it_reads_call_arguments() {
+		let info = call_info(&metadata(), PARACHAIN_SYSTEM_PALLET, AUTHORIZE_UPGRADE_CALL).expect("Call not found");
+		assert_eq!(vec!["code_hash", "check_version"], info.arguments);
+		assert!(info.has_argument(CHECK_VERSION_ARGUMENT));
+	}
 
 	#[test]
 	fn test_call_hash() {
@@ -204,6 +339,31 @@ mod prop_hash_tests {
 		);
 	}
 
+	#[test]
+	fn test_system_upgrade_calls() {
+		let wasm = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+		assert_eq!(
+			get_parachainsystem_authorize_upgrade((0x00, 0x09), &wasm, None).expect("Failed getting a hash"),
+			get_system_authorize_upgrade((0x00, 0x09), &wasm).expect("Failed getting a hash")
+		);
+		assert_eq!(
+			hex::encode(
+				get_system_apply_authorized_upgrade(PREFIX_SYSTEM_SETCODE, &[1, 2, 42]).expect("Failed getting a hash")
+			),
+			"85233004e044626a6c08e469573369536d8de9b264b08eb6611b76b80148e429"
+		);
+	}
+
+	#[test]
+	fn test_preimage_hash() {
+		let call = encode_call(PREFIX_SYSTEM_SETCODE, &vec![1u8, 2, 42].encode());
+		assert_eq!(vec![0x00, 0x02, 0x0c, 1, 2, 42], call);
+		assert_eq!(
+			get_call_hash(PREFIX_SYSTEM_SETCODE, &vec![1u8, 2, 42].encode()).expect("Failed getting a hash"),
+			get_preimage_hash(&call).expect("Failed getting a hash")
+		);
+	}
+
 	#[test]
 	fn test_hash_length() {
 		assert_eq!(32, get_call_hash((0, 0), &[0]).expect("Failed getting a hash").len());
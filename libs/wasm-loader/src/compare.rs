@@ -0,0 +1,178 @@
+use serde::Serialize;
+use std::fmt::Display;
+use wasmparser::{Parser, Payload};
+
+use crate::{
+	error::*,
+	sections::{raw_sections, RawSection},
+	wasm_sizes,
+};
+
+/// The first function whose body differs between 2 wasms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionDiff {
+	/// Index of the function, imported functions included
+	pub index: u32,
+
+	/// Name of the function in the first wasm, if it has a name section
+	pub name: Option<String>,
+}
+
+/// Outcome of the comparison of 2 wasms, section by section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "result")]
+pub enum WasmComparison {
+	/// Both wasms are byte for byte identical
+	Identical,
+
+	/// The wasms only differ by custom sections such as `name` or `producers`
+	/// that do not change how the runtime behaves
+	IdenticalIgnoringCustom { sections: Vec<String> },
+
+	/// The wasms are different
+	Different { sections: Vec<String>, first_function: Option<FunctionDiff> },
+}
+
+impl WasmComparison {
+	/// Returns true if the wasms behave the same
+	pub fn is_equivalent(&self) -> bool {
+		!matches!(self, Self::Different { .. })
+	}
+}
+
+impl Display for WasmComparison {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Identical => writeln!(f, "✅ The runtimes are identical"),
+			Self::IdenticalIgnoringCustom { sections } => writeln!(
+				f,
+				"✅ The runtimes are identical once the following sections are stripped: {}",
+				sections.join(", ")
+			),
+			Self::Different { sections, first_function } => {
+				writeln!(f, "❌ The runtimes are different, in the following sections: {}", sections.join(", "))?;
+				match first_function {
+					Some(FunctionDiff { index, name: Some(name) }) => {
+						writeln!(f, "   The first differing function is #{index}: {name}")
+					}
+					Some(FunctionDiff { index, name: None }) => {
+						writeln!(f, "   The first differing function is #{index}")
+					}
+					None => Ok(()),
+				}
+			}
+		}
+	}
+}
+
+/// Returns the bodies of the functions defined in the wasm
+fn function_bodies(wasm: &[u8]) -> Result<Vec<&[u8]>> {
+	let mut bodies = Vec::new();
+	for payload in Parser::new(0).parse_all(wasm) {
+		if let Payload::CodeSectionEntry(body) = payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))? {
+			bodies.push(&wasm[body.range()]);
+		}
+	}
+	Ok(bodies)
+}
+
+/// Find the first function whose body differs
+fn first_function_diff(a: &[u8], b: &[u8]) -> Result<Option<FunctionDiff>> {
+	let bodies_a = function_bodies(a)?;
+	let bodies_b = function_bodies(b)?;
+
+	let position = bodies_a.iter().zip(bodies_b.iter()).position(|(a, b)| a != b);
+	let position = match position {
+		Some(position) => position,
+		None if bodies_a.len() != bodies_b.len() => bodies_a.len().min(bodies_b.len()),
+		None => return Ok(None),
+	};
+
+	let sizes = wasm_sizes(a)?;
+	let imported = sizes.functions.first().map(|f| f.index).unwrap_or_default();
+	let index = imported + position as u32;
+	let name = sizes.functions.get(position).and_then(|f| f.name.clone());
+	Ok(Some(FunctionDiff { index, name }))
+}
+
+/// Returns the bytes of the sections called `name`, in order
+fn section_bytes<'a>(wasm: &'a [u8], sections: &[RawSection], name: &str) -> Vec<&'a [u8]> {
+	sections.iter().filter(|s| s.name == name).map(|s| &wasm[s.range.clone()]).collect()
+}
+
+/// Compare 2 wasms section by section.
+///
+/// The bytes must be the uncompressed wasms.
+pub fn compare(a: &[u8], b: &[u8]) -> Result<WasmComparison> {
+	if a == b {
+		return Ok(WasmComparison::Identical);
+	}
+
+	let sections_a = raw_sections(a)?;
+	let sections_b = raw_sections(b)?;
+
+	// Names of the sections, in order of appearance
+	let mut names: Vec<&str> = Vec::new();
+	for section in sections_a.iter().chain(sections_b.iter()) {
+		if !names.contains(&section.name.as_str()) {
+			names.push(&section.name);
+		}
+	}
+
+	let (semantic, custom): (Vec<&str>, Vec<&str>) = names
+		.into_iter()
+		.filter(|name| section_bytes(a, &sections_a, name) != section_bytes(b, &sections_b, name))
+		.partition(|name| RawSection::is_semantic_name(name));
+
+	let custom = custom.into_iter().map(String::from).collect();
+	if semantic.is_empty() {
+		return Ok(WasmComparison::IdenticalIgnoringCustom { sections: custom });
+	}
+
+	let sections = semantic.into_iter().map(String::from).collect();
+	Ok(WasmComparison::Different { sections, first_function: first_function_diff(a, b)? })
+}
+
+#[cfg(test)]
+mod tests_compare {
+	use super::*;
+
+	// (module (func i64.const 0 drop) (func i64.const 1 drop))
+	// followed by a custom section `producers` containing one byte
+	fn wasm(constant: u8, producers: u8) -> Vec<u8> {
+		vec![
+			0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+			0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section, () -> ()
+			0x03, 0x03, 0x02, 0x00, 0x00, // function section
+			0x0a, 0x0d, 0x02, // code section
+			0x05, 0x00, 0x42, 0x00, 0x1a, 0x0b, // i64.const 0, drop
+			0x05, 0x00, 0x42, constant, 0x1a, 0x0b, // i64.const <constant>, drop
+			0x00, 0x0b, 0x09, b'p', b'r', b'o', b'd', b'u', b'c', b'e', b'r', b's', producers, // custom section
+		]
+	}
+
+	#[test]
+	fn it_finds_identical_wasms() {
+		assert_eq!(WasmComparison::Identical, compare(&wasm(1, 0), &wasm(1, 0)).expect("Failed comparing"));
+	}
+
+	#[test]
+	fn it_ignores_custom_sections() {
+		let comparison = compare(&wasm(1, 0), &wasm(1, 1)).expect("Failed comparing");
+		assert_eq!(WasmComparison::IdenticalIgnoringCustom { sections: vec!["custom:producers".into()] }, comparison);
+		assert!(comparison.is_equivalent());
+	}
+
+	#[test]
+	fn it_finds_the_first_differing_function() {
+		let comparison = compare(&wasm(1, 0), &wasm(2, 1)).expect("Failed comparing");
+		assert_eq!(
+			WasmComparison::Different {
+				sections: vec!["code".into()],
+				first_function: Some(FunctionDiff { index: 1, name: None })
+			},
+			comparison
+		);
+		assert!(!comparison.is_equivalent());
+	}
+}
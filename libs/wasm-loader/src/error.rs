@@ -31,6 +31,12 @@ pub enum WasmLoaderError {
 
 	#[error("WebSocket Client error, url: `{0}`")]
 	WsClient(String),
+
+	#[error("Nothing valid stored under `{0}`")]
+	StorageNotFound(String),
+
+	#[error("Failed parsing the wasm: {0}")]
+	WasmParsing(String),
 }
 
 impl From<sp_maybe_compressed_blob::Error> for WasmLoaderError {
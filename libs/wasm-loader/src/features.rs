@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::{fmt::Display, str::FromStr};
+use wasmparser::{ExternalKind, Parser, Payload};
+
+use crate::{error::*, host_functions};
+
+/// Optional cargo features of a runtime that should not be enabled in production
+/// but leave visible traces in the wasm. The `GenesisBuilder` API is not listed
+/// here as production runtimes also export it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeFeature {
+	RuntimeBenchmarks,
+	TryRuntime,
+}
+
+impl RuntimeFeature {
+	pub const ALL: [RuntimeFeature; 2] = [RuntimeFeature::RuntimeBenchmarks, RuntimeFeature::TryRuntime];
+
+	/// Prefixes of the runtime API functions exported only when the feature is enabled
+	fn export_prefixes(&self) -> &'static [&'static str] {
+		match self {
+			Self::RuntimeBenchmarks => &["Benchmark_"],
+			Self::TryRuntime => &["TryRuntime_"],
+		}
+	}
+
+	/// Prefixes of the host functions imported only when the feature is enabled
+	fn import_prefixes(&self) -> &'static [&'static str] {
+		match self {
+			Self::RuntimeBenchmarks => &["ext_benchmarking_"],
+			Self::TryRuntime => &[],
+		}
+	}
+}
+
+impl FromStr for RuntimeFeature {
+	type Err = WasmLoaderError;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_lowercase().replace('_', "-").as_str() {
+			"runtime-benchmarks" | "benchmarks" => Ok(Self::RuntimeBenchmarks),
+			"try-runtime" => Ok(Self::TryRuntime),
+			_ => Err(WasmLoaderError::NotSupported(format!("Unknown feature: {s}"))),
+		}
+	}
+}
+
+impl Display for RuntimeFeature {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::RuntimeBenchmarks => "runtime-benchmarks",
+			Self::TryRuntime => "try-runtime",
+		})
+	}
+}
+
+/// A feature that appears to be compiled in, along with the exports
+/// and imports that gave it away.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DetectedFeature {
+	pub feature: RuntimeFeature,
+	pub evidence: Vec<String>,
+}
+
+/// List the names of the functions exported by the wasm.
+///
+/// The bytes must be the uncompressed wasm.
+pub fn exports(wasm: &[u8]) -> Result<Vec<String>> {
+	let mut exports = Vec::new();
+
+	for payload in Parser::new(0).parse_all(wasm) {
+		match payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))? {
+			Payload::ExportSection(reader) => {
+				for export in reader {
+					let export = export.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+					if export.kind == ExternalKind::Func {
+						exports.push(export.name.to_string());
+					}
+				}
+			}
+			Payload::CodeSectionStart { .. } => break,
+			_ => {}
+		}
+	}
+
+	exports.sort();
+	Ok(exports)
+}
+
+/// Look at the exports and imports of the wasm to find out which
+/// [RuntimeFeature]s appear to be compiled in.
+pub fn detect_features(wasm: &[u8]) -> Result<Vec<DetectedFeature>> {
+	let exports = exports(wasm)?;
+	let imports: Vec<String> = host_functions(wasm)?.into_iter().map(|f| f.name).collect();
+
+	Ok(RuntimeFeature::ALL
+		.iter()
+		.filter_map(|feature| {
+			let exported = exports.iter().filter(|e| feature.export_prefixes().iter().any(|p| e.starts_with(p)));
+			let imported = imports.iter().filter(|i| feature.import_prefixes().iter().any(|p| i.starts_with(p)));
+			let evidence: Vec<String> = exported.chain(imported).cloned().collect();
+
+			(!evidence.is_empty()).then_some(DetectedFeature { feature: *feature, evidence })
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod tests_features {
+	use super::*;
+
+	// (module
+	//   (import "env" "ext_benchmarking_current_time_version_1" (func (result i64)))
+	//   (func (export "Core_version") (param i32 i32) (result i64) i64.const 0)
+	//   (func (export "Benchmark_dispatch_benchmark") (param i32 i32) (result i64) i64.const 0))
+	const WASM: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x0b, 0x02, // type section, 2 types
+		0x60, 0x00, 0x01, 0x7e, // () -> i64
+		0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7e, // (i32, i32) -> i64
+		0x02, 0x2f, 0x01, // import section, 1 import
+		0x03, b'e', b'n', b'v', 0x27, b'e', b'x', b't', b'_', b'b', b'e', b'n', b'c', b'h', b'm', b'a', b'r', b'k',
+		b'i', b'n', b'g', b'_', b'c', b'u', b'r', b'r', b'e', b'n', b't', b'_', b't', b'i', b'm', b'e', b'_', b'v',
+		b'e', b'r', b's', b'i', b'o', b'n', b'_', b'1', 0x00, 0x00, // func, type 0
+		0x03, 0x03, 0x02, 0x01, 0x01, // function section, 2 functions of type 1
+		0x07, 0x2f, 0x02, // export section, 2 exports
+		0x0c, b'C', b'o', b'r', b'e', b'_', b'v', b'e', b'r', b's', b'i', b'o', b'n', 0x00, 0x01, // func 1
+		0x1c, b'B', b'e', b'n', b'c', b'h', b'm', b'a', b'r', b'k', b'_', b'd', b'i', b's', b'p', b'a', b't', b'c',
+		b'h', b'_', b'b', b'e', b'n', b'c', b'h', b'm', b'a', b'r', b'k', 0x00, 0x02, // func 2
+		0x0a, 0x0b, 0x02, // code section, 2 bodies
+		0x04, 0x00, 0x42, 0x00, 0x0b, // i64.const 0
+		0x04, 0x00, 0x42, 0x00, 0x0b, // i64.const 0
+	];
+
+	#[test]
+	fn it_lists_exports() {
+		let exports = exports(WASM).expect("Failed parsing wasm");
+		assert_eq!(vec!["Benchmark_dispatch_benchmark", "Core_version"], exports);
+	}
+
+	#[test]
+	fn it_detects_features() {
+		let features = detect_features(WASM).expect("Failed parsing wasm");
+		assert_eq!(1, features.len());
+		assert_eq!(RuntimeFeature::RuntimeBenchmarks, features[0].feature);
+		assert_eq!(2, features[0].evidence.len());
+	}
+
+	#[test]
+	fn it_parses_features() {
+		assert_eq!(RuntimeFeature::TryRuntime, RuntimeFeature::from_str("try_runtime").expect("Failed parsing"));
+		assert!(RuntimeFeature::from_str("std").is_err());
+	}
+}
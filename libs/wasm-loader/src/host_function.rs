@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use wasmparser::{Parser, Payload, TypeRef, ValType};
+
+use crate::error::*;
+
+/// The name of the module the host functions are imported from
+pub const HOST_MODULE: &str = "env";
+
+/// A function provided by the host (ie. the node) to the runtime.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HostFunction {
+	/// Name of the function, such as `ext_storage_get_version_1`
+	pub name: String,
+
+	/// Types of the parameters: `i32`, `i64`, `f32` or `f64`
+	pub params: Vec<String>,
+
+	/// Type of the returned value, if any
+	pub result: Option<String>,
+}
+
+impl HostFunction {
+	pub fn new(name: &str, params: Vec<String>, result: Option<String>) -> Self {
+		Self { name: name.to_string(), params, result }
+	}
+
+	/// Returns true if both functions have the same parameters and return type
+	pub fn same_signature(&self, other: &Self) -> bool {
+		self.params == other.params && self.result == other.result
+	}
+
+	/// Returns the signature only, without the name, such as `(i32, i64) -> i64`
+	pub fn signature(&self) -> String {
+		let result = self.result.as_ref().map(|r| format!(" -> {r}")).unwrap_or_default();
+		format!("({}){result}", self.params.join(", "))
+	}
+}
+
+impl Display for HostFunction {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}", self.name, self.signature())
+	}
+}
+
+fn val_type_name(ty: &ValType) -> String {
+	match ty {
+		ValType::I32 => "i32".to_string(),
+		ValType::I64 => "i64".to_string(),
+		ValType::F32 => "f32".to_string(),
+		ValType::F64 => "f64".to_string(),
+		ValType::V128 => "v128".to_string(),
+		ValType::Ref(r) => format!("{r:?}").to_lowercase(),
+	}
+}
+
+/// Extract the list of functions the wasm imports from the host, sorted by name.
+///
+/// The bytes must be the uncompressed wasm.
+pub fn host_functions(wasm: &[u8]) -> Result<Vec<HostFunction>> {
+	let mut types = Vec::new();
+	let mut functions = Vec::new();
+
+	for payload in Parser::new(0).parse_all(wasm) {
+		match payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))? {
+			Payload::TypeSection(reader) => {
+				for ty in reader {
+					let wasmparser::Type::Func(func_type) =
+						ty.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+					types.push(func_type);
+				}
+			}
+			Payload::ImportSection(reader) => {
+				for import in reader {
+					let import = import.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+					if import.module != HOST_MODULE {
+						continue;
+					}
+
+					if let TypeRef::Func(index) = import.ty {
+						let func_type = types.get(index as usize).ok_or_else(|| {
+							WasmLoaderError::WasmParsing(format!("Unknown type {index} for `{}`", import.name))
+						})?;
+						let params = func_type.params().iter().map(val_type_name).collect();
+						let result = func_type.results().first().map(val_type_name);
+						functions.push(HostFunction::new(import.name, params, result));
+					}
+				}
+			}
+			Payload::CodeSectionStart { .. } => break,
+			_ => {}
+		}
+	}
+
+	functions.sort();
+	Ok(functions)
+}
+
+/// Outcome of checking the functions a runtime imports against
+/// the functions some host provides.
+#[derive(Debug, Default, Serialize)]
+pub struct HostFunctionsCheck {
+	/// Functions imported by the runtime but not provided by the host
+	pub missing: Vec<HostFunction>,
+
+	/// Functions provided under the same name but with another signature,
+	/// as (imported, provided)
+	pub changed: Vec<(HostFunction, HostFunction)>,
+
+	/// Functions imported by the runtime and provided as expected
+	pub matching: Vec<HostFunction>,
+}
+
+impl HostFunctionsCheck {
+	/// Check the `imported` host functions against the `provided` ones.
+	pub fn new(imported: &[HostFunction], provided: &[HostFunction]) -> Self {
+		let mut check = Self::default();
+
+		for function in imported {
+			match provided.iter().find(|f| f.name == function.name) {
+				None => check.missing.push(function.clone()),
+				Some(f) if !f.same_signature(function) => check.changed.push((function.clone(), f.clone())),
+				Some(_) => check.matching.push(function.clone()),
+			}
+		}
+
+		check
+	}
+
+	/// The check passes when all imported functions are provided with the right signature.
+	pub fn is_ok(&self) -> bool {
+		self.missing.is_empty() && self.changed.is_empty()
+	}
+
+	/// Names of the functions that are missing or have a different signature
+	pub fn failures(&self) -> Vec<&str> {
+		self.missing.iter().chain(self.changed.iter().map(|(f, _)| f)).map(|f| f.name.as_str()).collect()
+	}
+}
+
+/// The alternate format (`{:#}`) only shows the failures.
+impl Display for HostFunctionsCheck {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if !f.alternate() {
+			for function in &self.matching {
+				writeln!(f, "  ✅ {function}")?;
+			}
+		}
+		for (imported, provided) in &self.changed {
+			writeln!(f, "  ❌ {imported} but the host provides {}", provided.signature())?;
+		}
+		for function in &self.missing {
+			writeln!(f, "  ❌ {function} is missing")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests_host_function {
+	use super::*;
+
+	// (module
+	//   (import "env" "ext_logging_max_level_version_1" (func (result i32)))
+	//   (import "env" "ext_storage_get_version_1" (func (param i64) (result i64)))
+	//   (import "other" "foo" (func)))
+	const WASM: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x0d, 0x03, // type section, 3 types
+		0x60, 0x00, 0x01, 0x7f, // () -> i32
+		0x60, 0x01, 0x7e, 0x01, 0x7e, // (i64) -> i64
+		0x60, 0x00, 0x00, // () -> ()
+		0x02, 0x53, 0x03, // import section, 3 imports
+		0x03, b'e', b'n', b'v', 0x1f, b'e', b'x', b't', b'_', b'l', b'o', b'g', b'g', b'i', b'n', b'g', b'_', b'm',
+		b'a', b'x', b'_', b'l', b'e', b'v', b'e', b'l', b'_', b'v', b'e', b'r', b's', b'i', b'o', b'n', b'_', b'1',
+		0x00, 0x00, // func, type 0
+		0x03, b'e', b'n', b'v', 0x19, b'e', b'x', b't', b'_', b's', b't', b'o', b'r', b'a', b'g', b'e', b'_', b'g',
+		b'e', b't', b'_', b'v', b'e', b'r', b's', b'i', b'o', b'n', b'_', b'1', 0x00, 0x01, // func, type 1
+		0x05, b'o', b't', b'h', b'e', b'r', 0x03, b'f', b'o', b'o', 0x00, 0x02, // func, type 2
+	];
+
+	#[test]
+	fn it_lists_host_functions() {
+		let functions = host_functions(WASM).expect("Failed parsing wasm");
+		assert_eq!(2, functions.len());
+		assert_eq!("ext_logging_max_level_version_1() -> i32", functions[0].to_string());
+		assert_eq!("ext_storage_get_version_1(i64) -> i64", functions[1].to_string());
+	}
+
+	#[test]
+	fn it_checks_host_functions() {
+		let imported = host_functions(WASM).expect("Failed parsing wasm");
+		let provided = vec![HostFunction::new("ext_storage_get_version_1", vec!["i32".into()], Some("i64".into()))];
+
+		let check = HostFunctionsCheck::new(&imported, &provided);
+		assert_eq!(1, check.missing.len());
+		assert_eq!(1, check.changed.len());
+		assert_eq!(vec!["ext_logging_max_level_version_1", "ext_storage_get_version_1"], check.failures());
+
+		let check = HostFunctionsCheck::new(&imported, &imported);
+		assert_eq!(2, check.matching.len());
+		assert!(check.is_ok());
+	}
+}
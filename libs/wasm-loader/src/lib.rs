@@ -1,16 +1,30 @@
 #![allow(clippy::derive_partial_eq_without_eq)]
 
+mod compare;
 mod compression;
 pub mod error;
+mod features;
+mod host_function;
+mod memory;
 mod node_endpoint;
 mod onchain_block;
+mod proposals;
+mod sections;
 mod source;
+mod strip;
 
+pub use compare::{compare, FunctionDiff, WasmComparison};
 pub use compression::Compression;
 pub use error::*;
+pub use features::{detect_features, exports, DetectedFeature, RuntimeFeature};
+pub use host_function::{host_functions, HostFunction, HostFunctionsCheck, HOST_MODULE};
+pub use memory::{memory_layout, MemoryLayout, WASM_PAGE_SIZE};
 pub use node_endpoint::NodeEndpoint;
 pub use onchain_block::{BlockRef, OnchainBlock};
+pub use proposals::{wasm_proposals, ProposalUse, WasmProposal, WasmTarget};
+pub use sections::{wasm_sizes, FunctionSize, SectionSize, WasmSizes, SEMANTIC_CUSTOM_SECTIONS};
 pub use source::Source;
+pub use strip::{custom_section, custom_sections, rewrite_custom_sections};
 
 use log::*;
 use serde::Deserialize;
@@ -20,7 +34,7 @@ use std::{fs::File, path::Path};
 use subrpcer::state;
 use tungstenite::Message;
 
-const CODE: &str = "0x3a636f6465"; // :code in hex
+pub const CODE: &str = "0x3a636f6465"; // :code in hex
 pub const CODE_BLOB_BOMB_LIMIT: usize = 50 * 1024 * 1024;
 pub type WasmBytes = Vec<u8>;
 
@@ -39,9 +53,14 @@ pub struct WasmLoader {
 impl WasmLoader {
 	/// Fetch the wasm blob from a node
 	fn fetch_wasm_from_rpc(reference: &OnchainBlock) -> Result<WasmBytes> {
+		WasmLoader::fetch_storage(reference, CODE)?.ok_or_else(|| WasmLoaderError::StorageNotFound(CODE.to_string()))
+	}
+
+	/// Fetch the value stored under the hex `key` at the block of `reference`, if any
+	pub fn fetch_storage(reference: &OnchainBlock, key: &str) -> Result<Option<Vec<u8>>> {
 		#[derive(Deserialize)]
 		struct Response {
-			result: String,
+			result: Option<String>,
 		}
 
 		fn map_err<O, E1, E2>(r: std::result::Result<O, E1>, e: E2) -> std::result::Result<O, E2>
@@ -55,12 +74,12 @@ impl WasmLoader {
 		}
 
 		let block_ref = reference.block_ref.as_ref();
-		let data = state::get_storage(0, CODE, block_ref);
-		let wasm_hex = match &reference.endpoint {
+		let data = state::get_storage(0, key, block_ref);
+		let value_hex = match &reference.endpoint {
 			NodeEndpoint::Http(url) => {
 				map_err(ureq::post(url).send_json(data), WasmLoaderError::HttpClient(url.to_string()))?
 					.into_json::<Response>()
-					.expect("unexpected response from node")
+					.map_err(|_| WasmLoaderError::HttpClient(url.to_string()))?
 					.result
 			}
 			NodeEndpoint::WebSocket(url) => {
@@ -71,7 +90,7 @@ impl WasmLoader {
 					WasmLoaderError::WsClient(url.to_string()),
 				)?;
 
-				let mut wasm_hex = None;
+				let mut response = None;
 
 				// One for Ping, one for response.
 				for _ in 0..2_u8 {
@@ -79,15 +98,18 @@ impl WasmLoader {
 						continue;
 					};
 
-					wasm_hex = serde_json::from_str::<Response>(&t).map(|r| r.result).ok();
+					response = serde_json::from_str::<Response>(&t).ok();
 				}
 
-				wasm_hex.expect("unexpected response from node")
+				response.ok_or_else(|| WasmLoaderError::WsClient(url.to_string()))?.result
 			}
 		};
-		let wasm = array_bytes::hex2bytes(wasm_hex).expect("Decoding bytes");
 
-		Ok(wasm)
+		value_hex
+			.map(|value_hex| {
+				array_bytes::hex2bytes(&value_hex).map_err(|_| WasmLoaderError::StorageNotFound(key.to_string()))
+			})
+			.transpose()
 	}
 
 	/// Load some binary from a file
@@ -128,6 +150,21 @@ impl WasmLoader {
 		}
 	}
 
+	/// List the functions the wasm imports from the host
+	pub fn host_functions(&self) -> Result<Vec<HostFunction>> {
+		host_functions(self.uncompressed_bytes())
+	}
+
+	/// Find out which optional [RuntimeFeature]s appear to be compiled in
+	pub fn features(&self) -> Result<Vec<DetectedFeature>> {
+		detect_features(self.uncompressed_bytes())
+	}
+
+	/// Validate the wasm and report the [WasmProposal]s it uses
+	pub fn proposals(&self) -> Result<Vec<ProposalUse>> {
+		wasm_proposals(self.uncompressed_bytes())
+	}
+
 	pub fn load_from_bytes(bytes: CompressedMaybe, compression: Compression) -> Result<Self> {
 		Ok(Self { bytes, compression })
 	}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use wasmparser::{ExternalKind, Operator, Parser, Payload, TypeRef};
+
+use crate::error::*;
+
+/// Size of a page of the linear memory, in bytes
+pub const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Layout of the linear memory of a runtime, before the runtime grows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryLayout {
+	/// Number of pages the memory starts with, whether it is imported or defined by the wasm
+	pub initial_pages: u64,
+
+	/// Value of the exported `__heap_base` global, where the allocator of the host starts
+	pub heap_base: Option<u32>,
+}
+
+impl MemoryLayout {
+	/// Number of pages the memory needs for the allocator to use `address_space_used` bytes above the heap base
+	pub fn pages_for(&self, address_space_used: u32) -> u64 {
+		let used = self.heap_base.unwrap_or_default() as u64 + address_space_used as u64;
+		used.div_ceil(WASM_PAGE_SIZE).max(self.initial_pages)
+	}
+}
+
+/// Read the [MemoryLayout] of the wasm
+pub fn memory_layout(wasm: &[u8]) -> Result<MemoryLayout> {
+	let map_err = |e: wasmparser::BinaryReaderError| WasmLoaderError::WasmParsing(e.to_string());
+	let mut initial_pages = 0;
+	let mut imported_globals = 0;
+	let mut globals = Vec::new();
+	let mut heap_base_index = None;
+
+	for payload in Parser::new(0).parse_all(wasm) {
+		match payload.map_err(map_err)? {
+			Payload::ImportSection(reader) => {
+				for import in reader {
+					match import.map_err(map_err)?.ty {
+						TypeRef::Memory(memory) => initial_pages = memory.initial,
+						TypeRef::Global(_) => imported_globals += 1,
+						_ => {}
+					}
+				}
+			}
+			Payload::MemorySection(reader) => {
+				for memory in reader {
+					initial_pages = memory.map_err(map_err)?.initial;
+				}
+			}
+			Payload::GlobalSection(reader) => {
+				for global in reader {
+					let value = match global.map_err(map_err)?.init_expr.get_operators_reader().read() {
+						Ok(Operator::I32Const { value }) => Some(value as u32),
+						_ => None,
+					};
+					globals.push(value);
+				}
+			}
+			Payload::ExportSection(reader) => {
+				for export in reader {
+					let export = export.map_err(map_err)?;
+					if export.name == "__heap_base" && export.kind == ExternalKind::Global {
+						heap_base_index = Some(export.index as usize);
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	let heap_base = heap_base_index
+		.and_then(|index| index.checked_sub(imported_globals))
+		.and_then(|index| globals.get(index).copied().flatten());
+	Ok(MemoryLayout { initial_pages, heap_base })
+}
+
+#[cfg(test)]
+mod tests_memory {
+	use super::*;
+
+	// (module (memory (import "env" "memory") 2) (global (export "__heap_base") i32 (i32.const 70000)))
+	const WASM: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x02, 0x0f, 0x01, 0x03, b'e', b'n', b'v', 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+		0x02, // import section
+		0x06, 0x08, 0x01, 0x7f, 0x00, 0x41, 0xf0, 0xa2, 0x04, 0x0b, // global section
+		0x07, 0x0f, 0x01, 0x0b, b'_', b'_', b'h', b'e', b'a', b'p', b'_', b'b', b'a', b's', b'e', 0x03,
+		0x00, // export section
+	];
+
+	#[test]
+	fn it_reads_the_memory_layout() {
+		let layout = memory_layout(WASM).expect("Failed parsing wasm");
+		assert_eq!(MemoryLayout { initial_pages: 2, heap_base: Some(70000) }, layout);
+	}
+
+	#[test]
+	fn it_computes_pages() {
+		let layout = MemoryLayout { initial_pages: 2, heap_base: Some(70000) };
+		assert_eq!(2, layout.pages_for(0));
+		assert_eq!(3, layout.pages_for(100_000));
+	}
+}
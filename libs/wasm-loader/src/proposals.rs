@@ -0,0 +1,303 @@
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+use wasmparser::{BlockType, Operator, Parser, Payload, Type, ValType, Validator, WasmFeatures};
+
+use crate::error::*;
+
+/// The wasm proposals a runtime may use on top of the MVP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WasmProposal {
+	SignExtension,
+	SaturatingFloatToInt,
+	BulkMemory,
+	MultiValue,
+	ReferenceTypes,
+	Simd,
+	Threads,
+	TailCall,
+	Exceptions,
+	FunctionReferences,
+	MemoryControl,
+
+	/// Floats are part of the MVP but their results are not fully deterministic,
+	/// so we report them along with the proposals.
+	Floats,
+}
+
+impl Display for WasmProposal {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::SignExtension => "sign-extension",
+			Self::SaturatingFloatToInt => "saturating-float-to-int",
+			Self::BulkMemory => "bulk-memory",
+			Self::MultiValue => "multi-value",
+			Self::ReferenceTypes => "reference-types",
+			Self::Simd => "simd",
+			Self::Threads => "threads",
+			Self::TailCall => "tail-call",
+			Self::Exceptions => "exceptions",
+			Self::FunctionReferences => "function-references",
+			Self::MemoryControl => "memory-control",
+			Self::Floats => "floats",
+		})
+	}
+}
+
+/// How much a runtime uses a given [WasmProposal]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProposalUse {
+	pub proposal: WasmProposal,
+
+	/// Number of instructions, block or function types relying on the proposal
+	pub count: usize,
+}
+
+macro_rules! proposal {
+	(mvp) => {
+		None
+	};
+	(sign_extension) => {
+		Some(WasmProposal::SignExtension)
+	};
+	(saturating_float_to_int) => {
+		Some(WasmProposal::SaturatingFloatToInt)
+	};
+	(bulk_memory) => {
+		Some(WasmProposal::BulkMemory)
+	};
+	(reference_types) => {
+		Some(WasmProposal::ReferenceTypes)
+	};
+	(simd) => {
+		Some(WasmProposal::Simd)
+	};
+	(relaxed_simd) => {
+		Some(WasmProposal::Simd)
+	};
+	(threads) => {
+		Some(WasmProposal::Threads)
+	};
+	(tail_call) => {
+		Some(WasmProposal::TailCall)
+	};
+	(exceptions) => {
+		Some(WasmProposal::Exceptions)
+	};
+	(function_references) => {
+		Some(WasmProposal::FunctionReferences)
+	};
+	(memory_control) => {
+		Some(WasmProposal::MemoryControl)
+	};
+}
+
+macro_rules! define_proposal_of {
+	($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+		/// Returns the proposal an operator comes from, `None` for the MVP, and the name of the operator
+		fn proposal_of(op: &Operator) -> (Option<WasmProposal>, &'static str) {
+			match op {
+				$( Operator::$op { .. } => (proposal!($proposal), stringify!($visit)), )*
+			}
+		}
+	};
+}
+
+wasmparser::for_each_operator!(define_proposal_of);
+
+/// The features of all the proposals, so that the runtimes using them validate and get reported
+const ALL_FEATURES: WasmFeatures = WasmFeatures {
+	mutable_global: true,
+	saturating_float_to_int: true,
+	sign_extension: true,
+	reference_types: true,
+	multi_value: true,
+	bulk_memory: true,
+	simd: true,
+	relaxed_simd: true,
+	threads: true,
+	tail_call: true,
+	floats: true,
+	multi_memory: true,
+	exceptions: true,
+	memory64: true,
+	extended_const: true,
+	component_model: false,
+	function_references: true,
+	memory_control: true,
+};
+
+/// Validate the wasm and report the [WasmProposal]s it uses, with the number of
+/// instructions or types relying on each of them.
+///
+/// The bytes must be the uncompressed wasm.
+pub fn wasm_proposals(wasm: &[u8]) -> Result<Vec<ProposalUse>> {
+	Validator::new_with_features(ALL_FEATURES)
+		.validate_all(wasm)
+		.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+
+	let mut uses: BTreeMap<WasmProposal, usize> = BTreeMap::new();
+	let mut add = |proposal| *uses.entry(proposal).or_default() += 1;
+
+	for payload in Parser::new(0).parse_all(wasm) {
+		match payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))? {
+			Payload::TypeSection(reader) => {
+				for ty in reader {
+					let Type::Func(func_type) = ty.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+					if func_type.results().len() > 1 {
+						add(WasmProposal::MultiValue);
+					}
+					if func_type.params().iter().chain(func_type.results()).any(|t| matches!(t, ValType::Ref(_))) {
+						add(WasmProposal::ReferenceTypes);
+					}
+				}
+			}
+			Payload::CodeSectionEntry(body) => {
+				let reader = body.get_operators_reader().map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+				for op in reader {
+					let op = op.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+					if let Operator::Block { blockty: BlockType::FuncType(_) }
+					| Operator::Loop { blockty: BlockType::FuncType(_) }
+					| Operator::If { blockty: BlockType::FuncType(_) } = op
+					{
+						add(WasmProposal::MultiValue);
+					}
+
+					match proposal_of(&op) {
+						(Some(proposal), _) => add(proposal),
+						(None, name) if name.contains("f32") || name.contains("f64") => add(WasmProposal::Floats),
+						_ => {}
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	Ok(uses.into_iter().map(|(proposal, count)| ProposalUse { proposal, count }).collect())
+}
+
+/// A set of [WasmProposal]s supported by some executor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WasmTarget {
+	/// The MVP, floats included
+	Mvp,
+
+	/// The MVP without floats
+	Deterministic,
+
+	/// The executor of the Polkadot v1.x nodes: wasmtime with NaN canonicalization and
+	/// without multi-value, bulk-memory, reference-types or simd
+	PolkadotV1,
+}
+
+impl WasmTarget {
+	/// Proposals this target supports
+	pub fn supported(&self) -> &'static [WasmProposal] {
+		match self {
+			Self::Mvp => &[WasmProposal::Floats],
+			Self::Deterministic => &[],
+			Self::PolkadotV1 => {
+				&[WasmProposal::SignExtension, WasmProposal::SaturatingFloatToInt, WasmProposal::Floats]
+			}
+		}
+	}
+
+	/// Returns the proposals among `uses` that this target does not support
+	pub fn unsupported(&self, uses: &[ProposalUse]) -> Vec<WasmProposal> {
+		uses.iter().map(|u| u.proposal).filter(|p| !self.supported().contains(p)).collect()
+	}
+}
+
+impl FromStr for WasmTarget {
+	type Err = WasmLoaderError;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"mvp" => Ok(Self::Mvp),
+			"deterministic" => Ok(Self::Deterministic),
+			"polkadot" | "polkadot-v1" | "polkadot-v1.x" => Ok(Self::PolkadotV1),
+			_ => Err(WasmLoaderError::NotSupported(format!(
+				"Unknown wasm target `{s}`, use one of: mvp, deterministic, polkadot-v1.x"
+			))),
+		}
+	}
+}
+
+impl Display for WasmTarget {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Mvp => "mvp",
+			Self::Deterministic => "deterministic",
+			Self::PolkadotV1 => "polkadot-v1.x",
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests_proposals {
+	use super::*;
+
+	// (module
+	//   (func (param i32) (result i32) local.get 0 i32.extend8_s)
+	//   (func (param f32) (result f32) local.get 0 f32.neg))
+	const WASM: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x0b, 0x02, // type section, 2 types
+		0x60, 0x01, 0x7f, 0x01, 0x7f, // (i32) -> i32
+		0x60, 0x01, 0x7d, 0x01, 0x7d, // (f32) -> f32
+		0x03, 0x03, 0x02, 0x00, 0x01, // function section
+		0x0a, 0x0d, 0x02, // code section, 2 bodies
+		0x05, 0x00, 0x20, 0x00, 0xc0, 0x0b, // local.get 0, i32.extend8_s
+		0x05, 0x00, 0x20, 0x00, 0x8c, 0x0b, // local.get 0, f32.neg
+	];
+
+	#[test]
+	fn it_reports_proposals() {
+		let uses = wasm_proposals(WASM).expect("Failed parsing wasm");
+		assert_eq!(
+			vec![
+				ProposalUse { proposal: WasmProposal::SignExtension, count: 1 },
+				ProposalUse { proposal: WasmProposal::Floats, count: 1 }
+			],
+			uses
+		);
+	}
+
+	// (module
+	//   (func atomic.fence return_call 0))
+	const WASM_POST_MVP: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section, () -> ()
+		0x03, 0x02, 0x01, 0x00, // function section
+		0x0a, 0x09, 0x01, // code section, 1 body
+		0x07, 0x00, 0xfe, 0x03, 0x00, 0x12, 0x00, 0x0b, // atomic.fence, return_call 0
+	];
+
+	#[test]
+	fn it_reports_threads_and_tail_calls() {
+		let uses = wasm_proposals(WASM_POST_MVP).expect("Failed parsing wasm");
+		assert_eq!(
+			vec![
+				ProposalUse { proposal: WasmProposal::Threads, count: 1 },
+				ProposalUse { proposal: WasmProposal::TailCall, count: 1 }
+			],
+			uses
+		);
+		assert_eq!(vec![WasmProposal::Threads, WasmProposal::TailCall], WasmTarget::PolkadotV1.unsupported(&uses));
+	}
+
+	#[test]
+	fn it_checks_targets() {
+		let uses = wasm_proposals(WASM).expect("Failed parsing wasm");
+		assert!(WasmTarget::PolkadotV1.unsupported(&uses).is_empty());
+		assert_eq!(vec![WasmProposal::SignExtension], WasmTarget::Mvp.unsupported(&uses));
+		assert_eq!(2, WasmTarget::Deterministic.unsupported(&uses).len());
+	}
+
+	#[test]
+	fn it_rejects_invalid_wasm() {
+		assert!(wasm_proposals(&WASM[..WASM.len() - 1]).is_err());
+	}
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+use std::ops::Range;
+use wasmparser::{Name, NameSectionReader, Parser, Payload, TypeRef};
+
+use crate::error::*;
+
+/// Prefix of the names we give to custom sections
+pub(crate) const CUSTOM_PREFIX: &str = "custom:";
+
+/// Custom sections that the node reads and that change how the runtime is handled,
+/// unlike `name` or `producers` for instance.
+pub const SEMANTIC_CUSTOM_SECTIONS: [&str; 2] = ["runtime_version", "runtime_apis"];
+
+/// Size of one section of the wasm
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionSize {
+	/// Name of the section such as `code` or `data`. Custom sections
+	/// are named after their own name, such as `custom:name`.
+	pub name: String,
+
+	/// Size of the section content, in bytes
+	pub size: usize,
+}
+
+/// Size of the body of one function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionSize {
+	/// Index of the function, imported functions included
+	pub index: u32,
+
+	/// Name of the function, from the name section if present
+	pub name: Option<String>,
+
+	/// Size of the function body, in bytes
+	pub size: usize,
+}
+
+/// Breakdown of the size of a wasm by section and by function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WasmSizes {
+	/// Total size of the wasm, in bytes
+	pub total: usize,
+
+	/// Sizes of all the sections, in the order they appear
+	pub sections: Vec<SectionSize>,
+
+	/// Sizes of all the functions defined in the wasm
+	pub functions: Vec<FunctionSize>,
+}
+
+/// A section of the wasm, with the range of its bytes, header included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RawSection {
+	/// Same as [SectionSize::name]
+	pub name: String,
+
+	/// Range of the whole section, id and size included
+	pub range: Range<usize>,
+}
+
+impl RawSection {
+	/// Returns false for the custom sections that can be removed without changing the runtime
+	pub fn is_semantic_name(name: &str) -> bool {
+		name.strip_prefix(CUSTOM_PREFIX).is_none_or(|custom| SEMANTIC_CUSTOM_SECTIONS.contains(&custom))
+	}
+}
+
+/// List the sections of the wasm along with their bytes ranges
+pub(crate) fn raw_sections(wasm: &[u8]) -> Result<Vec<RawSection>> {
+	let mut sections = Vec::new();
+	let mut start = 0;
+
+	for payload in Parser::new(0).parse_all(wasm) {
+		let payload = payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+		let (name, end) = match &payload {
+			Payload::Version { range, .. } => {
+				start = range.end;
+				continue;
+			}
+			Payload::CustomSection(reader) => (format!("{CUSTOM_PREFIX}{}", reader.name()), reader.range().end),
+			payload => match payload.as_section() {
+				Some((id, range)) => (section_name(id).to_string(), range.end),
+				None => continue,
+			},
+		};
+		sections.push(RawSection { name, range: start..end });
+		start = end;
+	}
+
+	Ok(sections)
+}
+
+fn section_name(id: u8) -> &'static str {
+	match id {
+		1 => "type",
+		2 => "import",
+		3 => "function",
+		4 => "table",
+		5 => "memory",
+		6 => "global",
+		7 => "export",
+		8 => "start",
+		9 => "element",
+		10 => "code",
+		11 => "data",
+		12 => "datacount",
+		13 => "tag",
+		_ => "unknown",
+	}
+}
+
+/// Compute the size of each section and each function of the wasm.
+///
+/// The bytes must be the uncompressed wasm.
+pub fn wasm_sizes(wasm: &[u8]) -> Result<WasmSizes> {
+	let mut sections = Vec::new();
+	let mut functions = Vec::new();
+	let mut imported_functions = 0;
+	let mut names = Vec::new();
+
+	for payload in Parser::new(0).parse_all(wasm) {
+		let payload = payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+
+		match &payload {
+			Payload::ImportSection(reader) => {
+				for import in reader.clone() {
+					let import = import.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))?;
+					if let TypeRef::Func(_) = import.ty {
+						imported_functions += 1;
+					}
+				}
+			}
+			Payload::CodeSectionEntry(body) => {
+				let index = imported_functions + functions.len() as u32;
+				functions.push(FunctionSize { index, name: None, size: body.range().len() });
+			}
+			Payload::CustomSection(reader) if reader.name() == "name" => {
+				for name in NameSectionReader::new(reader.data(), reader.data_offset()) {
+					if let Ok(Name::Function(map)) = name {
+						names.extend(map.into_iter().flatten().map(|n| (n.index, n.name.to_string())));
+					}
+				}
+			}
+			_ => {}
+		}
+
+		match &payload {
+			Payload::CustomSection(reader) => sections
+				.push(SectionSize { name: format!("{CUSTOM_PREFIX}{}", reader.name()), size: reader.range().len() }),
+			payload => {
+				if let Some((id, range)) = payload.as_section() {
+					sections.push(SectionSize { name: section_name(id).to_string(), size: range.len() });
+				}
+			}
+		}
+	}
+
+	for (index, name) in names {
+		if let Some(function) = index.checked_sub(imported_functions).and_then(|i| functions.get_mut(i as usize)) {
+			function.name = Some(name);
+		}
+	}
+
+	Ok(WasmSizes { total: wasm.len(), sections, functions })
+}
+
+#[cfg(test)]
+mod tests_sections {
+	use super::*;
+
+	// (module
+	//   (import "env" "ext_foo_version_1" (func))
+	//   (func $bar i64.const 0 drop))
+	// with a name section naming function 1 `bar`
+	const WASM: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section, () -> ()
+		0x02, 0x19, 0x01, 0x03, b'e', b'n', b'v', 0x11, b'e', b'x', b't', b'_', b'f', b'o', b'o', b'_', b'v', b'e',
+		b'r', b's', b'i', b'o', b'n', b'_', b'1', 0x00, 0x00, // import section
+		0x03, 0x02, 0x01, 0x00, // function section
+		0x0a, 0x07, 0x01, 0x05, 0x00, 0x42, 0x00, 0x1a, 0x0b, // code section
+		0x00, 0x0d, 0x04, b'n', b'a', b'm', b'e', // name section
+		0x01, 0x06, 0x01, 0x01, 0x03, b'b', b'a', b'r', // function names
+	];
+
+	#[test]
+	fn it_computes_sizes() {
+		let sizes = wasm_sizes(WASM).expect("Failed parsing wasm");
+		assert_eq!(WASM.len(), sizes.total);
+
+		let names: Vec<&str> = sizes.sections.iter().map(|s| s.name.as_str()).collect();
+		assert_eq!(vec!["type", "import", "function", "code", "custom:name"], names);
+
+		assert_eq!(vec![FunctionSize { index: 1, name: Some("bar".into()), size: 5 }], sizes.functions);
+	}
+
+	#[test]
+	fn it_finds_section_ranges() {
+		let sections = raw_sections(WASM).expect("Failed parsing wasm");
+		assert_eq!(5, sections.len());
+		assert_eq!(8, sections[0].range.start);
+		assert_eq!(WASM.len(), sections[4].range.end);
+		assert_eq!("custom:name", sections[4].name);
+		assert!(sections.windows(2).all(|w| w[0].range.end == w[1].range.start));
+	}
+}
@@ -0,0 +1,136 @@
+use wasmparser::{Parser, Payload};
+
+use crate::{
+	error::*,
+	sections::{raw_sections, CUSTOM_PREFIX},
+};
+
+/// Size of the wasm magic and version
+const HEADER_SIZE: usize = 8;
+
+/// Append `value` encoded as unsigned LEB128
+fn write_leb128(mut value: usize, out: &mut Vec<u8>) {
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+		if value == 0 {
+			out.push(byte);
+			return;
+		}
+		out.push(byte | 0x80);
+	}
+}
+
+/// Encode a whole custom section, id and size included
+fn custom_section_bytes(name: &str, data: &[u8]) -> Vec<u8> {
+	let mut content = Vec::with_capacity(name.len() + data.len() + 5);
+	write_leb128(name.len(), &mut content);
+	content.extend_from_slice(name.as_bytes());
+	content.extend_from_slice(data);
+
+	let mut section = vec![0x00];
+	write_leb128(content.len(), &mut section);
+	section.extend(content);
+	section
+}
+
+/// List the names of the custom sections of the wasm, in the order they appear
+pub fn custom_sections(wasm: &[u8]) -> Result<Vec<String>> {
+	let mut names = Vec::new();
+	for payload in Parser::new(0).parse_all(wasm) {
+		if let Payload::CustomSection(reader) = payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))? {
+			names.push(reader.name().to_string());
+		}
+	}
+	Ok(names)
+}
+
+/// Returns the content of the first custom section called `name`, if any
+pub fn custom_section<'a>(wasm: &'a [u8], name: &str) -> Result<Option<&'a [u8]>> {
+	for payload in Parser::new(0).parse_all(wasm) {
+		match payload.map_err(|e| WasmLoaderError::WasmParsing(e.to_string()))? {
+			Payload::CustomSection(reader) if reader.name() == name => return Ok(Some(reader.data())),
+			_ => {}
+		}
+	}
+	Ok(None)
+}
+
+/// Rebuild the wasm without the custom sections listed in `remove`, and with the
+/// content of the custom sections listed in `replace` swapped in place. Replaced
+/// sections that do not exist yet are appended. Other sections are copied as is.
+///
+/// The bytes must be the uncompressed wasm.
+pub fn rewrite_custom_sections(wasm: &[u8], remove: &[String], replace: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+	let mut output = Vec::with_capacity(wasm.len());
+	output.extend_from_slice(&wasm[..HEADER_SIZE.min(wasm.len())]);
+
+	let mut replaced = Vec::new();
+	for section in raw_sections(wasm)? {
+		let custom = section.name.strip_prefix(CUSTOM_PREFIX);
+
+		match custom.and_then(|name| replace.iter().find(|(n, _)| n == name)) {
+			Some((name, data)) => {
+				if !replaced.contains(&name) {
+					output.extend(custom_section_bytes(name, data));
+					replaced.push(name);
+				}
+			}
+			None if custom.is_some_and(|name| remove.iter().any(|n| n == name)) => {}
+			None => output.extend_from_slice(&wasm[section.range]),
+		}
+	}
+
+	for (name, data) in replace.iter().filter(|(name, _)| !replaced.contains(&name)) {
+		output.extend(custom_section_bytes(name, data));
+	}
+
+	Ok(output)
+}
+
+#[cfg(test)]
+mod tests_strip {
+	use super::*;
+
+	// (module (func i64.const 0 drop))
+	// followed by the custom sections `runtime_version` and `producers`
+	const WASM: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section, () -> ()
+		0x03, 0x02, 0x01, 0x00, // function section
+		0x0a, 0x07, 0x01, 0x05, 0x00, 0x42, 0x00, 0x1a, 0x0b, // code section
+		0x00, 0x11, 0x0f, b'r', b'u', b'n', b't', b'i', b'm', b'e', b'_', b'v', b'e', b'r', b's', b'i', b'o', b'n',
+		0x01, // runtime_version
+		0x00, 0x0b, 0x09, b'p', b'r', b'o', b'd', b'u', b'c', b'e', b'r', b's', 0x02, // producers
+	];
+
+	#[test]
+	fn it_encodes_leb128() {
+		let mut out = Vec::new();
+		write_leb128(624485, &mut out);
+		assert_eq!(vec![0xe5, 0x8e, 0x26], out);
+	}
+
+	#[test]
+	fn it_removes_custom_sections() {
+		let wasm = rewrite_custom_sections(WASM, &["producers".into()], &[]).expect("Failed rewriting");
+		assert_eq!(&WASM[..WASM.len() - 13], wasm.as_slice());
+		assert_eq!(vec!["runtime_version"], custom_sections(&wasm).expect("Failed parsing wasm"));
+	}
+
+	#[test]
+	fn it_replaces_custom_sections() {
+		let replace = vec![("runtime_version".to_string(), vec![0x2a, 0x2a])];
+		let wasm = rewrite_custom_sections(WASM, &[], &replace).expect("Failed rewriting");
+		assert_eq!(WASM.len() + 1, wasm.len());
+		assert_eq!(Some(&[0x2a, 0x2a][..]), custom_section(&wasm, "runtime_version").expect("Failed parsing wasm"));
+		assert_eq!(vec!["runtime_version", "producers"], custom_sections(&wasm).expect("Failed parsing wasm"));
+	}
+
+	#[test]
+	fn it_appends_missing_sections() {
+		let replace = vec![("extra".to_string(), vec![0x01])];
+		let wasm = rewrite_custom_sections(WASM, &[], &replace).expect("Failed rewriting");
+		assert_eq!(vec!["runtime_version", "producers", "extra"], custom_sections(&wasm).expect("Failed parsing wasm"));
+	}
+}
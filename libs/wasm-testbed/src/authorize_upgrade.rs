@@ -0,0 +1,64 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Where the signature of `authorize_upgrade` was taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureSource {
+	/// The metadata of the runtime of the chain
+	Metadata,
+
+	/// The `AUTHORIZE_UPGRADE_CHECK_VERSION` env variable
+	Env,
+
+	/// Neither, the signature without `check_version` is assumed
+	Default,
+}
+
+impl Display for SignatureSource {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Metadata => write!(f, "from the metadata"),
+			Self::Env => write!(f, "from the env"),
+			Self::Default => write!(f, "assumed"),
+		}
+	}
+}
+
+/// The `ParachainSystem::authorize_upgrade` call of a runtime and its hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorizeUpgrade {
+	/// Hash of the encoded call
+	pub hash: String,
+
+	/// Pallet index and call index
+	pub prefix: (u8, u8),
+
+	/// Value of the `check_version` argument, `None` with the signature that predates it
+	pub check_version: Option<bool>,
+
+	pub signature_source: SignatureSource,
+}
+
+impl Display for AuthorizeUpgrade {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.check_version {
+			Some(check_version) => write!(f, "authorize_upgrade(code_hash, check_version: {check_version})")?,
+			None => write!(f, "authorize_upgrade(code_hash)")?,
+		}
+		write!(f, " {}, indices 0x{:02x} 0x{:02x}", self.signature_source, self.prefix.0, self.prefix.1)
+	}
+}
+
+/// A call of the `System` pallet upgrading the runtime in two steps, and its hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SystemUpgradeCall {
+	/// Name of the call, such as `authorize_upgrade`
+	pub call: String,
+
+	/// Pallet index and call index
+	pub prefix: (u8, u8),
+
+	/// Hash of the encoded call
+	pub hash: String,
+}
@@ -0,0 +1,194 @@
+use crate::error::WasmTestbedError;
+use log::LevelFilter;
+use sc_executor::{HeapAllocStrategy, WasmExecutionMethod, WasmtimeInstantiationStrategy};
+use std::{fmt::Display, str::FromStr};
+
+/// Number of extra pages allocated with the [HeapStrategy::Static] strategy, the default of the nodes
+pub const DEFAULT_EXTRA_PAGES: u32 = sc_executor::DEFAULT_HEAP_ALLOC_PAGES;
+
+/// The way the runtime is executed.
+///
+/// The runtime is always compiled by `wasmtime`. These are the instantiation strategies `wasmtime` offers:
+/// whether instances are kept in a pool or recreated for each call, and whether their memory is mapped
+/// copy-on-write from the compiled module or initialized from scratch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMethod {
+	/// Compiled, instances are pooled and use copy-on-write memory. This is what nodes use.
+	#[default]
+	Pooling,
+
+	/// Compiled, a new instance is created for every call, still using copy-on-write memory.
+	Recreate,
+
+	/// Compiled, instances are pooled but without copy-on-write memory.
+	PoolingNoCow,
+
+	/// Compiled, a new instance is created for every call and the memory is fully initialized. Very slow.
+	RecreateNoCow,
+}
+
+impl From<ExecutionMethod> for WasmExecutionMethod {
+	fn from(method: ExecutionMethod) -> Self {
+		let instantiation_strategy = match method {
+			ExecutionMethod::Pooling => WasmtimeInstantiationStrategy::PoolingCopyOnWrite,
+			ExecutionMethod::Recreate => WasmtimeInstantiationStrategy::RecreateInstanceCopyOnWrite,
+			ExecutionMethod::PoolingNoCow => WasmtimeInstantiationStrategy::Pooling,
+			ExecutionMethod::RecreateNoCow => WasmtimeInstantiationStrategy::RecreateInstance,
+		};
+		WasmExecutionMethod::Compiled { instantiation_strategy }
+	}
+}
+
+impl FromStr for ExecutionMethod {
+	type Err = WasmTestbedError;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"pooling" | "compiled" => Ok(Self::Pooling),
+			"recreate" => Ok(Self::Recreate),
+			"pooling-no-cow" => Ok(Self::PoolingNoCow),
+			"recreate-no-cow" => Ok(Self::RecreateNoCow),
+			_ => Err(WasmTestbedError::Config(format!(
+				"Unknown execution method `{s}`, use one of: pooling, recreate, pooling-no-cow, recreate-no-cow"
+			))),
+		}
+	}
+}
+
+impl Display for ExecutionMethod {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Pooling => "pooling",
+			Self::Recreate => "recreate",
+			Self::PoolingNoCow => "pooling-no-cow",
+			Self::RecreateNoCow => "recreate-no-cow",
+		})
+	}
+}
+
+/// How the heap of the runtime is allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeapStrategy {
+	/// The memory grows as needed, up to [WasmTestBedConfig::max_pages] if provided.
+	#[default]
+	Dynamic,
+
+	/// [WasmTestBedConfig::extra_pages] are allocated on top of what the runtime requests initially.
+	Static,
+}
+
+impl FromStr for HeapStrategy {
+	type Err = WasmTestbedError;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"dynamic" => Ok(Self::Dynamic),
+			"static" => Ok(Self::Static),
+			_ => Err(WasmTestbedError::Config(format!("Unknown heap strategy `{s}`, use one of: dynamic, static"))),
+		}
+	}
+}
+
+impl Display for HeapStrategy {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Dynamic => "dynamic",
+			Self::Static => "static",
+		})
+	}
+}
+
+/// Configuration of the executor used by the [crate::WasmTestBed].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmTestBedConfig {
+	/// How instances of the runtime are created
+	pub execution_method: ExecutionMethod,
+
+	/// How the heap is allocated
+	pub heap_strategy: HeapStrategy,
+
+	/// The maximum number of 64KB pages the memory may grow to with the [HeapStrategy::Dynamic] strategy,
+	/// `None` meaning no limit
+	pub max_pages: Option<u32>,
+
+	/// The number of 64KB pages allocated on top of the initial memory of the runtime with the
+	/// [HeapStrategy::Static] strategy
+	pub extra_pages: u32,
+
+	/// Number of runtime instances kept ready for reuse
+	pub instances: usize,
+
+	/// Most verbose level of the runtime logs captured during calls
+	pub log_level: LevelFilter,
+
+	/// Record the calls the runtime makes into the host functions. This is slower.
+	pub trace: bool,
+}
+
+impl Default for WasmTestBedConfig {
+	fn default() -> Self {
+		// Substrate V14 requires a heap of ~34.
+		// Polkadot V14 requires a heap of ~20.
+		Self {
+			execution_method: ExecutionMethod::default(),
+			heap_strategy: HeapStrategy::default(),
+			max_pages: Some(64),
+			extra_pages: DEFAULT_EXTRA_PAGES,
+			instances: 8,
+			log_level: LevelFilter::Off,
+			trace: false,
+		}
+	}
+}
+
+impl WasmTestBedConfig {
+	/// Returns the [HeapAllocStrategy] matching this config
+	pub fn heap_alloc_strategy(&self) -> HeapAllocStrategy {
+		match self.heap_strategy {
+			HeapStrategy::Dynamic => HeapAllocStrategy::Dynamic { maximum_pages: self.max_pages },
+			HeapStrategy::Static => HeapAllocStrategy::Static { extra_pages: self.extra_pages },
+		}
+	}
+}
+
+impl Display for WasmTestBedConfig {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let heap = match self.heap_alloc_strategy() {
+			HeapAllocStrategy::Dynamic { maximum_pages: Some(pages) } => format!("dynamic, max {pages} pages"),
+			HeapAllocStrategy::Dynamic { maximum_pages: None } => "dynamic, unlimited".to_string(),
+			HeapAllocStrategy::Static { extra_pages } => format!("static, {extra_pages} extra pages"),
+		};
+		write!(
+			f,
+			"method: {}, heap: {heap}, instances: {}, runtime logs: {}, trace: {}",
+			self.execution_method, self.instances, self.log_level, self.trace
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests_config {
+	use super::*;
+
+	#[test]
+	fn it_parses_execution_methods() {
+		assert_eq!(ExecutionMethod::Pooling, ExecutionMethod::from_str("pooling").expect("Failed parsing"));
+		assert_eq!(
+			ExecutionMethod::RecreateNoCow,
+			ExecutionMethod::from_str("Recreate-No-Cow").expect("Failed parsing")
+		);
+		assert!(ExecutionMethod::from_str("interpreted").is_err());
+	}
+
+	#[test]
+	fn it_builds_the_heap_strategy() {
+		let config = WasmTestBedConfig::default();
+		assert_eq!(HeapAllocStrategy::Dynamic { maximum_pages: Some(64) }, config.heap_alloc_strategy());
+
+		let config = WasmTestBedConfig { heap_strategy: HeapStrategy::Static, ..Default::default() };
+		assert_eq!(HeapAllocStrategy::Static { extra_pages: DEFAULT_EXTRA_PAGES }, config.heap_alloc_strategy());
+
+		let config = WasmTestBedConfig { heap_strategy: HeapStrategy::Static, extra_pages: 64, ..Default::default() };
+		assert_eq!(HeapAllocStrategy::Static { extra_pages: 64 }, config.heap_alloc_strategy());
+	}
+}
@@ -2,6 +2,8 @@ use sc_executor_common::error::WasmError;
 use substrate_runtime_proposal_hash::error::RuntimePropHashError;
 use thiserror::Error;
 
+use crate::RuntimeLog;
+
 pub type Result<T> = std::result::Result<T, WasmTestbedError>;
 
 #[derive(Error, Debug, Clone)]
@@ -9,8 +11,15 @@ pub enum WasmTestbedError {
 	#[error("Error while loading source: `{0}`")]
 	Loading(String),
 
-	#[error("Error while calling method: `{0}`")]
-	Calling(String),
+	/// The call failed (method, cause, logs emitted by the runtime before failing)
+	#[error("Error while calling method `{0}`: {1}{}", show_logs(.2))]
+	Calling(String, String, Vec<RuntimeLog>),
+
+	#[error("Error while instantiating the runtime: {0}")]
+	Instantiating(String),
+
+	#[error("Error while loading the state: {0}")]
+	State(String),
 
 	#[error("Failed decoding bytes: {0:?}")]
 	Decoding(Vec<u8>),
@@ -20,6 +29,24 @@ pub enum WasmTestbedError {
 
 	#[error("This runtime is not supported")]
 	UnsupportedRuntime,
+
+	#[error("Invalid testbed configuration: {0}")]
+	Config(String),
+}
+
+/// Show the runtime logs on their own lines, after the error
+fn show_logs(logs: &[RuntimeLog]) -> String {
+	logs.iter().map(|log| format!("\n  {log}")).collect()
+}
+
+impl WasmTestbedError {
+	/// Logs emitted by the runtime before the error, if any
+	pub fn logs(&self) -> &[RuntimeLog] {
+		match self {
+			Self::Calling(_, _, logs) => logs,
+			_ => &[],
+		}
+	}
 }
 
 impl From<RuntimePropHashError> for WasmTestbedError {
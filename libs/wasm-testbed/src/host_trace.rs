@@ -0,0 +1,265 @@
+use serde::Serialize;
+use sp_externalities::{decl_extension, ExternalitiesExt};
+use sp_wasm_interface::{
+	anyhow,
+	wasmtime::{self, Caller},
+	Function, FunctionContext, HostFunctionRegistry, HostFunctions, IntoValue, Pointer, Signature, TryFromValue, Value,
+	ValueType, WordSize,
+};
+use std::{
+	cell::Cell,
+	marker::PhantomData,
+	panic::{catch_unwind, AssertUnwindSafe},
+	sync::{Arc, Mutex},
+	time::Instant,
+};
+
+/// A call from the runtime into a host function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostCall {
+	/// Name of the host function, such as `ext_storage_get_version_1`
+	pub name: String,
+
+	/// Number of bytes the host function read from the runtime memory
+	pub bytes_in: usize,
+
+	/// Number of bytes the host function allocated or wrote in the runtime memory
+	pub bytes_out: usize,
+
+	/// Time spent in the host function, in nanoseconds
+	pub elapsed_ns: u64,
+}
+
+decl_extension! {
+	/// Extension registered in the externalities of a call to record the host calls
+	pub struct HostCallTrace(Arc<Mutex<Vec<HostCall>>>);
+}
+
+/// A [FunctionContext] counting the bytes going through the memory of the runtime
+struct CountingContext<'a> {
+	inner: &'a mut dyn FunctionContext,
+	bytes_in: Cell<usize>,
+	bytes_out: usize,
+}
+
+impl FunctionContext for CountingContext<'_> {
+	fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> sp_wasm_interface::Result<()> {
+		self.bytes_in.set(self.bytes_in.get() + dest.len());
+		self.inner.read_memory_into(address, dest)
+	}
+
+	fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> sp_wasm_interface::Result<()> {
+		self.bytes_out += data.len();
+		self.inner.write_memory(address, data)
+	}
+
+	fn allocate_memory(&mut self, size: WordSize) -> sp_wasm_interface::Result<Pointer<u8>> {
+		self.inner.allocate_memory(size)
+	}
+
+	fn deallocate_memory(&mut self, ptr: Pointer<u8>) -> sp_wasm_interface::Result<()> {
+		self.inner.deallocate_memory(ptr)
+	}
+
+	fn register_panic_error_message(&mut self, message: &str) {
+		self.inner.register_panic_error_message(message)
+	}
+}
+
+/// Execute a host function through its dynamic interface and record the call
+fn execute_traced(
+	function: &'static dyn Function,
+	context: &mut dyn FunctionContext,
+	args: Vec<Value>,
+) -> anyhow::Result<Option<Value>> {
+	let mut context = CountingContext { inner: context, bytes_in: Cell::new(0), bytes_out: 0 };
+	let start = Instant::now();
+	let result = catch_unwind(AssertUnwindSafe(|| function.execute(&mut context, &mut args.into_iter())));
+	let elapsed_ns = start.elapsed().as_nanos() as u64;
+
+	let call = HostCall {
+		name: function.name().to_string(),
+		bytes_in: context.bytes_in.get(),
+		bytes_out: context.bytes_out,
+		elapsed_ns,
+	};
+	sp_externalities::with_externalities(|mut ext| {
+		if let Some(trace) = ext.extension::<HostCallTrace>() {
+			if let Ok(mut calls) = trace.lock() {
+				calls.push(call);
+			}
+		}
+	});
+
+	match result {
+		Ok(result) => result.map_err(anyhow::Error::msg),
+		Err(panic) => {
+			let message = panic
+				.downcast_ref::<String>()
+				.map(String::as_str)
+				.or_else(|| panic.downcast_ref::<&'static str>().copied())
+				.unwrap_or_default();
+			Err(anyhow::Error::msg(format!("host code panicked while being called by the runtime: {message}")))
+		}
+	}
+}
+
+/// The value returned by a host function, if any
+trait HostReturn: Sized {
+	const VALUE_TYPE: Option<ValueType>;
+
+	fn from_value(value: Option<Value>) -> anyhow::Result<Self>;
+}
+
+impl HostReturn for () {
+	const VALUE_TYPE: Option<ValueType> = None;
+
+	fn from_value(_value: Option<Value>) -> anyhow::Result<Self> {
+		Ok(())
+	}
+}
+
+macro_rules! impl_host_return {
+	($($ty:ty),*) => {
+		$(
+			impl HostReturn for $ty {
+				const VALUE_TYPE: Option<ValueType> = Some(<$ty as IntoValue>::VALUE_TYPE);
+
+				fn from_value(value: Option<Value>) -> anyhow::Result<Self> {
+					value
+						.and_then(<$ty>::try_from_value)
+						.ok_or_else(|| anyhow::Error::msg("Unexpected value returned by the host"))
+				}
+			}
+		)*
+	};
+}
+
+impl_host_return!(i32, i64);
+
+/// Register `function` with the first of the listed signatures it matches and return `true`.
+/// Wasmtime needs the exact types of the closures, so we list the signatures of the host functions we provide.
+macro_rules! register_traced {
+	($registry:ident, $function:ident, $( ($($arg:ident: $ty:ty),*) -> $ret:ty ),* $(,)?) => {
+		$(
+			if $function.signature() == Signature::new(vec![$(<$ty as IntoValue>::VALUE_TYPE),*], <$ret as HostReturn>::VALUE_TYPE) {
+				return $registry.register_static(
+					$function.name(),
+					move |caller: Caller<T::State>, $($arg: $ty),*| -> anyhow::Result<$ret> {
+						T::with_function_context(caller, |context| {
+							let args: Vec<Value> = vec![$($arg.into_value()),*];
+							<$ret as HostReturn>::from_value(execute_traced($function, context, args)?)
+						})
+					},
+				).map(|_| true);
+			}
+		)*
+	};
+}
+
+/// Register `function` so that its calls are traced. Returns `false`, without registering it, if its
+/// signature is not supported.
+fn register_traced<T: HostFunctionRegistry>(
+	registry: &mut T,
+	function: &'static dyn Function,
+) -> core::result::Result<bool, T::Error> {
+	register_traced!(registry, function,
+		() -> (),
+		() -> i32,
+		() -> i64,
+		(a: i32) -> (),
+		(a: i32) -> i32,
+		(a: i32) -> i64,
+		(a: i64) -> (),
+		(a: i64) -> i32,
+		(a: i64) -> i64,
+		(a: i32, b: i32) -> (),
+		(a: i32, b: i32) -> i64,
+		(a: i32, b: i64) -> (),
+		(a: i32, b: i64) -> i32,
+		(a: i32, b: i64) -> i64,
+		(a: i64, b: i32) -> (),
+		(a: i64, b: i32) -> i32,
+		(a: i64, b: i32) -> i64,
+		(a: i64, b: i64) -> (),
+		(a: i64, b: i64) -> i32,
+		(a: i64, b: i64) -> i64,
+		(a: i32, b: i32, c: i32) -> (),
+		(a: i32, b: i32, c: i32) -> i32,
+		(a: i32, b: i32, c: i32) -> i64,
+		(a: i32, b: i32, c: i64) -> i64,
+		(a: i32, b: i64, c: i32) -> i32,
+		(a: i32, b: i64, c: i64) -> (),
+		(a: i32, b: i64, c: i64) -> i64,
+		(a: i64, b: i64, c: i32) -> i64,
+		(a: i64, b: i64, c: i64) -> (),
+		(a: i64, b: i64, c: i64) -> i64,
+		(a: i32, b: i64, c: i64, d: i64) -> i32,
+		(a: i64, b: i64, c: i64, d: i32) -> i64,
+		(a: i64, b: i64, c: i64, d: i64) -> i64,
+		(a: i32, b: i64, c: i64, d: i64, e: i32) -> i32,
+	);
+
+	Ok(false)
+}
+
+/// A registry forwarding the registration of the functions named `names` only, so that the functions
+/// we cannot trace are registered untraced by the [HostFunctions] providing them
+struct UntracedRegistry<'a, T> {
+	inner: &'a mut T,
+	names: Vec<&'static str>,
+}
+
+impl<T: HostFunctionRegistry> HostFunctionRegistry for UntracedRegistry<'_, T> {
+	type State = T::State;
+	type Error = T::Error;
+	type FunctionContext = T::FunctionContext;
+
+	fn with_function_context<R>(
+		caller: Caller<Self::State>,
+		callback: impl FnOnce(&mut dyn FunctionContext) -> R,
+	) -> R {
+		T::with_function_context(caller, callback)
+	}
+
+	fn register_static<Params, Results>(
+		&mut self,
+		fn_name: &str,
+		func: impl wasmtime::IntoFunc<Self::State, Params, Results> + 'static,
+	) -> core::result::Result<(), Self::Error> {
+		if self.names.contains(&fn_name) {
+			self.inner.register_static(fn_name, func)
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// Wraps the host functions `H` to record every call the runtime makes into them
+/// in the [HostCallTrace] extension.
+pub struct TracedHostFunctions<H>(PhantomData<H>);
+
+impl<H: HostFunctions> HostFunctions for TracedHostFunctions<H> {
+	fn host_functions() -> Vec<&'static dyn Function> {
+		H::host_functions()
+	}
+
+	fn register_static<T>(registry: &mut T) -> core::result::Result<(), T::Error>
+	where
+		T: HostFunctionRegistry,
+	{
+		let mut names = Vec::new();
+		for function in H::host_functions() {
+			if !register_traced(registry, function)? {
+				log::warn!("Cannot trace host function {} with signature {:?}", function.name(), function.signature());
+				names.push(function.name());
+			}
+		}
+
+		// The calls to these functions are not part of the trace but the runtime can still be instantiated
+		if !names.is_empty() {
+			H::register_static(&mut UntracedRegistry { inner: registry, names })?;
+		}
+		Ok(())
+	}
+}
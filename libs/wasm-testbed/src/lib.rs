@@ -1,16 +1,40 @@
+mod authorize_upgrade;
+mod config;
 mod error;
-mod logger_mock;
+mod host_trace;
+mod runtime_logger;
+mod state;
 
+pub use authorize_upgrade::{AuthorizeUpgrade, SignatureSource, SystemUpgradeCall};
+pub use config::{ExecutionMethod, HeapStrategy, WasmTestBedConfig, DEFAULT_EXTRA_PAGES};
 pub use error::{Result, WasmTestbedError};
 use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
 use hex::FromHex;
-use sc_executor::{WasmExecutionMethod, WasmExecutor};
-use sc_executor_common::runtime_blob::RuntimeBlob;
+pub use host_trace::HostCall;
+use host_trace::{HostCallTrace, TracedHostFunctions};
+use runtime_logger::{KeptLogs, RuntimeLogging, RuntimeLogs};
+pub use runtime_logger::{RuntimeLog, MAX_KEPT_LOGS};
+use sc_executor::{with_externalities_safe, WasmExecutor};
+use sc_executor_common::wasm_runtime::AllocationStats;
 use scale::Decode;
-use sp_core::Hasher;
+use serde::Serialize;
+use sp_core::{
+	traits::{RuntimeCode, WrappedRuntimeCode},
+	Hasher,
+};
+pub use sp_externalities::Externalities;
 use sp_runtime::traits::BlakeTwo256;
+pub use sp_state_machine::BasicExternalities;
 use sp_version::RuntimeVersion as SubstrateRuntimeVersion;
-use std::{env, fmt};
+use sp_wasm_interface::{ExtendedHostFunctions, HostFunctions, ValueType};
+pub use state::{StateFormat, StateSnapshot};
+use std::{
+	any::TypeId,
+	borrow::Cow,
+	env, fmt,
+	sync::{Arc, Mutex, OnceLock},
+	time::{Duration, Instant},
+};
 use substrate_runtime_proposal_hash::{error::RuntimePropHashError, *};
 use wasm_loader::*;
 
@@ -18,9 +42,54 @@ use wasm_loader::*;
 pub type ReservedMeta = [u8; 4];
 const META: ReservedMeta = [0x6d, 0x65, 0x74, 0x61]; // 1635018093 in decimal, 'atem' as string...
 
+/// The host functions of `sp_io`, with the logging ones replaced by ours to capture the runtime logs
+type TestbedHostFunctions = ExtendedHostFunctions<sp_io::SubstrateHostFunctions, RuntimeLogging>;
+
+/// The executor of the testbed, with or without the tracing of the host calls
+enum TestbedExecutor {
+	Plain(WasmExecutor<TestbedHostFunctions>),
+	Traced(WasmExecutor<TracedHostFunctions<TestbedHostFunctions>>),
+}
+
+/// The output of a call into the runtime along with the logs it emitted
+/// and the host calls it made, if tracing is enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallResult {
+	pub output: Vec<u8>,
+	pub logs: Vec<RuntimeLog>,
+	pub trace: Vec<HostCall>,
+	pub allocation: Option<AllocatorStats>,
+}
+
+/// Statistics of the allocator the host provides to the runtime, for one call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AllocatorStats {
+	/// The peak number of bytes allocated at the same time
+	pub bytes_allocated_peak: u32,
+
+	/// The sum of all the allocations
+	pub bytes_allocated_sum: u128,
+
+	/// Bytes of linear memory used by the allocator above the heap base
+	pub address_space_used: u32,
+}
+
+impl From<AllocationStats> for AllocatorStats {
+	fn from(stats: AllocationStats) -> Self {
+		Self {
+			bytes_allocated_peak: stats.bytes_allocated_peak,
+			bytes_allocated_sum: stats.bytes_allocated_sum,
+			address_space_used: stats.address_space_used,
+		}
+	}
+}
+
 /// A Substrate wasm runtime testbed. This is a (very) minimum environment that allows loading and
 /// executing *some* calls into the wasm. Note that any call that most calls that requires storage will fail
-/// (for instance `balances.transfer`, `system.remark`, ...)
+/// (for instance `balances.transfer`, `system.remark`, ...) unless a [StateSnapshot] is provided.
+///
+/// The runtime is compiled once, when the testbed is created, and the same executor is used for
+/// all the calls. The metadata is only fetched and decoded when first needed.
 pub struct WasmTestBed {
 	/// The WASM as bytes, it has been uncompressed as needed here.
 	wasm: Vec<u8>,
@@ -30,58 +99,80 @@ pub struct WasmTestBed {
 
 	compression: Compression,
 
-	/// Prefixed version of the RuntimeMetadata.
-	runtime_metadata_prefixed: RuntimeMetadataPrefixed,
+	/// The executor holding the compiled runtime and its instances
+	executor: TestbedExecutor,
+
+	/// The configuration the executor was built with
+	config: WasmTestBedConfig,
 
-	metadata: Vec<u8>,
+	/// Hash of the uncompressed wasm, used by the executor to find our runtime in its cache
+	code_hash: Vec<u8>,
 
-	/// Metadata version
-	metadata_version: u8,
+	/// Raw metadata as returned by `Metadata_metadata`
+	metadata: OnceLock<Vec<u8>>,
+
+	/// Prefixed version of the RuntimeMetadata.
+	runtime_metadata_prefixed: OnceLock<RuntimeMetadataPrefixed>,
 
 	/// Core version as reported by the runtime
 	core_version: SubstrateRuntimeVersion,
+
+	/// Logs emitted by the runtime during the calls since they were last taken
+	logs: Mutex<KeptLogs>,
+
+	/// Host calls made during the last call, if tracing is enabled
+	trace: Arc<Mutex<Vec<HostCall>>>,
+
+	/// Storage the calls run against, empty if not set
+	state: Option<StateSnapshot>,
 }
 
 impl fmt::Debug for WasmTestBed {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		f.debug_struct("SubstrateWasm")
-			.field("metadata_version", &self.metadata_version)
+			.field("spec", &self.core_version.spec_name)
 			.field("wasm length", &self.wasm.len())
+			.field("config", &self.config)
 			.finish()
 	}
 }
 
 impl WasmTestBed {
+	/// Load a runtime using the default [WasmTestBedConfig].
 	pub fn new(source: &Source) -> Result<Self> {
+		Self::with_config(source, &WasmTestBedConfig::default())
+	}
+
+	/// Load a runtime using the provided [WasmTestBedConfig].
+	pub fn with_config(source: &Source, config: &WasmTestBedConfig) -> Result<Self> {
 		log::debug!("Loading testbed with source: {source:?}");
+		log::debug!("Testbed config: {config}");
 
 		let loader = WasmLoader::load_from_source(source).map_err(|_| WasmTestbedError::Loading(source.to_string()))?;
 		let wasm = loader.uncompressed_bytes().to_vec();
-		let metadata_encoded = Self::call(&wasm, "Metadata_metadata", &[])?;
-		let metadata =
-			<Vec<u8>>::decode(&mut &metadata_encoded[..]).map_err(|_| WasmTestbedError::Decoding(metadata_encoded))?;
 
-		log::debug!("decoded_metadata bytes, length: {}", metadata.len());
-		if !WasmTestBed::is_substrate_wasm(&metadata) {
-			return Err(WasmTestbedError::UnsupportedRuntime);
-		}
-
-		let runtime_metadata_prefixed: RuntimeMetadataPrefixed =
-			scale::Decode::decode(&mut &metadata[..]).map_err(|e| {
-				log::error!("e = {:#?}", e);
-				WasmTestbedError::Decoding(metadata[..128].to_vec())
-			})?;
-
-		let core_version = Self::get_core_version(&wasm)?;
-		let metadata_version = Self::get_metadata_version(&metadata);
+		let executor = Self::executor(config);
+		let code_hash = sp_core::blake2_256(&wasm).to_vec();
+		let trace = Arc::new(Mutex::new(Vec::new()));
+		let code_fetcher = WrappedRuntimeCode(Cow::Borrowed(&wasm));
+		let runtime_code = Self::runtime_code(&code_fetcher, &code_hash);
+		let mut ext = BasicExternalities::default();
+		let core_version = Self::call_with(&executor, config, &runtime_code, "Core_version", &[], &trace, &mut ext)?;
+		let logs = Mutex::new(KeptLogs::new(core_version.logs));
+		let core_version = <SubstrateRuntimeVersion>::decode(&mut &core_version.output[..])?;
 
 		Ok(Self {
 			wasm,
 			bytes: loader.original_bytes().to_vec(),
-			runtime_metadata_prefixed,
-			metadata,
-			metadata_version,
+			executor,
+			config: config.clone(),
+			code_hash,
+			metadata: OnceLock::new(),
+			runtime_metadata_prefixed: OnceLock::new(),
 			core_version,
+			logs,
+			trace,
+			state: None,
 			compression: loader.compression(),
 		})
 	}
@@ -106,12 +197,13 @@ impl WasmTestBed {
 		[metadata[0], metadata[1], metadata[2], metadata[3]] == META
 	}
 
-	pub fn reserved_meta(&self) -> ReservedMeta {
-		[self.metadata[0], self.metadata[1], self.metadata[2], self.metadata[3]]
+	pub fn reserved_meta(&self) -> Result<ReservedMeta> {
+		let metadata = self.metadata_bytes()?;
+		Ok([metadata[0], metadata[1], metadata[2], metadata[3]])
 	}
 
-	pub fn reserved_meta_valid(&self) -> bool {
-		self.reserved_meta() == META
+	pub fn reserved_meta_valid(&self) -> Result<bool> {
+		Ok(self.reserved_meta()? == META)
 	}
 
 	pub fn wasm(&self) -> &WasmBytes {
@@ -126,41 +218,209 @@ impl WasmTestBed {
 		data[4]
 	}
 
-	/// Call a function in the provided wasm. Note that we can only call a few limited set of functions
+	/// Call a function in the wasm. Note that we can only call a few limited set of functions
 	/// as we have no blocks, storage, etc...
-	fn call(wasm: &[u8], method: &str, call_data: &[u8]) -> Result<Vec<u8>> {
-		let mut ext = sp_state_machine::BasicExternalities::default();
-
-		// Substrate V14 requires a heap of ~34.
-		// Polkadot V14 requires a heap of ~20.
-		let executor: WasmExecutor<sp_io::SubstrateHostFunctions> = WasmExecutor::builder()
-			.with_execution_method(WasmExecutionMethod::default())
-			.with_offchain_heap_alloc_strategy(sc_executor::HeapAllocStrategy::Dynamic { maximum_pages: Some(64) })
-			.with_max_runtime_instances(8)
+	pub fn call(&self, method: &str, call_data: &[u8]) -> Result<Vec<u8>> {
+		Ok(self.call_with_logs(method, call_data)?.output)
+	}
+
+	/// Same as [WasmTestBed::call] but also returns the logs the runtime emitted, up to
+	/// [WasmTestBedConfig::log_level], and the host calls it made if [WasmTestBedConfig::trace] is set.
+	pub fn call_with_logs(&self, method: &str, call_data: &[u8]) -> Result<CallResult> {
+		let mut state = self.state.as_ref().map(StateSnapshot::externalities).unwrap_or_default();
+		self.call_with_state(method, call_data, &mut state)
+	}
+
+	/// Run the next calls against the storage of `state`. Each call gets its own copy
+	/// so the changes a call makes are not seen by the next ones.
+	pub fn with_state(mut self, state: StateSnapshot) -> Self {
+		self.state = Some(state);
+		self
+	}
+
+	/// Get the storage the calls run against, if any
+	pub fn state(&self) -> Option<&StateSnapshot> {
+		self.state.as_ref()
+	}
+
+	/// Same as [WasmTestBed::call_with_logs] but the runtime reads and writes the storage of `state`
+	/// instead of an empty one. This is how the calls that need the state of a chain can succeed.
+	pub fn call_with_state(
+		&self,
+		method: &str,
+		call_data: &[u8],
+		state: &mut BasicExternalities,
+	) -> Result<CallResult> {
+		let code_fetcher = WrappedRuntimeCode(Cow::Borrowed(&self.wasm));
+		let runtime_code = Self::runtime_code(&code_fetcher, &self.code_hash);
+		let result =
+			Self::call_with(&self.executor, &self.config, &runtime_code, method, call_data, &self.trace, state)
+				.inspect_err(|e| self.keep_logs(e.logs()))?;
+		self.keep_logs(&result.logs);
+		Ok(result)
+	}
+
+	fn keep_logs(&self, logs: &[RuntimeLog]) {
+		if let Ok(mut kept) = self.logs.lock() {
+			kept.extend(logs);
+		}
+	}
+
+	/// Take the logs the runtime emitted, up to [WasmTestBedConfig::log_level], during the calls made since
+	/// they were last taken. Only the last [MAX_KEPT_LOGS] are kept.
+	pub fn take_logs(&self) -> Vec<RuntimeLog> {
+		self.logs.lock().map(|mut kept| kept.take()).unwrap_or_default()
+	}
+
+	/// Get the host calls made during the last call, including a failed one.
+	/// This is empty unless [WasmTestBedConfig::trace] is set.
+	pub fn last_trace(&self) -> Vec<HostCall> {
+		self.trace.lock().map(|trace| trace.clone()).unwrap_or_default()
+	}
+
+	/// Get the configuration used by this testbed
+	pub fn config(&self) -> &WasmTestBedConfig {
+		&self.config
+	}
+
+	fn executor(config: &WasmTestBedConfig) -> TestbedExecutor {
+		match config.trace {
+			true => TestbedExecutor::Traced(Self::build_executor(config)),
+			false => TestbedExecutor::Plain(Self::build_executor(config)),
+		}
+	}
+
+	fn build_executor<H: HostFunctions>(config: &WasmTestBedConfig) -> WasmExecutor<H> {
+		WasmExecutor::builder()
+			.with_execution_method(config.execution_method.into())
+			.with_onchain_heap_alloc_strategy(config.heap_alloc_strategy())
+			.with_offchain_heap_alloc_strategy(config.heap_alloc_strategy())
+			.with_max_runtime_instances(config.instances)
 			.with_runtime_cache_size(2)
-			.build();
+			.build()
+	}
+
+	/// The [RuntimeCode] the executor uses to find our runtime in its cache
+	fn runtime_code<'a>(code_fetcher: &'a WrappedRuntimeCode, code_hash: &[u8]) -> RuntimeCode<'a> {
+		RuntimeCode { code_fetcher, heap_pages: None, hash: code_hash.to_vec() }
+	}
+
+	fn call_with(
+		executor: &TestbedExecutor,
+		config: &WasmTestBedConfig,
+		runtime_code: &RuntimeCode,
+		method: &str,
+		call_data: &[u8],
+		trace: &Arc<Mutex<Vec<HostCall>>>,
+		ext: &mut BasicExternalities,
+	) -> Result<CallResult> {
+		let runtime_logs = RuntimeLogs::new(config.log_level);
+		let collector = runtime_logs.0.clone();
+		let take_logs = || collector.lock().map(|mut c| std::mem::take(&mut c.logs)).unwrap_or_default();
+
+		if let Ok(mut trace) = trace.lock() {
+			trace.clear();
+		}
+
+		ext.register_extension(runtime_logs);
+		ext.register_extension(HostCallTrace(trace.clone()));
+
+		let result = match executor {
+			TestbedExecutor::Plain(executor) => Self::call_on(executor, config, runtime_code, ext, method, call_data),
+			TestbedExecutor::Traced(executor) => Self::call_on(executor, config, runtime_code, ext, method, call_data),
+		};
+		// The extensions only make sense for this call, the state may be used for the next ones
+		ext.extensions().deregister(TypeId::of::<RuntimeLogs>());
+		ext.extensions().deregister(TypeId::of::<HostCallTrace>());
+		let (output, allocation) =
+			result.map_err(|e| WasmTestbedError::Calling(method.to_string(), e.to_string(), take_logs()))?;
+
+		let trace = trace.lock().map(|trace| trace.clone()).unwrap_or_default();
+		Ok(CallResult { output, logs: take_logs(), trace, allocation: allocation.map(AllocatorStats::from) })
+	}
 
-		let runtime_blob = RuntimeBlob::new(wasm)?;
-		executor
-			.uncached_call(runtime_blob, &mut ext, true, method, call_data)
-			.map_err(|_| WasmTestbedError::Calling(method.to_string()))
+	fn call_on<H: HostFunctions>(
+		executor: &WasmExecutor<H>,
+		config: &WasmTestBedConfig,
+		runtime_code: &RuntimeCode,
+		ext: &mut BasicExternalities,
+		method: &str,
+		call_data: &[u8],
+	) -> sc_executor::error::Result<(Vec<u8>, Option<AllocationStats>)> {
+		executor.with_instance(
+			runtime_code,
+			ext,
+			config.heap_alloc_strategy(),
+			|_module, mut instance, _version, mut ext| {
+				with_externalities_safe(&mut **ext, move || {
+					let (result, stats) = instance.call_with_allocation_stats(method, call_data);
+					if let Some(stats) = &stats {
+						log::debug!(
+							"{method}: heap peak {} bytes, address space used {} bytes",
+							stats.bytes_allocated_peak,
+							stats.address_space_used
+						);
+					}
+					result.map(|output| (output, stats))
+				})
+			},
+		)
 	}
 
-	pub fn get_core_version(wasm: &[u8]) -> Result<SubstrateRuntimeVersion> {
-		let encoded = Self::call(wasm, "Core_version", &[])?;
-		let version = <SubstrateRuntimeVersion>::decode(&mut &encoded[..])?;
-		Ok(version)
+	/// Measure the time it takes to create a new instance of the runtime, once it is compiled.
+	/// The calls usually reuse instances so they do not pay this cost every time.
+	pub fn instantiation_time(&self) -> Result<Duration> {
+		let mut ext = BasicExternalities::default();
+		let code_fetcher = WrappedRuntimeCode(Cow::Borrowed(&self.wasm));
+		let runtime_code = Self::runtime_code(&code_fetcher, &self.code_hash);
+
+		match &self.executor {
+			TestbedExecutor::Plain(executor) => Self::instantiate(executor, &self.config, &runtime_code, &mut ext),
+			TestbedExecutor::Traced(executor) => Self::instantiate(executor, &self.config, &runtime_code, &mut ext),
+		}
+		.map_err(|e| WasmTestbedError::Instantiating(e.to_string()))
+	}
+
+	fn instantiate<H: HostFunctions>(
+		executor: &WasmExecutor<H>,
+		config: &WasmTestBedConfig,
+		runtime_code: &RuntimeCode,
+		ext: &mut BasicExternalities,
+	) -> sc_executor::error::Result<Duration> {
+		executor.with_instance(runtime_code, ext, config.heap_alloc_strategy(), |module, _instance, _version, _ext| {
+			let start = Instant::now();
+			module.new_instance()?;
+			Ok(Ok(start.elapsed()))
+		})
+	}
+
+	/// Get the raw metadata bytes, calling `Metadata_metadata` the first time.
+	pub fn metadata_bytes(&self) -> Result<&Vec<u8>> {
+		if let Some(metadata) = self.metadata.get() {
+			return Ok(metadata);
+		}
+
+		let metadata_encoded = self.call("Metadata_metadata", &[])?;
+		let metadata =
+			<Vec<u8>>::decode(&mut &metadata_encoded[..]).map_err(|_| WasmTestbedError::Decoding(metadata_encoded))?;
+
+		log::debug!("decoded_metadata bytes, length: {}", metadata.len());
+		if !WasmTestBed::is_substrate_wasm(&metadata) {
+			return Err(WasmTestbedError::UnsupportedRuntime);
+		}
+
+		Ok(self.metadata.get_or_init(|| metadata))
 	}
 
 	/// We probably don't need to maintain this as decoding the runtime will
 	/// tell us whether the version is supported or not.
 	pub fn is_supported(&self) -> bool {
-		matches!(self.metadata_version, x if x >= 12)
+		matches!(self.metadata_version(), Ok(x) if x >= 12)
 	}
 
-	/// Get a reference to the substrate wasm's metadata version.
-	pub fn metadata_version(&self) -> &u8 {
-		&self.metadata_version
+	/// Get the substrate wasm's metadata version.
+	pub fn metadata_version(&self) -> Result<u8> {
+		Ok(Self::get_metadata_version(self.metadata_bytes()?))
 	}
 
 	/// Get the size of the runtime
@@ -169,13 +429,25 @@ impl WasmTestBed {
 	}
 
 	/// Get a reference to the substrate wasm's runtime metadata prefixed.
-	pub fn runtime_metadata_prefixed(&self) -> &RuntimeMetadataPrefixed {
-		&self.runtime_metadata_prefixed
+	/// The metadata is decoded the first time it is requested.
+	pub fn runtime_metadata_prefixed(&self) -> Result<&RuntimeMetadataPrefixed> {
+		if let Some(runtime_metadata_prefixed) = self.runtime_metadata_prefixed.get() {
+			return Ok(runtime_metadata_prefixed);
+		}
+
+		let metadata = self.metadata_bytes()?;
+		let runtime_metadata_prefixed: RuntimeMetadataPrefixed =
+			scale::Decode::decode(&mut &metadata[..]).map_err(|e| {
+				log::error!("e = {:#?}", e);
+				WasmTestbedError::Decoding(metadata[..128].to_vec())
+			})?;
+
+		Ok(self.runtime_metadata_prefixed.get_or_init(|| runtime_metadata_prefixed))
 	}
 
 	/// Get the `RuntimeMetada`
-	pub fn metadata(&self) -> &RuntimeMetadata {
-		&self.runtime_metadata_prefixed.1
+	pub fn metadata(&self) -> Result<&RuntimeMetadata> {
+		Ok(&self.runtime_metadata_prefixed()?.1)
 	}
 
 	/// Get a reference to the substrate wasm's core version.
@@ -183,40 +455,83 @@ impl WasmTestBed {
 		self.core_version.clone()
 	}
 
-	/// Compute the proposal hash of the runtime
+	/// Find `pallet::call` in the metadata of the runtime of the chain, this runtime if `chain` is not given
+	fn call_info(&self, chain: Option<&RuntimeMetadata>, pallet: &str, call: &str) -> Option<CallInfo> {
+		chain.or_else(|| self.metadata().ok()).and_then(|metadata| call_info(metadata, pallet, call))
+	}
+
+	/// Compute the proposal hash of the runtime, which is the hash of the `System::set_code` call
 	pub fn proposal_hash(&self) -> Result<String> {
-		let result: SrhResult = get_result(substrate_runtime_proposal_hash::PREFIX_SYSTEM_SETCODE, &self.bytes)?;
+		self.proposal_hash_for(None)
+	}
+
+	/// Compute the proposal hash of the runtime for the chain whose runtime has the metadata `chain`.
+	/// The call is encoded with the indices of that runtime, the one executing it.
+	pub fn proposal_hash_for(&self, chain: Option<&RuntimeMetadata>) -> Result<String> {
+		let prefix = self.call_info(chain, SYSTEM_PALLET, SET_CODE_CALL).map(|info| info.prefix).unwrap_or_else(|| {
+			log::warn!("`{SYSTEM_PALLET}::{SET_CODE_CALL}` not found in the metadata, using {PREFIX_SYSTEM_SETCODE:?}");
+			PREFIX_SYSTEM_SETCODE
+		});
+		let result: SrhResult = get_result(prefix, &self.bytes)?;
 		Ok(format!("0x{}", &result.encoded_hash))
 	}
 
-	/// Compute the proposal hash of the runtime
+	/// Compute the hash of the `ParachainSystem::authorize_upgrade` call of the runtime
 	pub fn parachain_authorize_upgrade_hash(&self) -> Result<String> {
-		let s1 = env::var(PARACHAIN_PALLET_ID_ENV)
-			.unwrap_or_else(|_| DEFAULT_PARACHAIN_PALLET_ID.into())
-			.replacen("0x", "", 1);
-		let s2 = env::var(AUTHORIZE_UPGRADE_PREFIX_ENV)
-			.unwrap_or_else(|_| DEFAULT_AUTHORIZE_UPGRADE_PREFIX.into())
-			.replacen("0x", "", 1);
-		let check_version = env::var(AUTHORIZE_UPGRADE_CHECK_VERSION_ENV)
-			.map(|var| if var == "true" { Some(true) } else { Some(false) })
-			.unwrap_or_else(|_| None);
-		if check_version.is_none() {
-			log::warn!("Env variable `{AUTHORIZE_UPGRADE_CHECK_VERSION_ENV}` not specified. If your chain is running on Substrate >= 0.9.41, this will most likely yield wrong values for the `parachainSystem::authorizeUpgrade` call hash.");
-		}
-
-		let decoded1 = <[u8; 1]>::from_hex(&s1).map_err(|_| RuntimePropHashError::HexDecoding(s1))?;
-		let decoded2 = <[u8; 1]>::from_hex(&s2).map_err(|_| RuntimePropHashError::HexDecoding(s2))?;
+		self.authorize_upgrade(None).map(|authorize_upgrade| authorize_upgrade.hash)
+	}
 
-		let parachain_pallet_id = *decoded1.first().expect("Failure while fecthing the Parachain Pallet ID");
-		let authorize_upgrade_prefix = *decoded2.first().expect("Failure while fecthing the Auhtorize upgrade ID");
+	/// Build the `ParachainSystem::authorize_upgrade` call of the runtime for the chain whose runtime has the
+	/// metadata `chain`, this runtime if not given. The indices and whether the call takes `check_version` come
+	/// from that metadata, `PARACHAIN_PALLET_ID`, `AUTHORIZE_UPGRADE_PREFIX` and `AUTHORIZE_UPGRADE_CHECK_VERSION`
+	/// override them.
+	pub fn authorize_upgrade(&self, chain: Option<&RuntimeMetadata>) -> Result<AuthorizeUpgrade> {
+		let found = self.call_info(chain, PARACHAIN_SYSTEM_PALLET, AUTHORIZE_UPGRADE_CALL);
+
+		let (check_version, signature_source) = match (env::var(AUTHORIZE_UPGRADE_CHECK_VERSION_ENV), &found) {
+			(Ok(var), _) => (Some(var == "true"), SignatureSource::Env),
+			// Upgrades are authorized with the version checks unless told otherwise
+			(Err(_), Some(info)) => {
+				(info.has_argument(CHECK_VERSION_ARGUMENT).then_some(true), SignatureSource::Metadata)
+			}
+			(Err(_), None) => {
+				log::warn!("`{PARACHAIN_SYSTEM_PALLET}::{AUTHORIZE_UPGRADE_CALL}` not found in the metadata and env variable `{AUTHORIZE_UPGRADE_CHECK_VERSION_ENV}` not specified. If your chain is running on Substrate >= 0.9.41, this will most likely yield wrong values for the `parachainSystem::authorizeUpgrade` call hash.");
+				(None, SignatureSource::Default)
+			}
+		};
+
+		let found = found.map(|info| info.prefix);
+		let parachain_pallet_id = match (env_index(PARACHAIN_PALLET_ID_ENV)?, found) {
+			(Some(index), _) => index,
+			(None, Some((index, _))) => index,
+			(None, None) => parse_index(DEFAULT_PARACHAIN_PALLET_ID)?,
+		};
+		let authorize_upgrade_prefix = match (env_index(AUTHORIZE_UPGRADE_PREFIX_ENV)?, found) {
+			(Some(index), _) => index,
+			(None, Some((_, index))) => index,
+			(None, None) => parse_index(DEFAULT_AUTHORIZE_UPGRADE_PREFIX)?,
+		};
+
+		let prefix = (parachain_pallet_id, authorize_upgrade_prefix);
+		let result = get_parachainsystem_authorize_upgrade(prefix, &self.bytes, check_version)?;
+		Ok(AuthorizeUpgrade { hash: format!("0x{}", hex::encode(result)), prefix, check_version, signature_source })
+	}
 
-		let parachainsystem_authorize_upgrade_prefix = (parachain_pallet_id, authorize_upgrade_prefix);
-		let result = get_parachainsystem_authorize_upgrade(
-			parachainsystem_authorize_upgrade_prefix,
-			&self.bytes,
-			check_version,
-		)?;
-		Ok(format!("0x{}", hex::encode(result)))
+	/// Compute the hashes of the calls of the `System` pallet authorizing and applying the upgrade to this runtime,
+	/// for the chain whose runtime has the metadata `chain`, this runtime if not given.
+	/// Only the calls the metadata knows are returned, older runtimes have none of them.
+	pub fn system_upgrade_calls(&self, chain: Option<&RuntimeMetadata>) -> Result<Vec<SystemUpgradeCall>> {
+		SYSTEM_UPGRADE_CALLS
+			.iter()
+			.filter_map(|call| self.call_info(chain, SYSTEM_PALLET, call).map(|info| (call, info.prefix)))
+			.map(|(call, prefix)| {
+				let hash = match *call {
+					APPLY_AUTHORIZED_UPGRADE_CALL => get_system_apply_authorized_upgrade(prefix, &self.bytes)?,
+					_ => get_system_authorize_upgrade(prefix, &self.bytes)?,
+				};
+				Ok(SystemUpgradeCall { call: call.to_string(), prefix, hash: format!("0x{}", hex::encode(hash)) })
+			})
+			.collect()
 	}
 
 	/// Compute the blake2-256 hash of the runtime
@@ -224,6 +539,59 @@ impl WasmTestBed {
 		let result = BlakeTwo256::hash(&self.bytes);
 		Ok(format!("{result:?}"))
 	}
+
+	/// List the host functions the runtime imports
+	pub fn host_functions(&self) -> Result<Vec<HostFunction>> {
+		host_functions(&self.wasm).map_err(|e| WasmTestbedError::Loading(e.to_string()))
+	}
+
+	/// Find out which optional [RuntimeFeature]s appear to be compiled in
+	pub fn features(&self) -> Result<Vec<DetectedFeature>> {
+		detect_features(&self.wasm).map_err(|e| WasmTestbedError::Loading(e.to_string()))
+	}
+
+	/// Validate the wasm and report the [WasmProposal]s it uses
+	pub fn proposals(&self) -> Result<Vec<ProposalUse>> {
+		wasm_proposals(&self.wasm).map_err(|e| WasmTestbedError::Loading(e.to_string()))
+	}
+
+	/// List the host functions of `sp_io`, this is the set the testbed provides to the runtime.
+	pub fn builtin_host_functions() -> Vec<HostFunction> {
+		fn type_name(ty: &ValueType) -> String {
+			match ty {
+				ValueType::I32 => "i32",
+				ValueType::I64 => "i64",
+				ValueType::F32 => "f32",
+				ValueType::F64 => "f64",
+			}
+			.to_string()
+		}
+
+		let mut functions: Vec<HostFunction> = TestbedHostFunctions::host_functions()
+			.iter()
+			.map(|f| {
+				let signature = f.signature();
+				HostFunction::new(
+					f.name(),
+					signature.args.iter().map(type_name).collect(),
+					signature.return_value.as_ref().map(type_name),
+				)
+			})
+			.collect();
+		functions.sort();
+		functions
+	}
+}
+
+/// Read a pallet or call index from the env variable `name`, if it is set
+fn env_index(name: &str) -> Result<Option<u8>> {
+	env::var(name).ok().map(|value| parse_index(&value)).transpose()
+}
+
+/// Parse a pallet or call index given in hex, such as `0x01`
+fn parse_index(value: &str) -> Result<u8> {
+	let hex = value.replacen("0x", "", 1);
+	<[u8; 1]>::from_hex(&hex).map(|[index]| index).map_err(|_| RuntimePropHashError::HexDecoding(hex).into())
 }
 
 #[cfg(test)]
@@ -244,6 +612,97 @@ mod tests {
 	const RUNTIME_V13: &str = "../../data/kusama/V13/9090.wasm";
 	const RUNTIME_V14: &str = "../../data/polkadot/V14/9100.wasm";
 
+	#[cfg(test)]
+	mod logs {
+		use super::*;
+		use log::{Level, LevelFilter};
+
+		// (module
+		//   (import "env" "ext_logging_log_version_1" (func (param i32 i64 i64)))
+		//   (memory (export "memory") 1)
+		//   (global (export "__heap_base") i32 (i32.const 1024))
+		//   (func (export "test_log") (param i32 i32) (result i64)
+		//     (call 0 (i32.const 3) (i64.const <"test">) (i64.const <"hello">))
+		//     (i64.const 0))
+		//   (data (i32.const 0) "testhello"))
+		const WASM: &[u8] = &[
+			0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+			0x01, 0x0d, 0x02, 0x60, 0x03, 0x7f, 0x7e, 0x7e, 0x00, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+			0x7e, // type section
+			0x02, 0x21, 0x01, 0x03, b'e', b'n', b'v', 0x19, b'e', b'x', b't', b'_', b'l', b'o', b'g', b'g', b'i', b'n',
+			b'g', b'_', b'l', b'o', b'g', b'_', b'v', b'e', b'r', b's', b'i', b'o', b'n', b'_', b'1', 0x00,
+			0x00, // import section
+			0x03, 0x02, 0x01, 0x01, // function section
+			0x05, 0x03, 0x01, 0x00, 0x01, // memory section
+			0x06, 0x07, 0x01, 0x7f, 0x00, 0x41, 0x80, 0x08, 0x0b, // global section
+			0x07, 0x23, 0x03, 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x0b, b'_', b'_', b'h', b'e', b'a',
+			b'p', b'_', b'b', b'a', b's', b'e', 0x03, 0x00, 0x08, b't', b'e', b's', b't', b'_', b'l', b'o', b'g', 0x00,
+			0x01, // export section
+			0x0a, 0x18, 0x01, 0x16, 0x00, 0x41, 0x03, // code section, i32.const 3
+			0x42, 0x80, 0x80, 0x80, 0x80, 0xc0, 0x00, // i64.const, ptr 0 and len 4
+			0x42, 0x84, 0x80, 0x80, 0x80, 0xd0, 0x00, // i64.const, ptr 4 and len 5
+			0x10, 0x00, 0x42, 0x00, 0x0b, // call 0, i64.const 0
+			0x0b, 0x0f, 0x01, 0x00, 0x41, 0x00, 0x0b, 0x09, b't', b'e', b's', b't', b'h', b'e', b'l', b'l',
+			b'o', // data section
+		];
+
+		fn call_with_config(config: WasmTestBedConfig, method: &str) -> Result<CallResult> {
+			let code_hash = sp_core::blake2_256(WASM).to_vec();
+			let code_fetcher = WrappedRuntimeCode(Cow::Borrowed(WASM));
+			let runtime_code = WasmTestBed::runtime_code(&code_fetcher, &code_hash);
+			let trace = Arc::new(Mutex::new(Vec::new()));
+			let executor = WasmTestBed::executor(&config);
+			let mut ext = BasicExternalities::default();
+			WasmTestBed::call_with(&executor, &config, &runtime_code, method, &[], &trace, &mut ext)
+		}
+
+		fn call(log_level: LevelFilter, method: &str) -> Result<CallResult> {
+			call_with_config(WasmTestBedConfig { log_level, ..Default::default() }, method)
+		}
+
+		#[test]
+		fn it_captures_runtime_logs() {
+			let result = call(LevelFilter::Info, "test_log").expect("Failed calling the runtime");
+			assert_eq!(
+				vec![RuntimeLog { level: Level::Info, target: "test".into(), message: "hello".into() }],
+				result.logs
+			);
+		}
+
+		#[test]
+		fn it_filters_runtime_logs() {
+			let result = call(LevelFilter::Warn, "test_log").expect("Failed calling the runtime");
+			assert!(result.logs.is_empty());
+		}
+
+		#[test]
+		fn it_traces_host_calls() {
+			let config = WasmTestBedConfig { log_level: LevelFilter::Info, trace: true, ..Default::default() };
+			let result = call_with_config(config, "test_log").expect("Failed calling the runtime");
+			assert_eq!(1, result.logs.len());
+			assert_eq!(1, result.trace.len());
+			assert_eq!("ext_logging_log_version_1", result.trace[0].name);
+			assert_eq!(9, result.trace[0].bytes_in);
+
+			let result = call(LevelFilter::Info, "test_log").expect("Failed calling the runtime");
+			assert!(result.trace.is_empty());
+		}
+
+		#[test]
+		fn it_reports_allocator_stats() {
+			let result = call(LevelFilter::Off, "test_log").expect("Failed calling the runtime");
+			assert!(result.allocation.is_some());
+		}
+
+		#[test]
+		fn it_keeps_the_cause_of_errors() {
+			let error = call(LevelFilter::Off, "missing").expect_err("The call should fail");
+			assert!(
+				matches!(error, WasmTestbedError::Calling(ref method, ref cause, _) if method == "missing" && !cause.is_empty())
+			);
+		}
+	}
+
 	#[cfg(test)]
 	mod common {
 		use super::*;
@@ -263,7 +722,7 @@ mod tests {
 		fn it_loads_v12() {
 			let runtime = WasmTestBed::new(&Source::File(PathBuf::from(RUNTIME_V12))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 12);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 12);
 			// assert!(runtime.core_version);
 			assert!(runtime.is_supported());
 		}
@@ -273,7 +732,7 @@ mod tests {
 		fn it_loads_v13() {
 			let runtime = WasmTestBed::new(&Source::File(PathBuf::from(RUNTIME_V13))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 13);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 13);
 			// assert!(runtime.core_version);
 			assert!(runtime.is_supported());
 		}
@@ -283,7 +742,7 @@ mod tests {
 		fn it_loads_v14() {
 			let runtime = WasmTestBed::new(&Source::File(PathBuf::from(RUNTIME_V14))).expect("Failed loading runtime");
 			println!("runtime = {:?}", &runtime);
-			assert!(runtime.metadata_version == 14);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 14);
 			assert!(runtime.is_supported());
 		}
 	}
@@ -299,7 +758,7 @@ mod tests {
 			let runtime =
 				WasmTestBed::new(&Source::File(PathBuf::from(KUSAMA_1050_VXX))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 11);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 11);
 			assert!(runtime.is_supported());
 		}
 
@@ -310,7 +769,7 @@ mod tests {
 			let runtime =
 				WasmTestBed::new(&Source::File(PathBuf::from(KUSAMA_1062_VXX))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 11);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 11);
 			assert!(runtime.is_supported());
 
 			let v = &runtime.core_version;
@@ -328,7 +787,7 @@ mod tests {
 			let runtime =
 				WasmTestBed::new(&Source::File(PathBuf::from(KUSAMA_2030_VXX))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 12);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 12);
 			assert!(runtime.is_supported());
 		}
 
@@ -338,7 +797,7 @@ mod tests {
 			let runtime =
 				WasmTestBed::new(&Source::File(PathBuf::from(KUSAMA_2030_VXX))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 12);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 12);
 			assert!(runtime.is_supported());
 
 			let v = &runtime.core_version;
@@ -362,7 +821,7 @@ mod tests {
 			let runtime =
 				WasmTestBed::new(&Source::File(PathBuf::from(POLKADOT_01_V11))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 11);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 11);
 			assert!(runtime.is_supported());
 		}
 
@@ -374,7 +833,7 @@ mod tests {
 
 			println!("{runtime:#?}");
 
-			assert!(runtime.metadata_version == 12);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 12);
 			assert!(runtime.is_supported());
 		}
 	}
@@ -389,7 +848,7 @@ mod tests {
 			let runtime =
 				WasmTestBed::new(&Source::File(PathBuf::from(WESTEND_V30_V12))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 12);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 12);
 			assert!(runtime.is_supported());
 		}
 	}
@@ -403,7 +862,7 @@ mod tests {
 		fn it_loads_polkadot_dev() {
 			let runtime = WasmTestBed::new(&Source::File(PathBuf::from(POLKADOT_DEV))).expect("Failed loading runtime");
 			println!("{runtime:#?}");
-			assert!(runtime.metadata_version == 12);
+			assert!(runtime.metadata_version().expect("Failed getting metadata version") == 12);
 			assert!(runtime.is_supported());
 		}
 	}
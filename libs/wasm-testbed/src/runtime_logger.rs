@@ -0,0 +1,234 @@
+use log::{Level, LevelFilter};
+use sp_externalities::{decl_extension, ExternalitiesExt};
+use sp_wasm_interface::{
+	anyhow, wasmtime::Caller, Function, FunctionContext, HostFunctionRegistry, HostFunctions, Pointer, Signature,
+	Value, ValueType,
+};
+use std::{
+	collections::VecDeque,
+	fmt::Display,
+	sync::{Arc, Mutex},
+};
+
+/// Number of logs kept across calls, until they are taken
+pub const MAX_KEPT_LOGS: usize = 10_000;
+
+/// A message logged by the runtime through `ext_logging_log_version_1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeLog {
+	pub level: Level,
+	pub target: String,
+	pub message: String,
+}
+
+impl Display for RuntimeLog {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:<5} {}: {}", self.level, self.target, self.message)
+	}
+}
+
+/// The logs of the calls made so far, until they are taken. Past [MAX_KEPT_LOGS], the oldest ones are dropped
+/// so that calling a runtime many times, as `bench` or `smoke` do, does not use more and more memory.
+#[derive(Debug, Default)]
+pub(crate) struct KeptLogs {
+	logs: VecDeque<RuntimeLog>,
+	dropped: usize,
+}
+
+impl KeptLogs {
+	pub fn new(logs: Vec<RuntimeLog>) -> Self {
+		let mut kept = Self::default();
+		kept.extend(&logs);
+		kept
+	}
+
+	pub fn extend(&mut self, logs: &[RuntimeLog]) {
+		self.logs.extend(logs.iter().cloned());
+		let excess = self.logs.len().saturating_sub(MAX_KEPT_LOGS);
+		self.logs.drain(..excess);
+		self.dropped += excess;
+	}
+
+	/// Take the logs kept so far, leaving none
+	pub fn take(&mut self) -> Vec<RuntimeLog> {
+		if self.dropped > 0 {
+			log::warn!("{} older runtime logs were dropped, only the last {MAX_KEPT_LOGS} are kept", self.dropped);
+			self.dropped = 0;
+		}
+		std::mem::take(&mut self.logs).into()
+	}
+}
+
+/// Logs captured during a call, up to `max_level`
+#[derive(Debug)]
+pub struct LogCollector {
+	pub max_level: LevelFilter,
+	pub logs: Vec<RuntimeLog>,
+}
+
+decl_extension! {
+	/// Extension registered in the externalities of a call to capture the runtime logs
+	pub struct RuntimeLogs(Arc<Mutex<LogCollector>>);
+}
+
+impl RuntimeLogs {
+	pub fn new(max_level: LevelFilter) -> Self {
+		Self(Arc::new(Mutex::new(LogCollector { max_level, logs: Vec::new() })))
+	}
+}
+
+/// Run `f` on the [LogCollector] of the current call, if any
+fn with_collector<R>(f: impl FnOnce(&mut LogCollector) -> R) -> Option<R> {
+	sp_externalities::with_externalities(|mut ext| {
+		ext.extension::<RuntimeLogs>().map(|logs| logs.lock().ok().map(|mut collector| f(&mut collector)))
+	})
+	.flatten()
+	.flatten()
+}
+
+/// Read a `&str` or `&[u8]` passed by the runtime as a pointer and a length packed in a `u64`
+fn read_ptr_len(context: &mut dyn FunctionContext, ptr_len: u64) -> sp_wasm_interface::Result<Vec<u8>> {
+	let ptr = ptr_len as u32;
+	let len = (ptr_len >> 32) as u32;
+	context.read_memory(Pointer::new(ptr), len)
+}
+
+fn level_from_u32(level: u32) -> Level {
+	match level {
+		1 => Level::Error,
+		2 => Level::Warn,
+		3 => Level::Info,
+		4 => Level::Debug,
+		_ => Level::Trace,
+	}
+}
+
+/// Host implementation of `ext_logging_log_version_1` that captures the logs of the runtime.
+/// The logs are also forwarded to the `log` crate as `sp_io` does.
+struct LogFunction;
+
+impl LogFunction {
+	fn call(context: &mut dyn FunctionContext, level: u32, target: u64, message: u64) -> sp_wasm_interface::Result<()> {
+		let target = String::from_utf8_lossy(&read_ptr_len(context, target)?).to_string();
+		let message = String::from_utf8_lossy(&read_ptr_len(context, message)?).to_string();
+		let level = level_from_u32(level);
+
+		log::log!(target: &target, level, "{message}");
+		with_collector(|collector| {
+			if level <= collector.max_level {
+				collector.logs.push(RuntimeLog { level, target, message });
+			}
+		});
+		Ok(())
+	}
+}
+
+impl Function for LogFunction {
+	fn name(&self) -> &str {
+		"ext_logging_log_version_1"
+	}
+
+	fn signature(&self) -> Signature {
+		Signature::new(vec![ValueType::I32, ValueType::I64, ValueType::I64], None)
+	}
+
+	fn execute(
+		&self,
+		context: &mut dyn FunctionContext,
+		args: &mut dyn Iterator<Item = Value>,
+	) -> sp_wasm_interface::Result<Option<Value>> {
+		match (args.next(), args.next(), args.next()) {
+			(Some(Value::I32(level)), Some(Value::I64(target)), Some(Value::I64(message))) => {
+				Self::call(context, level as u32, target as u64, message as u64).map(|_| None)
+			}
+			_ => Err(format!("Invalid arguments for {}", self.name())),
+		}
+	}
+}
+
+/// Host implementation of `ext_logging_max_level_version_1`. It tells the runtime the most
+/// verbose level we are interested in, so that it does not format messages nobody reads.
+struct MaxLevelFunction;
+
+impl MaxLevelFunction {
+	fn call() -> u32 {
+		let captured = with_collector(|collector| collector.max_level).unwrap_or(LevelFilter::Off);
+		captured.max(log::max_level()) as u32
+	}
+}
+
+impl Function for MaxLevelFunction {
+	fn name(&self) -> &str {
+		"ext_logging_max_level_version_1"
+	}
+
+	fn signature(&self) -> Signature {
+		Signature::new(vec![], Some(ValueType::I32))
+	}
+
+	fn execute(
+		&self,
+		_context: &mut dyn FunctionContext,
+		_args: &mut dyn Iterator<Item = Value>,
+	) -> sp_wasm_interface::Result<Option<Value>> {
+		Ok(Some(Value::I32(Self::call() as i32)))
+	}
+}
+
+/// The logging host functions of the testbed. They replace the ones of `sp_io` to capture the
+/// runtime logs in the [RuntimeLogs] extension.
+pub struct RuntimeLogging;
+
+impl HostFunctions for RuntimeLogging {
+	fn host_functions() -> Vec<&'static dyn Function> {
+		vec![&LogFunction, &MaxLevelFunction]
+	}
+
+	fn register_static<T>(registry: &mut T) -> core::result::Result<(), T::Error>
+	where
+		T: HostFunctionRegistry,
+	{
+		registry.register_static(
+			LogFunction.name(),
+			|caller: Caller<T::State>, level: u32, target: u64, message: u64| -> anyhow::Result<()> {
+				T::with_function_context(caller, |context| {
+					LogFunction::call(context, level, target, message).map_err(anyhow::Error::msg)
+				})
+			},
+		)?;
+		registry.register_static(MaxLevelFunction.name(), |_caller: Caller<T::State>| -> anyhow::Result<u32> {
+			Ok(MaxLevelFunction::call())
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests_runtime_logger {
+	use super::*;
+
+	#[test]
+	fn it_converts_levels() {
+		assert_eq!(Level::Error, level_from_u32(1));
+		assert_eq!(Level::Debug, level_from_u32(4));
+		assert_eq!(LevelFilter::Info as u32, 3);
+	}
+
+	#[test]
+	fn it_drops_the_oldest_logs() {
+		let log =
+			|message: usize| RuntimeLog { level: Level::Info, target: "test".into(), message: message.to_string() };
+		let mut kept = KeptLogs::new(vec![log(0)]);
+		kept.extend(&(1..=MAX_KEPT_LOGS).map(log).collect::<Vec<_>>());
+
+		let logs = kept.take();
+		assert_eq!(MAX_KEPT_LOGS, logs.len());
+		assert_eq!(Some(&log(1)), logs.first());
+		assert!(kept.take().is_empty());
+	}
+
+	#[test]
+	fn it_shows_logs() {
+		let log = RuntimeLog { level: Level::Warn, target: "runtime::system".into(), message: "hello".into() };
+		assert_eq!("WARN  runtime::system: hello", log.to_string());
+	}
+}
@@ -0,0 +1,260 @@
+use scale::{Compact, Decode};
+use serde_json::Value;
+use sp_core::{
+	storage::{well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX, ChildInfo, StateVersion, Storage, StorageChild},
+	H256,
+};
+use sp_runtime::traits::BlakeTwo256;
+use sp_state_machine::{backend::IterArgs, Backend, BasicExternalities, TestExternalities};
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path};
+
+use crate::error::*;
+
+/// Version of the try-runtime snapshots we can read
+const TRY_RUNTIME_SNAPSHOT_VERSION: u16 = 4;
+
+/// The formats a state snapshot can be loaded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateFormat {
+	/// Json array of `[key, value]` pairs, as returned by the `state_getPairs` rpc,
+	/// or a json object of `key: value`
+	Pairs,
+
+	/// Raw chain spec, the state being its genesis storage
+	ChainSpec,
+
+	/// Text with one `key value` pair per line, both in hex
+	HexDump,
+
+	/// Snapshot created by `try-runtime create-snapshot`
+	TryRuntime,
+}
+
+impl Display for StateFormat {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Pairs => write!(f, "key/value pairs"),
+			Self::ChainSpec => write!(f, "raw chain spec"),
+			Self::HexDump => write!(f, "hex dump"),
+			Self::TryRuntime => write!(f, "try-runtime snapshot"),
+		}
+	}
+}
+
+/// Storage of a chain the runtime calls can run against, instead of an empty one
+#[derive(Debug, Clone)]
+pub struct StateSnapshot {
+	pub storage: Storage,
+	pub format: StateFormat,
+}
+
+impl StateSnapshot {
+	/// Load a snapshot from a file, guessing its [StateFormat]
+	pub fn load(path: &Path) -> Result<Self> {
+		let bytes = fs::read(path).map_err(|e| WasmTestbedError::State(format!("{}: {e}", path.display())))?;
+		Self::from_bytes(&bytes)
+	}
+
+	/// Load a snapshot from the content of a file, guessing its [StateFormat]
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+		if let Ok(json) = serde_json::from_slice::<Value>(bytes) {
+			return Self::from_json(json);
+		}
+		let try_runtime =
+			|| Self::from_try_runtime(bytes).map(|storage| Self { storage, format: StateFormat::TryRuntime });
+		match std::str::from_utf8(bytes) {
+			// A binary snapshot may happen to be valid utf8
+			Ok(text) => Self::from_hex_dump(text)
+				.map(|storage| Self { storage, format: StateFormat::HexDump })
+				.or_else(|e| try_runtime().map_err(|_| e)),
+			Err(_) => try_runtime(),
+		}
+	}
+
+	/// Number of keys in the top trie and the child tries
+	pub fn len(&self) -> usize {
+		self.storage.top.len() + self.storage.children_default.values().map(|c| c.data.len()).sum::<usize>()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Externalities holding a copy of the storage, so that calls do not change the snapshot
+	pub fn externalities(&self) -> BasicExternalities {
+		BasicExternalities::new(self.storage.clone())
+	}
+
+	fn from_json(json: Value) -> Result<Self> {
+		match json {
+			// A whole rpc response
+			Value::Object(mut object) if object.contains_key("jsonrpc") => match object.remove("result") {
+				Some(result) => Self::from_json(result),
+				None => Err(WasmTestbedError::State("rpc response without result".to_string())),
+			},
+			Value::Object(object) if object.contains_key("genesis") => {
+				let raw = &object["genesis"]["raw"];
+				if !raw.is_object() {
+					return Err(WasmTestbedError::State("the chain spec is not raw".to_string()));
+				}
+				let mut storage = Storage { top: Self::map(&raw["top"])?, ..Default::default() };
+				if let Some(children) = raw["childrenDefault"].as_object() {
+					for (key, child) in children {
+						let child_info = ChildInfo::new_default(&decode_hex(key)?);
+						let data = Self::map(child)?;
+						storage
+							.children_default
+							.insert(child_info.storage_key().to_vec(), StorageChild { data, child_info });
+					}
+				}
+				Ok(Self { storage, format: StateFormat::ChainSpec })
+			}
+			Value::Object(_) => Ok(Self { storage: Self::storage(Self::map(&json)?), format: StateFormat::Pairs }),
+			Value::Array(pairs) => {
+				let top = pairs
+					.iter()
+					.map(|pair| match pair.as_array().map(|p| p.as_slice()) {
+						Some([Value::String(key), Value::String(value)]) => Ok((decode_hex(key)?, decode_hex(value)?)),
+						_ => Err(WasmTestbedError::State(format!("invalid key/value pair: {pair}"))),
+					})
+					.collect::<Result<_>>()?;
+				Ok(Self { storage: Self::storage(top), format: StateFormat::Pairs })
+			}
+			_ => Err(WasmTestbedError::State("unknown json format".to_string())),
+		}
+	}
+
+	/// Read a json object of hex keys and values
+	fn map(value: &Value) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+		let object = value.as_object().ok_or_else(|| WasmTestbedError::State(format!("not an object: {value}")))?;
+		object
+			.iter()
+			.map(|(key, value)| match value {
+				Value::String(value) => Ok((decode_hex(key)?, decode_hex(value)?)),
+				_ => Err(WasmTestbedError::State(format!("invalid value for {key}: {value}"))),
+			})
+			.collect()
+	}
+
+	fn from_hex_dump(text: &str) -> Result<Storage> {
+		let top = text
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.map(|line| {
+				let mut parts =
+					line.split(|c: char| c.is_whitespace() || c == ':' || c == '=').filter(|p| !p.is_empty());
+				match (parts.next(), parts.next(), parts.next()) {
+					(Some(key), Some(value), None) => Ok((decode_hex(key)?, decode_hex(value)?)),
+					_ => Err(WasmTestbedError::State(format!("invalid line: {line}"))),
+				}
+			})
+			.collect::<Result<_>>()?;
+		Ok(Self::storage(top))
+	}
+
+	/// Split the child tries out of the key/value pairs, the way the node stores them
+	fn storage(top: BTreeMap<Vec<u8>, Vec<u8>>) -> Storage {
+		let (children, top): (BTreeMap<_, _>, BTreeMap<_, _>) =
+			top.into_iter().partition(|(key, _)| key.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX));
+		if !children.is_empty() {
+			log::warn!(
+				"Ignoring {} child trie roots, the content of child tries cannot be loaded from pairs",
+				children.len()
+			);
+		}
+		Storage { top, ..Default::default() }
+	}
+
+	/// Rebuild the storage from the trie nodes of a try-runtime snapshot
+	fn from_try_runtime(bytes: &[u8]) -> Result<Storage> {
+		let input = &mut &bytes[..];
+		let map_err = |e: scale::Error| WasmTestbedError::State(format!("invalid try-runtime snapshot: {e}"));
+
+		let version = Compact::<u16>::decode(input).map_err(map_err)?.0;
+		if version != TRY_RUNTIME_SNAPSHOT_VERSION {
+			return Err(WasmTestbedError::State(format!("unsupported try-runtime snapshot version {version}")));
+		}
+		let state_version = StateVersion::decode(input).map_err(map_err)?;
+		let _block_hash = H256::decode(input).map_err(map_err)?;
+		let raw_storage = <Vec<(Vec<u8>, (Vec<u8>, i32))>>::decode(input).map_err(map_err)?;
+		let storage_root = H256::decode(input).map_err(map_err)?;
+		// The header of the block follows, we do not need it
+
+		let backend =
+			TestExternalities::<BlakeTwo256>::from_raw_snapshot(raw_storage, storage_root, state_version).as_backend();
+		let pairs = |child_info: Option<ChildInfo>| -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+			let mut args = IterArgs::default();
+			args.child_info = child_info;
+			backend.pairs(args).and_then(|pairs| pairs.collect()).map_err(WasmTestbedError::State)
+		};
+
+		let mut storage = Storage { top: pairs(None)?, ..Default::default() };
+		let child_keys: Vec<Vec<u8>> =
+			storage.top.keys().filter(|key| key.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX)).cloned().collect();
+		for key in child_keys {
+			storage.top.remove(&key);
+			let child_info = ChildInfo::new_default(&key[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..]);
+			let data = pairs(Some(child_info.clone()))?;
+			storage.children_default.insert(child_info.storage_key().to_vec(), StorageChild { data, child_info });
+		}
+		Ok(storage)
+	}
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+	hex::decode(s.trim_start_matches("0x")).map_err(|e| WasmTestbedError::State(format!("invalid hex {s}: {e}")))
+}
+
+#[cfg(test)]
+mod tests_state {
+	use super::*;
+	use scale::Encode;
+
+	#[test]
+	fn it_loads_pairs() {
+		let snapshot =
+			StateSnapshot::from_bytes(br#"{"jsonrpc":"2.0","result":[["0x01","0x02"],["0x03","0x"]],"id":1}"#)
+				.expect("Failed loading state");
+		assert_eq!(StateFormat::Pairs, snapshot.format);
+		assert_eq!(BTreeMap::from([(vec![1], vec![2]), (vec![3], vec![])]), snapshot.storage.top);
+	}
+
+	#[test]
+	fn it_loads_chain_specs() {
+		let spec = br#"{"name":"dev","genesis":{"raw":{"top":{"0x3a636f6465":"0x0061"},"childrenDefault":{"0x0a":{"0x01":"0x02"}}}}}"#;
+		let snapshot = StateSnapshot::from_bytes(spec).expect("Failed loading state");
+		assert_eq!(StateFormat::ChainSpec, snapshot.format);
+		assert_eq!(Some(&vec![0x00, 0x61]), snapshot.storage.top.get(b":code".as_slice()));
+		assert_eq!(2, snapshot.len());
+
+		assert!(StateSnapshot::from_bytes(br#"{"genesis":{"runtimeGenesis":{}}}"#).is_err());
+	}
+
+	#[test]
+	fn it_loads_hex_dumps() {
+		let snapshot = StateSnapshot::from_bytes(b"# comment\n0x01 0x02\n03: 04\n").expect("Failed loading state");
+		assert_eq!(StateFormat::HexDump, snapshot.format);
+		assert_eq!(BTreeMap::from([(vec![1], vec![2]), (vec![3], vec![4])]), snapshot.storage.top);
+		assert!(StateSnapshot::from_bytes(b"0x01\n").is_err());
+	}
+
+	#[test]
+	fn it_loads_try_runtime_snapshots() {
+		let mut ext = TestExternalities::<BlakeTwo256>::new_empty();
+		ext.insert(vec![1], vec![2]);
+		ext.insert_child(ChildInfo::new_default(b"child"), vec![3], vec![4]);
+		ext.commit_all().expect("Failed committing");
+		let (raw_storage, root) = ext.into_raw_snapshot();
+
+		let mut bytes = Compact(TRY_RUNTIME_SNAPSHOT_VERSION).encode();
+		bytes.extend((StateVersion::V1, H256::zero(), raw_storage, root).encode());
+		// Some header bytes that we ignore
+		bytes.extend([0xff, 0xfe]);
+
+		let snapshot = StateSnapshot::from_bytes(&bytes).expect("Failed loading state");
+		assert_eq!(StateFormat::TryRuntime, snapshot.format);
+		assert_eq!(Some(&vec![2]), snapshot.storage.top.get(&vec![1]));
+		assert_eq!(BTreeMap::from([(vec![3], vec![4])]), snapshot.storage.children_default[&b"child".to_vec()].data);
+	}
+}